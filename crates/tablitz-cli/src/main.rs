@@ -12,10 +12,12 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Recover OneTab data from a browser LevelDB store
+    /// Recover OneTab data from a browser store (Chromium LevelDB or Firefox IndexedDB)
     Recover {
-        #[arg(long, value_enum, default_value = "chrome")]
-        browser: BrowserArg,
+        /// Browser id from the registry: chrome, edge, brave, comet, chromium,
+        /// vivaldi, opera, arc, firefox, or one added in config.toml
+        #[arg(long, default_value = "chrome")]
+        browser: String,
         #[arg(long, default_value = "Default")]
         profile: String,
         #[arg(long, value_enum, default_value = "json")]
@@ -26,6 +28,45 @@ enum Commands {
         dry_run: bool,
         #[arg(long)]
         db_path: Option<PathBuf>,
+        /// Parse the raw .ldb/.log files instead of opening the database, for
+        /// corrupted stores; also recovers stale and overwritten group versions
+        #[arg(long)]
+        salvage: bool,
+        /// List every version of OneTab's state still on disk, then exit
+        #[arg(long)]
+        list_versions: bool,
+        /// Recover one version by sequence number (from --list-versions), or
+        /// `all` for the union of every version
+        #[arg(long, value_name = "SEQ|all", value_parser = parse_version_arg)]
+        version: Option<VersionArg>,
+        /// Recover from every browser profile with OneTab data
+        #[arg(long, conflicts_with_all = ["db_path", "out", "list_versions", "version"])]
+        all: bool,
+        /// Bring groups already in the store in step with the browser: add,
+        /// remove, reorder and retitle their tabs, and copy labels and flags
+        #[arg(long, conflicts_with = "out")]
+        update: bool,
+    },
+    /// Write tabs from the store back into a browser's OneTab store
+    ///
+    /// Replaces OneTab's saved groups with the store's (or those matching
+    /// QUERY). The browser must be closed; the original LevelDB directory is
+    /// kept beside it as a backup.
+    WriteOnetab {
+        /// Only write tabs matching this query (see `tablitz search --help`)
+        query: Option<String>,
+        #[arg(long, default_value = "chrome")]
+        browser: String,
+        #[arg(long, default_value = "Default")]
+        profile: String,
+        /// Write to this OneTab LevelDB directory instead of the browser's
+        #[arg(long)]
+        db_path: Option<PathBuf>,
+        /// Only write groups and tabs carrying this tag
+        #[arg(long)]
+        tag: Option<String>,
+        #[arg(long)]
+        dry_run: bool,
     },
     /// Import tab data into the tablitz store
     Import {
@@ -33,50 +74,175 @@ enum Commands {
         from_onetab_export: Option<PathBuf>,
         #[arg(long)]
         from_onetab_leveldb: Option<PathBuf>,
-        #[arg(long, value_enum)]
-        browser: Option<BrowserArg>,
+        /// A Firefox IndexedDB `.sqlite` file or `idb` directory holding OneTab's storage
+        #[arg(long)]
+        from_firefox_idb: Option<PathBuf>,
+        /// A Netscape `bookmarks.html` file (browser, Pocket or Raindrop export); folders become groups
+        #[arg(long)]
+        from_bookmarks_html: Option<PathBuf>,
+        /// A Chromium `Sessions/Session_*` or `Tabs_*` file, or a profile directory; each window becomes a group
+        #[arg(long)]
+        from_chromium_session: Option<PathBuf>,
+        /// A Firefox `sessionstore.jsonlz4` / `recovery.jsonlz4` file, or a profile directory
+        #[arg(long)]
+        from_firefox_session: Option<PathBuf>,
+        /// Browser id from the registry, recorded as the import's source
+        #[arg(long)]
+        browser: Option<String>,
         #[arg(long)]
         profile: Option<String>,
+        /// Timezone of markdown `Created` times: `local`, `utc` or an offset like `+02:00`
+        #[arg(long, default_value = "local")]
+        timezone: tablitz_recover::Timezone,
+        /// A tablitz JSON or TOML file: a session from `recover --out`, or groups from `export`
         #[arg(long)]
         from_tablitz: Option<PathBuf>,
+        /// Keep stored groups that differ from the file's copy (the default)
+        #[arg(long, requires = "from_tablitz", conflicts_with = "replace")]
+        merge: bool,
+        /// Overwrite stored groups that differ from the file's copy
+        #[arg(long, requires = "from_tablitz")]
+        replace: bool,
+        /// Update stored groups that differ from the imported copy in place,
+        /// keeping their tags and history
+        #[arg(long, conflicts_with_all = ["merge", "replace"])]
+        update: bool,
+    },
+    /// Record visit counts, last-visit times and bookmarks from browser history
+    ///
+    /// Reads Chromium `History` and `Bookmarks` or Firefox `places.sqlite`
+    /// (from copies, so the browser can stay open) and matches them to stored
+    /// tabs by normalized URL. Filter and sort on the result with `visits:`,
+    /// `bookmarked:`, `visited-after:` and `--sort`.
+    Enrich {
+        /// Browser ids to read; defaults to every browser that has the profile
+        #[arg(long)]
+        browser: Vec<String>,
+        #[arg(long, default_value = "Default")]
+        profile: String,
+        /// A Chromium `History` database to read instead of a browser profile
+        #[arg(long)]
+        chromium_history: Vec<PathBuf>,
+        /// A Chromium `Bookmarks` JSON file to read instead of a browser profile
+        #[arg(long)]
+        chromium_bookmarks: Vec<PathBuf>,
+        /// A Firefox `places.sqlite` database to read instead of a browser profile
+        #[arg(long)]
+        firefox_places: Vec<PathBuf>,
+        #[arg(long)]
+        dry_run: bool,
     },
     /// Export tab data from the store
     Export {
+        /// Only export tabs matching this query (see `tablitz search --help`)
+        query: Option<String>,
         #[arg(long, value_enum, default_value = "markdown")]
         format: ExportFormat,
         #[arg(long, short)]
         out: Option<PathBuf>,
+        /// Only export groups whose label contains this text (same as `label:`)
         #[arg(long)]
         filter: Option<String>,
+        /// Only export groups and tabs carrying this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Timezone to write markdown `Created` times in: `local`, `utc` or an offset like `+02:00`
+        #[arg(long, default_value = "local")]
+        timezone: tablitz_recover::Timezone,
     },
     /// Search the tablitz store
+    ///
+    /// The query combines free text with filters: `domain:`, `url:`, `title:`,
+    /// `label:`, `tag:`, `starred:` / `pinned:` / `locked:` (true or false),
+    /// `after:` / `before:` (YYYY-MM-DD), `bookmarked:`, `visits:` (at least N),
+    /// `visited-after:` / `visited-before:`, `"quoted phrases"`, `OR`, `-term`
+    /// and parentheses. Free text is ranked by the search mode; the rest filters.
     Search {
         query: String,
         #[arg(long, value_enum, default_value = "fuzzy")]
         mode: SearchMode,
         #[arg(long, default_value = "20")]
         limit: usize,
+        /// Only return tabs carrying this tag, directly or through their group
+        #[arg(long)]
+        tag: Option<String>,
+        /// Order results by this instead of by rank
+        #[arg(long, value_enum)]
+        sort: Option<SortKey>,
     },
     /// List tab groups
     List {
+        /// Only list tabs matching this query (see `tablitz search --help`)
+        query: Option<String>,
+        /// Only list groups whose label contains this text (same as `label:`)
         #[arg(long)]
         filter: Option<String>,
         #[arg(long, default_value = "50")]
         limit: usize,
+        /// Only list groups and tabs carrying this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Order groups by this instead of store order
+        #[arg(long, value_enum)]
+        sort: Option<SortKey>,
     },
     /// Deduplicate and normalize tab data
+    ///
+    /// Every run is recorded, with the tabs it removed and what they were
+    /// merged into, so it can be undone.
     Dedup {
         #[arg(long, value_enum, default_value = "normalized-url")]
         strategy: DedupStrategyArg,
+        /// How similar two URLs (`fuzzy-url`, default 0.9) or titles
+        /// (`near-duplicate`, default 0.8) must be, from 0.0 to 1.0
+        #[arg(long, value_parser = parse_threshold)]
+        threshold: Option<f32>,
         #[arg(long)]
         normalize_titles: bool,
         #[arg(long)]
         dry_run: bool,
+        /// Accept or reject each cluster of `near-duplicate` matches
+        #[arg(long, conflicts_with = "dry_run")]
+        review: bool,
+        /// Put back the tabs a dedup run removed or changed
+        #[arg(
+            long,
+            value_name = "RUN",
+            conflicts_with_all = ["threshold", "normalize_titles", "dry_run", "review", "history"]
+        )]
+        undo: Option<i64>,
+        /// List past dedup runs
+        #[arg(long, conflicts_with_all = ["threshold", "normalize_titles", "dry_run", "review"])]
+        history: bool,
+    },
+    /// Clean up stored tab titles
+    ///
+    /// Strips notification counters like "(3) " and site names like
+    /// " - YouTube", plus whatever the `[titles]` rules in config.toml say.
+    /// Check the changes with `--preview` before saving them.
+    Normalize {
+        /// Only normalize tabs matching this query (see `tablitz search --help`)
+        query: Option<String>,
+        /// Show each title before and after, without saving
+        #[arg(long)]
+        preview: bool,
     },
     /// Initialize tablitz (create config directory)
     Init,
     /// Show store statistics
     Stats,
+    /// Rebuild the persistent full-text index from the store (requires --features full-text)
+    Reindex,
+    /// Add, remove, or list tags on tabs and groups
+    Tag {
+        #[command(subcommand)]
+        command: TagCommand,
+    },
+    /// Inspect and upgrade the store's database schema
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
     /// Start MCP server (requires --features mcp)
     Serve {
         #[arg(long, default_value = "0")]
@@ -107,12 +273,76 @@ enum Commands {
     },
 }
 
-#[derive(ValueEnum, Clone, Debug)]
-enum BrowserArg {
-    Chrome,
-    Edge,
-    Brave,
-    Comet,
+#[derive(Subcommand)]
+enum DbCommand {
+    /// Show which schema migrations are applied and which are pending
+    Status {
+        #[arg(long)]
+        db_path: Option<PathBuf>,
+    },
+    /// Apply pending schema migrations
+    Migrate {
+        #[arg(long)]
+        dry_run: bool,
+        #[arg(long)]
+        db_path: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TagCommand {
+    /// Tag a tab or group
+    Add {
+        #[arg(required = true)]
+        tags: Vec<String>,
+        #[command(flatten)]
+        target: TagTargetArgs,
+    },
+    /// Remove tags from a tab or group
+    Remove {
+        #[arg(required = true)]
+        tags: Vec<String>,
+        #[command(flatten)]
+        target: TagTargetArgs,
+    },
+    /// List all tags with their tab and group counts
+    List,
+}
+
+#[derive(clap::Args)]
+#[group(required = true, multiple = false)]
+struct TagTargetArgs {
+    /// Tab id (or a unique prefix of it)
+    #[arg(long)]
+    tab: Option<String>,
+    /// Group id (or a unique prefix of it)
+    #[arg(long)]
+    group: Option<String>,
+}
+
+impl TagTargetArgs {
+    fn into_target(self) -> tablitz_store::TagTarget {
+        match (self.tab, self.group) {
+            (Some(tab), _) => tablitz_store::TagTarget::Tab(tab),
+            (None, Some(group)) => tablitz_store::TagTarget::Group(group),
+            (None, None) => unreachable!("clap requires --tab or --group"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum VersionArg {
+    Sequence(u64),
+    All,
+}
+
+fn parse_version_arg(s: &str) -> Result<VersionArg, String> {
+    if s.eq_ignore_ascii_case("all") {
+        return Ok(VersionArg::All);
+    }
+    s.parse()
+        .map(VersionArg::Sequence)
+        .map_err(|_| format!("expected a sequence number or `all`, got '{}'", s))
 }
 
 #[derive(ValueEnum, Clone, Debug)]
@@ -128,6 +358,10 @@ enum ExportFormat {
     Json,
     Markdown,
     Toml,
+    /// OneTab's `url | title` format, for its "Import URLs" box
+    Pipe,
+    /// Netscape `bookmarks.html`, one folder per group
+    BookmarksHtml,
 }
 
 #[derive(ValueEnum, Clone, Debug)]
@@ -136,19 +370,32 @@ enum SearchMode {
     FullText,
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum SortKey {
+    /// Newest first by when the tab (or group) was added
+    Added,
+    /// Most recently visited first, from `tablitz enrich`
+    LastVisited,
+    /// Most visited first, from `tablitz enrich`
+    Visits,
+}
+
 #[derive(ValueEnum, Clone, Debug)]
 enum DedupStrategyArg {
     ExactUrl,
     NormalizedUrl,
+    /// Near-identical URLs on the same host and first path segment
+    FuzzyUrl,
     UrlAndTitle,
+    /// The same page under another URL (AMP, mobile, short links, arXiv
+    /// PDFs), or a similar title
+    NearDuplicate,
 }
 
-fn browser_arg_to_recover(b: &BrowserArg) -> tablitz_recover::Browser {
-    match b {
-        BrowserArg::Chrome => tablitz_recover::Browser::Chrome,
-        BrowserArg::Edge => tablitz_recover::Browser::Edge,
-        BrowserArg::Brave => tablitz_recover::Browser::Brave,
-        BrowserArg::Comet => tablitz_recover::Browser::Comet,
+fn parse_threshold(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(t) if (0.0..=1.0).contains(&t) => Ok(t),
+        _ => Err(format!("expected a number from 0.0 to 1.0, got '{}'", s)),
     }
 }
 
@@ -158,55 +405,183 @@ fn main() -> Result<()> {
     rt.block_on(run(cli))
 }
 
+/// Compiles the title rules from the `[titles]` table of tablitz's config
+/// file, or the defaults if it has none.
+fn load_title_pipeline() -> Result<tablitz_search::TitlePipeline> {
+    use anyhow::Context;
+
+    #[derive(serde::Deserialize)]
+    struct TitlesFile {
+        #[serde(default)]
+        titles: tablitz_search::TitleRules,
+    }
+    let Some(path) = tablitz_recover::default_config_path().filter(|p| p.is_file()) else {
+        return Ok(Default::default());
+    };
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let file: TitlesFile = toml::from_str(&content)
+        .with_context(|| format!("Invalid [titles] rules in {}", path.display()))?;
+    tablitz_search::TitlePipeline::new(&file.titles)
+        .with_context(|| format!("Invalid [titles] rules in {}", path.display()))
+}
+
+/// The canonicalization and title rules from tablitz's config file, passed
+/// to the commands that use them.
+struct Rules {
+    canonical: tablitz_core::CanonicalRules,
+    titles: tablitz_search::TitlePipeline,
+}
+
+impl Rules {
+    /// Loads the canonicalization and title rules from tablitz's config file.
+    ///
+    /// Commands that canonicalize or normalize stored tabs fail on invalid
+    /// rules; the others warn and keep the built-in defaults, so a typo in the
+    /// config file doesn't lock the user out of reading their tabs.
+    fn load(strict: bool) -> Result<Self> {
+        let warn = |e: anyhow::Error| -> Result<()> {
+            if strict {
+                return Err(e);
+            }
+            eprintln!("{} using the default rules: {:#}", "warning:".yellow(), e);
+            Ok(())
+        };
+        let canonical = match tablitz_recover::load_canonical_rules() {
+            Ok(rules) => rules,
+            Err(e) => {
+                warn(e)?;
+                Default::default()
+            }
+        };
+        let titles = match load_title_pipeline() {
+            Ok(titles) => titles,
+            Err(e) => {
+                warn(e)?;
+                Default::default()
+            }
+        };
+        Ok(Self { canonical, titles })
+    }
+}
+
 async fn run(cli: Cli) -> Result<()> {
+    let canonicalizes = matches!(
+        cli.command,
+        Commands::Recover { .. }
+            | Commands::Import { .. }
+            | Commands::Enrich { .. }
+            | Commands::Dedup { .. }
+            | Commands::Normalize { .. }
+            | Commands::Restore { .. }
+            | Commands::Serve { .. }
+            | Commands::Db { command: DbCommand::Migrate { .. } }
+    );
+    let rules = Rules::load(canonicalizes)?;
     match cli.command {
-        Commands::Recover { browser, profile, dry_run, db_path, out, format } => {
-            cmd_recover(browser, profile, dry_run, db_path, out, format).await
+        Commands::Recover { all: true, dry_run, salvage, update, .. } => cmd_recover_all(&rules, dry_run, salvage, update).await,
+        Commands::Recover { browser, profile, dry_run, db_path, salvage, list_versions, version, out, format, all: false, update } => {
+            let opts = tablitz_recover::RecoverOptions {
+                browser: tablitz_recover::BrowserRegistry::load()?.browser(&browser)?,
+                profile,
+                dry_run,
+                db_path,
+                salvage: salvage || matches!(version, Some(VersionArg::All)),
+                version: match version {
+                    Some(VersionArg::Sequence(seq)) => Some(seq),
+                    _ => None,
+                },
+            };
+            if list_versions {
+                cmd_recover_list_versions(&opts)
+            } else {
+                cmd_recover(&rules, opts, out, format, update).await
+            }
+        }
+        Commands::Import { from_onetab_export, from_onetab_leveldb, from_firefox_idb, from_bookmarks_html, from_chromium_session, from_firefox_session, browser, profile, timezone, from_tablitz, merge: _, replace, update } => {
+            let mode = if replace {
+                tablitz_store::ImportMode::Replace
+            } else if update {
+                tablitz_store::ImportMode::Update
+            } else {
+                tablitz_store::ImportMode::Merge
+            };
+            cmd_import(
+                &rules, from_onetab_export, from_onetab_leveldb, from_firefox_idb, from_bookmarks_html,
+                from_chromium_session, from_firefox_session, browser, profile, timezone, from_tablitz, mode,
+            )
+            .await
+        }
+        Commands::WriteOnetab { query, browser, profile, db_path, tag, dry_run } => {
+            let db_path = match db_path {
+                Some(path) => path,
+                None => {
+                    let browser = tablitz_recover::BrowserRegistry::load()?.browser(&browser)?;
+                    tablitz_recover::resolve_leveldb_path(&browser, &profile)?
+                }
+            };
+            cmd_write_onetab(db_path, build_query(query.as_deref(), None, tag)?, dry_run).await
         }
-        Commands::Import { from_onetab_export, from_onetab_leveldb, browser, profile, from_tablitz } => {
-            cmd_import(from_onetab_export, from_onetab_leveldb, browser, profile, from_tablitz).await
+        Commands::Enrich { browser, profile, chromium_history, chromium_bookmarks, firefox_places, dry_run } => {
+            cmd_enrich(&rules, browser, profile, chromium_history, chromium_bookmarks, firefox_places, dry_run).await
         }
-        Commands::Export { format, out, filter } => {
-            cmd_export(format, out, filter).await
+        Commands::Export { query, format, out, filter, tag, timezone } => {
+            cmd_export(format, out, build_query(query.as_deref(), filter, tag)?, timezone).await
         }
-        Commands::Search { query, mode, limit } => {
-            cmd_search(query, mode, limit).await
+        Commands::Search { query, mode, limit, tag, sort } => {
+            let parsed = build_query(Some(&query), None, tag)?;
+            cmd_search(query, parsed, mode, limit, sort).await
         }
-        Commands::List { filter, limit } => {
-            cmd_list(filter, limit).await
+        Commands::List { query, filter, limit, tag, sort } => {
+            cmd_list(build_query(query.as_deref(), filter, tag)?, limit, sort).await
+        }
+        Commands::Dedup { strategy, threshold, normalize_titles, dry_run, review, undo, history } => {
+            if let Some(run_id) = undo {
+                cmd_dedup_undo(&rules, run_id).await
+            } else if history {
+                cmd_dedup_history().await
+            } else {
+                cmd_dedup(&rules, strategy, threshold, normalize_titles, dry_run, review).await
+            }
         }
-        Commands::Dedup { strategy, normalize_titles, dry_run } => {
-            cmd_dedup(strategy, normalize_titles, dry_run).await
+        Commands::Normalize { query, preview } => {
+            cmd_normalize(&rules, build_query(query.as_deref(), None, None)?, preview).await
         }
         Commands::Init => cmd_init().await,
-        Commands::Stats => cmd_stats().await,
-        Commands::Serve { port: _ } => cmd_serve().await,
+        Commands::Stats => cmd_stats(&rules).await,
+        Commands::Reindex => cmd_reindex().await,
+        Commands::Tag { command: TagCommand::Add { tags, target } } => {
+            cmd_tag_add(tags, target.into_target()).await
+        }
+        Commands::Tag { command: TagCommand::Remove { tags, target } } => {
+            cmd_tag_remove(tags, target.into_target()).await
+        }
+        Commands::Tag { command: TagCommand::List } => cmd_tag_list().await,
+        Commands::Db { command: DbCommand::Status { db_path } } => cmd_db_status(db_path).await,
+        Commands::Db { command: DbCommand::Migrate { dry_run, db_path } } => {
+            cmd_db_migrate(&rules, dry_run, db_path).await
+        }
+        Commands::Serve { port: _ } => cmd_serve(&rules).await,
         Commands::Snapshot { repo, filename } => cmd_snapshot(repo, filename).await,
-        Commands::Restore { repo, commit, filename } => cmd_restore(repo, commit, filename).await,
+        Commands::Restore { repo, commit, filename } => cmd_restore(&rules, repo, commit, filename).await,
         Commands::Snapshots { repo, limit } => cmd_snapshots(repo, limit).await,
     }
 }
 
 async fn cmd_recover(
-    browser: BrowserArg,
-    profile: String,
-    dry_run: bool,
-    db_path: Option<PathBuf>,
+    rules: &Rules,
+    opts: tablitz_recover::RecoverOptions,
     out: Option<PathBuf>,
     _format: OutputFormat,
+    update: bool,
 ) -> Result<()> {
     use indicatif::{ProgressBar, ProgressStyle};
     let pb = ProgressBar::new_spinner();
     pb.set_style(ProgressStyle::default_spinner().template("{spinner} {msg}").unwrap());
-    pb.set_message(format!("Recovering from {} profile '{}'...", format!("{:?}", browser).to_lowercase(), profile));
+    pb.set_message(format!("Recovering from {} profile '{}'...", opts.browser.display_name(), opts.profile));
     pb.enable_steady_tick(std::time::Duration::from_millis(80));
 
-    let opts = tablitz_recover::RecoverOptions {
-        browser: browser_arg_to_recover(&browser),
-        profile,
-        dry_run,
-        db_path,
-    };
+    let dry_run = opts.dry_run;
     let session = tablitz_recover::recover(opts)?;
     pb.finish_and_clear();
 
@@ -220,34 +595,132 @@ async fn cmd_recover(
     }
 
     if let Some(path) = out {
-        let json = serde_json::to_string_pretty(&session)?;
+        let json = session.to_json_pretty()?;
         std::fs::write(&path, json)?;
         println!("  Saved to {}", path.display());
     } else {
-        let store = tablitz_store::Store::open_default().await?;
-        let stats = store.insert_session(&session).await?;
+        let store = open_canonicalizing_store(rules).await?;
+        let stats = insert_or_update(&store, &session, update_mode(update)).await?;
         println!(
             "  Imported: {} groups, {} tabs (skipped: {} groups, {} tabs)",
             stats.groups_inserted, stats.tabs_inserted,
             stats.groups_skipped, stats.tabs_skipped
         );
+        print_recognised(&stats);
+        print_updates(&stats.updates);
     }
     Ok(())
 }
 
+async fn cmd_recover_all(rules: &Rules, dry_run: bool, salvage: bool, update: bool) -> Result<()> {
+    let stores = tablitz_recover::discover_onetab_stores(&tablitz_recover::BrowserRegistry::load()?);
+    if stores.is_empty() {
+        println!("{}", "No OneTab data found in any browser profile.".yellow());
+        return Ok(());
+    }
+
+    println!("Found OneTab data in {} profiles:", stores.len().to_string().bold());
+    let label = |found: &tablitz_recover::DiscoveredStore| {
+        let name = match &found.display_name {
+            Some(display) if *display != found.profile => format!("{} ({})", found.profile, display),
+            _ => found.profile.clone(),
+        };
+        format!("  {:<18} {:<28}", found.browser.display_name(), name)
+    };
+
+    if dry_run {
+        for found in &stores {
+            println!("{} {}", label(found), found.path.display().to_string().dimmed());
+        }
+        println!("{}", "(dry run — nothing imported)".dimmed());
+        return Ok(());
+    }
+
+    let store = open_canonicalizing_store(rules).await?;
+    for found in &stores {
+        let opts = tablitz_recover::RecoverOptions { salvage, ..found.recover_options() };
+        let result = match tablitz_recover::recover(opts) {
+            Ok(session) => insert_or_update(&store, &session, update_mode(update)).await.map(|stats| (session, stats)),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok((session, stats)) => {
+                let updated = if stats.updates.is_empty() {
+                    String::new()
+                } else {
+                    format!(", {} groups updated", stats.updates.len())
+                };
+                println!(
+                    "{} {:>5} groups {:>6} tabs  {} {} groups, {} tabs{}",
+                    label(found), session.groups.len(), session.total_tab_count(),
+                    "→ imported".green(), stats.groups_inserted, stats.tabs_inserted, updated
+                )
+            }
+            Err(e) => println!("{} {} {:#}", label(found), "✗".red(), e),
+        }
+    }
+    Ok(())
+}
+
+fn cmd_recover_list_versions(opts: &tablitz_recover::RecoverOptions) -> Result<()> {
+    let versions = tablitz_recover::list_versions(opts)?;
+    if versions.is_empty() {
+        println!("{}", "No versions of OneTab state found.".yellow());
+        return Ok(());
+    }
+
+    println!("{} versions of OneTab state (oldest first):", versions.len().to_string().bold());
+    for (i, v) in versions.iter().enumerate() {
+        let file = v.path.file_name().unwrap_or_default().to_string_lossy();
+        let latest = if i + 1 == versions.len() { " (latest)".green().to_string() } else { String::new() };
+        println!(
+            "  {:>8}  {:>5} groups  {:>6} tabs  {}{}",
+            v.sequence.to_string().cyan(), v.groups.len(), v.tab_count(), file.dimmed(), latest
+        );
+    }
+    println!(
+        "{}",
+        "Recover one with --version <SEQ>, or the union of all with --version all".dimmed()
+    );
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn cmd_import(
+    rules: &Rules,
     from_onetab_export: Option<PathBuf>,
     from_onetab_leveldb: Option<PathBuf>,
-    browser: Option<BrowserArg>,
+    from_firefox_idb: Option<PathBuf>,
+    from_bookmarks_html: Option<PathBuf>,
+    from_chromium_session: Option<PathBuf>,
+    from_firefox_session: Option<PathBuf>,
+    browser: Option<String>,
     profile: Option<String>,
-    _from_tablitz: Option<PathBuf>,
+    timezone: tablitz_recover::Timezone,
+    from_tablitz: Option<PathBuf>,
+    mode: tablitz_store::ImportMode,
 ) -> Result<()> {
-    let store = tablitz_store::Store::open_default().await?;
+    let store = open_canonicalizing_store(rules).await?;
 
-    if let Some(path) = from_onetab_export {
+    if let Some(path) = from_tablitz {
+        println!("Importing from tablitz export: {}", path.display());
+        let session = tablitz_recover::parse_tablitz_export(&path)?;
+        let stats = store.import_session(&session, mode).await?;
+        println!(
+            "{} {} groups inserted, {} replaced, {} skipped",
+            "✓".green(), stats.groups_inserted, stats.groups_replaced, stats.groups_skipped
+        );
+        println!(
+            "  {} tabs inserted, {} skipped",
+            stats.tabs_inserted, stats.tabs_skipped
+        );
+        print_recognised(&stats);
+        print_updates(&stats.updates);
+        print_conflicts(&stats.conflicts, mode);
+    } else if let Some(path) = from_onetab_export {
         println!("Importing from OneTab export: {}", path.display());
-        let session = tablitz_recover::parse_onetab_export(&path)?;
-        let stats = store.insert_session(&session).await?;
+        let session = tablitz_recover::parse_onetab_export_with_timezone(&path, timezone)?;
+        let stats = insert_or_update(&store, &session, mode).await?;
         println!(
             "{} {} groups inserted, {} skipped",
             "✓".green(), stats.groups_inserted, stats.groups_skipped
@@ -256,65 +729,342 @@ async fn cmd_import(
             "  {} tabs inserted, {} skipped",
             stats.tabs_inserted, stats.tabs_skipped
         );
+        print_recognised(&stats);
+        print_updates(&stats.updates);
     } else if let Some(path) = from_onetab_leveldb {
-        let b = browser.unwrap_or(BrowserArg::Chrome);
+        let b = tablitz_recover::BrowserRegistry::load()?.browser(browser.as_deref().unwrap_or("chrome"))?;
         let p = profile.unwrap_or_else(|| "Default".to_string());
-        let source = match &b {
-            BrowserArg::Chrome => tablitz_core::SessionSource::Chrome { profile: p.clone() },
-            BrowserArg::Edge => tablitz_core::SessionSource::Edge { profile: p.clone() },
-            BrowserArg::Brave => tablitz_core::SessionSource::Brave { profile: p.clone() },
-            BrowserArg::Comet => tablitz_core::SessionSource::Comet { profile: p.clone() },
-        };
+        let source = b.session_source(&p);
         let session = tablitz_recover::extract_from_leveldb(&path, source)?;
-        let stats = store.insert_session(&session).await?;
+        let stats = insert_or_update(&store, &session, mode).await?;
         println!(
             "{} Imported from LevelDB ({}): {} groups, {} tabs",
-            "✓".green(), format!("{:?}", b).to_lowercase(),
+            "✓".green(), b.id,
             stats.groups_inserted, stats.tabs_inserted
         );
+        print_recognised(&stats);
+        print_updates(&stats.updates);
+    } else if let Some(path) = from_firefox_idb {
+        let p = profile.unwrap_or_else(|| "default".to_string());
+        let b = tablitz_recover::BrowserRegistry::load()?.browser(browser.as_deref().unwrap_or("firefox"))?;
+        let source = b.session_source(&p);
+        let session = tablitz_recover::extract_from_firefox_idb(&path, source)?;
+        let stats = insert_or_update(&store, &session, mode).await?;
+        println!(
+            "{} Imported from Firefox IndexedDB: {} groups, {} tabs",
+            "✓".green(), stats.groups_inserted, stats.tabs_inserted
+        );
+        print_recognised(&stats);
+        print_updates(&stats.updates);
+    } else if let Some(path) = from_bookmarks_html {
+        println!("Importing bookmarks: {}", path.display());
+        let session = tablitz_recover::parse_bookmarks_html(&path)?;
+        let stats = insert_or_update(&store, &session, mode).await?;
+        println!(
+            "{} {} folders inserted, {} skipped",
+            "✓".green(), stats.groups_inserted, stats.groups_skipped
+        );
+        println!(
+            "  {} bookmarks inserted, {} skipped",
+            stats.tabs_inserted, stats.tabs_skipped
+        );
+        print_recognised(&stats);
+        print_updates(&stats.updates);
+    } else if let Some(path) = from_chromium_session {
+        let source = tablitz_core::SessionSource::ChromiumSession { profile: profile.unwrap_or_else(|| "Default".to_string()) };
+        let session = tablitz_recover::parse_chromium_session(&path, source)?;
+        let stats = insert_or_update(&store, &session, mode).await?;
+        println!(
+            "{} Imported open tabs from Chromium session: {} windows, {} tabs (skipped: {} windows)",
+            "✓".green(), stats.groups_inserted, stats.tabs_inserted, stats.groups_skipped
+        );
+        print_recognised(&stats);
+        print_updates(&stats.updates);
+    } else if let Some(path) = from_firefox_session {
+        let source = tablitz_core::SessionSource::FirefoxSession { profile: profile.unwrap_or_else(|| "default".to_string()) };
+        let session = tablitz_recover::parse_firefox_session(&path, source)?;
+        let stats = insert_or_update(&store, &session, mode).await?;
+        println!(
+            "{} Imported open tabs from Firefox session: {} windows, {} tabs (skipped: {} windows)",
+            "✓".green(), stats.groups_inserted, stats.tabs_inserted, stats.groups_skipped
+        );
+        print_recognised(&stats);
+        print_updates(&stats.updates);
     } else {
-        eprintln!("{} No import source specified. Use --from-onetab-export or --from-onetab-leveldb", "✗".red());
+        eprintln!(
+            "{} No import source specified. Use --from-onetab-export, --from-onetab-leveldb, --from-firefox-idb, \
+             --from-bookmarks-html, --from-chromium-session, --from-firefox-session or --from-tablitz",
+            "✗".red()
+        );
         std::process::exit(1);
     }
     Ok(())
 }
 
-async fn cmd_export(format: ExportFormat, out: Option<PathBuf>, filter: Option<String>) -> Result<()> {
-    let store = tablitz_store::Store::open_default().await?;
-    let session = store.get_session().await?;
+/// `ImportMode::Update` if `--update` was given, else `ImportMode::Merge`.
+fn update_mode(update: bool) -> tablitz_store::ImportMode {
+    if update { tablitz_store::ImportMode::Update } else { tablitz_store::ImportMode::Merge }
+}
 
-    let groups: Vec<_> = if let Some(ref f) = filter {
-        session.groups.iter()
-            .filter(|g| g.label.as_deref().unwrap_or("").contains(f.as_str()))
-            .cloned()
-            .collect()
-    } else {
-        session.groups.clone()
+/// Inserts a session, or with `ImportMode::Update` brings the stored groups
+/// it holds in step with it.
+async fn insert_or_update(
+    store: &tablitz_store::Store,
+    session: &tablitz_core::TabSession,
+    mode: tablitz_store::ImportMode,
+) -> Result<tablitz_store::InsertStats> {
+    match mode {
+        tablitz_store::ImportMode::Update => store.import_session(session, mode).await,
+        _ => store.insert_session(session).await,
+    }
+}
+
+/// Lists the groups `--update` changed and how.
+fn print_updates(updates: &[tablitz_store::GroupUpdate]) {
+    if updates.is_empty() {
+        return;
+    }
+    println!("{} {} groups updated:", "~".cyan(), updates.len());
+    for u in updates {
+        let mut changes: Vec<String> = Vec::new();
+        if u.tabs_added > 0 {
+            changes.push(format!("+{} tabs", u.tabs_added));
+        }
+        if u.tabs_removed > 0 {
+            changes.push(format!("-{} tabs", u.tabs_removed));
+        }
+        if u.tabs_changed > 0 {
+            changes.push(format!("{} tabs changed", u.tabs_changed));
+        }
+        if u.reordered {
+            changes.push("reordered".to_string());
+        }
+        changes.extend(u.fields.iter().map(|f| f.to_string()));
+        println!(
+            "  {} {} {}",
+            u.group_id.dimmed(),
+            u.label.as_deref().unwrap_or("(unlabeled)").cyan(),
+            changes.join(", ")
+        );
+    }
+}
+
+/// Notes how many skipped groups were already stored from another source.
+fn print_recognised(stats: &tablitz_store::InsertStats) {
+    if stats.groups_recognised > 0 {
+        println!(
+            "  {} groups were already stored from another source (their IDs are kept as aliases)",
+            stats.groups_recognised
+        );
+    }
+}
+
+/// Lists groups whose imported copy differs from the stored one.
+fn print_conflicts(conflicts: &[tablitz_store::GroupConflict], mode: tablitz_store::ImportMode) {
+    if conflicts.is_empty() {
+        return;
+    }
+    let outcome = match mode {
+        tablitz_store::ImportMode::Merge => "kept the stored copy; use --replace to overwrite",
+        tablitz_store::ImportMode::Replace => "replaced with the imported copy",
+        tablitz_store::ImportMode::Update => "updated in place",
     };
+    println!("{} {} groups differ from the store ({}):", "!".yellow(), conflicts.len(), outcome);
+    for c in conflicts {
+        let tabs = if c.stored_tabs != c.imported_tabs {
+            format!(" ({} → {} tabs)", c.stored_tabs, c.imported_tabs)
+        } else {
+            String::new()
+        };
+        println!(
+            "  {} {} {}{}",
+            c.group_id.dimmed(),
+            c.label.as_deref().unwrap_or("(unlabeled)").cyan(),
+            c.fields.join(", "),
+            tabs
+        );
+    }
+}
 
-    let content = match format {
-        ExportFormat::Json => serde_json::to_string_pretty(&groups)?,
-        ExportFormat::Toml => {
-            #[derive(serde::Serialize)]
-            struct TomlRoot<'a> { groups: &'a [tablitz_core::TabGroup] }
-            toml::to_string(&TomlRoot { groups: &groups })?
-        }
-        ExportFormat::Markdown => {
-            let mut md = String::new();
-            for group in &groups {
-                md.push_str(&format!("---\n## {} tabs\n", group.tabs.len()));
-                md.push_str(&format!("> Created {}\n", group.created_at.format("%-m/%-d/%Y, %-I:%M:%S %p")));
-                if let Some(label) = &group.label {
-                    md.push_str(&format!("> {}\n", label));
+async fn cmd_enrich(
+    rules: &Rules,
+    browsers: Vec<String>,
+    profile: String,
+    chromium_history: Vec<PathBuf>,
+    chromium_bookmarks: Vec<PathBuf>,
+    firefox_places: Vec<PathBuf>,
+    dry_run: bool,
+) -> Result<()> {
+    use tablitz_recover::{read_chromium_bookmarks, read_chromium_history, read_firefox_places};
+    type Reader = fn(&std::path::Path) -> Result<Vec<tablitz_core::UrlActivity>>;
+
+    // Open the store before rusqlite touches SQLite, so libsql gets to configure it
+    let store = open_store_with(rules).await?;
+    let files: Vec<(PathBuf, Reader)> = chromium_history.into_iter()
+        .map(|p| (p, read_chromium_history as Reader))
+        .chain(chromium_bookmarks.into_iter().map(|p| (p, read_chromium_bookmarks as Reader)))
+        .chain(firefox_places.into_iter().map(|p| (p, read_firefox_places as Reader)))
+        .collect();
+    let mut activity = Vec::new();
+    let read_profiles = files.is_empty() || !browsers.is_empty();
+    for (path, read) in files {
+        let found = read(&path)?;
+        println!("  {:<40} {:>7} URLs", path.display(), found.len());
+        activity.extend(found);
+    }
+
+    if read_profiles {
+        let registry = tablitz_recover::BrowserRegistry::load()?;
+        // Named browsers must have the profile; otherwise take whichever do
+        let explicit = !browsers.is_empty();
+        let targets = if explicit {
+            browsers.iter().map(|id| registry.browser(id)).collect::<Result<Vec<_>>>()?
+        } else {
+            registry.browsers().to_vec()
+        };
+        for browser in &targets {
+            let found = tablitz_recover::resolve_profile_dir(browser, &profile)
+                .and_then(|dir| tablitz_recover::read_profile_activity(browser, &dir));
+            match found {
+                Ok(found) => {
+                    println!("  {:<40} {:>7} URLs", format!("{} '{}'", browser.display_name(), profile), found.len());
+                    activity.extend(found);
                 }
-                md.push('\n');
-                for tab in &group.tabs {
-                    md.push_str(&format!("[{}]({})\n", tab.title, tab.url));
+                Err(e) if explicit => return Err(e),
+                Err(_) => {}
+            }
+        }
+    }
+
+    if activity.is_empty() {
+        println!("{}", format!("No history or bookmarks found for profile '{}'.", profile).yellow());
+        return Ok(());
+    }
+
+    let matched = store.match_activity(&activity).await?;
+    let bookmarked = matched.values().filter(|a| a.bookmarked).count();
+    println!(
+        "{} Matched {} tabs ({} bookmarked) from {} history and bookmark entries",
+        "✓".green(), matched.len(), bookmarked, activity.len()
+    );
+    if dry_run {
+        println!("{}", "(dry run — nothing updated)".dimmed());
+        return Ok(());
+    }
+    let updated = store.set_tab_activity(&matched).await?;
+    println!("  Updated {} tabs", updated);
+    Ok(())
+}
+
+async fn cmd_normalize(rules: &Rules, query: tablitz_search::Query, preview: bool) -> Result<()> {
+    let store = open_store().await?;
+    let session = store.get_session_matching(&query).await?;
+    let mut titles = std::collections::HashMap::new();
+    for group in &session.groups {
+        let mut shown_group = false;
+        for tab in &group.tabs {
+            let normalized = rules.titles.normalize_tab(tab);
+            if normalized == tab.title {
+                continue;
+            }
+            if preview {
+                if !shown_group {
+                    println!("{}", group.label.as_deref().unwrap_or("(unlabeled)").cyan());
+                    shown_group = true;
                 }
-                md.push('\n');
+                println!("  {} {}", "-".red(), tab.title.red());
+                println!("  {} {}", "+".green(), normalized.green());
             }
-            md
+            titles.insert(tab.id.clone(), normalized);
         }
+    }
+
+    let total = session.total_tab_count();
+    if titles.is_empty() {
+        println!("All {} titles are already normalized.", total);
+        return Ok(());
+    }
+    if preview {
+        println!("{} of {} titles would change", titles.len().to_string().bold(), total);
+        println!("{}", "(preview — nothing saved)".dimmed());
+        return Ok(());
+    }
+    let updated = store.set_tab_titles(&titles).await?;
+    println!("{} Normalized {} of {} titles", "✓".green(), updated, total);
+    Ok(())
+}
+
+/// Parses a query string and folds the `--filter` and `--tag` shorthands into it.
+fn build_query(query: Option<&str>, label: Option<String>, tag: Option<String>) -> Result<tablitz_search::Query> {
+    use tablitz_search::{Predicate, Query};
+    let mut parsed = Query::parse(query.unwrap_or(""))?;
+    if let Some(label) = label {
+        parsed = parsed.and(Predicate::Label(label.to_ascii_lowercase()).into());
+    }
+    if let Some(tag) = tag.as_deref().and_then(tablitz_core::normalize_tag) {
+        parsed = parsed.and(Predicate::Tag(tag).into());
+    }
+    Ok(parsed)
+}
+
+async fn cmd_write_onetab(db_path: PathBuf, query: tablitz_search::Query, dry_run: bool) -> Result<()> {
+    let store = open_store().await?;
+    let groups = store.get_groups_matching(&query).await?;
+    let session = tablitz_core::TabSession {
+        version: tablitz_core::TabSession::CURRENT_VERSION,
+        source: tablitz_core::SessionSource::Unknown,
+        groups,
+        created_at: chrono::Utc::now(),
+        imported_at: chrono::Utc::now(),
+    };
+
+    if dry_run {
+        println!("Dry run: Would write to {}", db_path.display());
+        println!(
+            "  {} groups, {} tabs",
+            session.groups.len(), session.total_tab_count()
+        );
+        if tablitz_recover::leveldb_is_locked(&db_path).unwrap_or(false) {
+            println!("{}", "  The store is locked; close the browser first".yellow());
+        }
+        println!("{}", "(dry run — nothing written)".dimmed());
+        return Ok(());
+    }
+
+    let report = tablitz_recover::write_to_leveldb(&db_path, &session)?;
+    println!(
+        "{} Wrote {} groups, {} tabs to {} (replacing {} groups)",
+        "✓".green(), report.groups_written, report.tabs_written,
+        report.path.display(), report.groups_replaced
+    );
+    println!("  Original kept at {}", report.backup.display());
+    Ok(())
+}
+
+async fn cmd_export(
+    format: ExportFormat,
+    out: Option<PathBuf>,
+    query: tablitz_search::Query,
+    timezone: tablitz_recover::Timezone,
+) -> Result<()> {
+    let store = open_store().await?;
+    let groups = store.get_groups_matching(&query).await?;
+
+    /// What `--format json` and `--format toml` write: the groups, and the
+    /// document version `import --from-tablitz` reads them as.
+    #[derive(serde::Serialize)]
+    struct GroupList<'a> {
+        version: u32,
+        groups: &'a [tablitz_core::TabGroup],
+    }
+    let list = GroupList { version: tablitz_core::TabSession::CURRENT_VERSION, groups: &groups };
+
+    let content = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&list)?,
+        ExportFormat::Toml => toml::to_string(&list)?,
+        ExportFormat::Pipe => tablitz_recover::format_pipe_export(&groups),
+        ExportFormat::BookmarksHtml => tablitz_recover::format_bookmarks_html(&groups),
+        ExportFormat::Markdown => tablitz_recover::format_markdown_export(&groups, timezone),
     };
 
     if let Some(path) = out {
@@ -326,55 +1076,159 @@ async fn cmd_export(format: ExportFormat, out: Option<PathBuf>, filter: Option<S
     Ok(())
 }
 
-async fn cmd_search(query: String, mode: SearchMode, limit: usize) -> Result<()> {
-    let store = tablitz_store::Store::open_default().await?;
+/// A search result, with the highlighted snippet when the backend provides one.
+struct Hit {
+    result: tablitz_search::SearchResult,
+    snippet: Option<String>,
+}
+
+/// Runs `query` against the store: free text is ranked by `mode`, every other
+/// term filters. Without free text, matching tabs are returned in store order.
+async fn search_store(
+    store: &tablitz_store::Store,
+    query: &tablitz_search::Query,
+    mode: SearchMode,
+    limit: usize,
+) -> Result<Vec<Hit>> {
+    use tablitz_search::{MatchKind, SearchResult};
+
+    let (text, filters) = query.split_text();
+    let hit = |tab: tablitz_core::Tab, group_id: String, score: f32, match_kind| Hit {
+        result: SearchResult { tab, group_id, score, match_kind },
+        snippet: None,
+    };
+    if text.is_empty() {
+        let session = store.get_session_matching(&filters).await?;
+        return Ok(session.groups.into_iter()
+            .flat_map(|g| {
+                let group_id = g.id;
+                g.tabs.into_iter().map(move |t| hit(t, group_id.clone(), 0.0, MatchKind::Exact))
+            })
+            .take(limit)
+            .collect());
+    }
+    // Phrases keep their quotes so ranked backends match them as phrases
+    let ranked_query = text.iter()
+        .map(|t| if t.contains(char::is_whitespace) { format!("\"{}\"", t) } else { t.clone() })
+        .collect::<Vec<_>>()
+        .join(" ");
+
     match mode {
         SearchMode::Fuzzy => {
-            let session = store.get_session().await?;
-            let results = tablitz_search::FuzzySearcher::search(&query, &session);
-            let results: Vec<_> = results.into_iter().take(limit).collect();
-            if results.is_empty() {
-                println!("No results for '{}'", query);
-                return Ok(());
-            }
-            println!("{} results for '{}':", results.len(), query.bold());
-            for r in &results {
-                println!("  [{:.0}] {} \n        {}", r.score, r.tab.title.cyan(), r.tab.url.as_str().dimmed());
-            }
+            let session = filters.filter_session(&store.get_session().await?);
+            Ok(tablitz_search::FuzzySearcher::search(&text.join(" "), &session)
+                .into_iter()
+                .take(limit)
+                .map(|result| Hit { result, snippet: None })
+                .collect())
         }
+        #[cfg(feature = "full-text")]
         SearchMode::FullText => {
-            let by_url = store.search_by_url(&query).await?;
-            let by_title = store.search_by_title(&query).await?;
-            let mut seen = std::collections::HashSet::new();
-            let merged: Vec<_> = by_url.into_iter().chain(by_title)
-                .filter(|t| seen.insert(t.url.to_string()))
-                .take(limit).collect();
-            if merged.is_empty() {
-                println!("No results for '{}'", query);
-                return Ok(());
-            }
-            println!("{} results for '{}':", merged.len(), query.bold());
-            for tab in &merged {
-                println!("  {} \n        {}", tab.title.cyan(), tab.url.as_str().dimmed());
-            }
+            Ok(store.search_index_filtered(&ranked_query, &filters, limit).await?
+                .into_iter()
+                .map(|result| Hit { result, snippet: None })
+                .collect())
+        }
+        #[cfg(not(feature = "full-text"))]
+        SearchMode::FullText => {
+            Ok(store.search_fts_filtered(&ranked_query, &filters, limit).await?
+                .into_iter()
+                .map(|h| Hit {
+                    snippet: Some(h.snippet),
+                    ..hit(h.tab, h.group_id, -h.rank as f32, MatchKind::FullText)
+                })
+                .collect())
         }
     }
-    Ok(())
 }
 
-async fn cmd_list(filter: Option<String>, limit: usize) -> Result<()> {
-    let store = tablitz_store::Store::open_default().await?;
-    let groups = store.get_all_groups().await?;
+fn format_score(r: &tablitz_search::SearchResult) -> String {
+    match r.match_kind {
+        tablitz_search::MatchKind::Exact => String::new(),
+        tablitz_search::MatchKind::Fuzzy => format!("[{:.0}] ", r.score),
+        _ => format!("[{:.2}] ", r.score),
+    }
+}
 
-    let groups: Vec<_> = if let Some(ref f) = filter {
-        groups.into_iter()
-            .filter(|g| g.label.as_deref().unwrap_or("").contains(f.as_str()))
-            .collect()
-    } else {
-        groups
+/// Sorts tabs by `key`, best first; ties keep their current order.
+fn sort_tabs<T>(items: &mut [T], key: SortKey, tab: impl Fn(&T) -> &tablitz_core::Tab) {
+    match key {
+        SortKey::Added => items.sort_by_key(|t| std::cmp::Reverse(tab(t).added_at)),
+        SortKey::LastVisited => items.sort_by_key(|t| std::cmp::Reverse(tab(t).activity.last_visited_at)),
+        SortKey::Visits => items.sort_by_key(|t| std::cmp::Reverse(tab(t).activity.visit_count)),
+    }
+}
+
+/// A tab's visit history for display, e.g. ` ★ 12 visits, last 2024-03-01`.
+fn format_activity(activity: &tablitz_core::TabActivity) -> String {
+    let mut out = String::new();
+    if activity.bookmarked {
+        out.push_str(" ★");
+    }
+    if activity.visit_count > 0 {
+        out.push_str(&format!(" {} visits", activity.visit_count));
+    }
+    if let Some(at) = activity.last_visited_at {
+        let sep = if activity.visit_count > 0 { "," } else { "" };
+        out.push_str(&format!("{} last {}", sep, at.format("%Y-%m-%d")));
+    }
+    out
+}
+
+async fn cmd_search(
+    text: String,
+    query: tablitz_search::Query,
+    mode: SearchMode,
+    limit: usize,
+    sort: Option<SortKey>,
+) -> Result<()> {
+    let store = open_store().await?;
+    let hits = match sort {
+        None => search_store(&store, &query, mode, limit).await?,
+        Some(key) => {
+            // Sorting has to see every match, not just the best-ranked few
+            let total = store.get_stats().await?.total_tabs as usize;
+            let mut hits = search_store(&store, &query, mode, total.max(limit)).await?;
+            sort_tabs(&mut hits, key, |h| &h.result.tab);
+            hits.truncate(limit);
+            hits
+        }
     };
+    if hits.is_empty() {
+        println!("No results for '{}'", text);
+        return Ok(());
+    }
+    println!("{} results for '{}':", hits.len(), text.bold());
+    for Hit { result: r, snippet } in &hits {
+        println!(
+            "  {}{} {}{} \n        {}",
+            format_score(r), r.tab.id.dimmed(), r.tab.title.cyan(),
+            format_activity(&r.tab.activity).yellow(), r.tab.url.as_str().dimmed()
+        );
+        if let Some(snippet) = snippet.as_ref().filter(|s| **s != r.tab.title) {
+            println!("        {}", snippet.dimmed());
+        }
+    }
+    Ok(())
+}
 
-    let groups: Vec<_> = groups.into_iter().take(limit).collect();
+async fn cmd_list(query: tablitz_search::Query, limit: usize, sort: Option<SortKey>) -> Result<()> {
+    let store = open_store().await?;
+    let groups = store.get_all_groups().await?;
+    // Prefixes must be unique across every group, not just the listed ones
+    let short_ids = unique_prefixes(groups.iter().map(|g| g.id.as_str()));
+    let mut groups: Vec<_> = groups.iter().filter_map(|g| query.filter_group(g)).collect();
+    match sort {
+        None => {}
+        Some(SortKey::Added) => groups.sort_by_key(|g| std::cmp::Reverse(g.created_at)),
+        Some(SortKey::LastVisited) => groups.sort_by_key(|g| {
+            std::cmp::Reverse(g.tabs.iter().filter_map(|t| t.activity.last_visited_at).max())
+        }),
+        Some(SortKey::Visits) => groups.sort_by_key(|g| {
+            std::cmp::Reverse(g.tabs.iter().map(|t| t.activity.visit_count).sum::<u64>())
+        }),
+    }
+    groups.truncate(limit);
 
     if groups.is_empty() {
         println!("No groups found.");
@@ -390,33 +1244,145 @@ async fn cmd_list(filter: Option<String>, limit: usize) -> Result<()> {
             if group.locked { "🔒" } else { "" },
             if group.starred { "⭐" } else { "" },
         );
+        let tags = if group.tags.is_empty() {
+            String::new()
+        } else {
+            format!(" #{}", group.tags.join(" #"))
+        };
         println!(
-            "  {} {} {} ({} tabs)",
-            group.id.get(..8).unwrap_or(&group.id).dimmed(),
+            "  {} {} {} ({} tabs){}",
+            short_ids[&group.id].dimmed(),
             label.cyan(),
             flags,
-            group.tabs.len()
+            group.tabs.len(),
+            tags.yellow()
         );
     }
     Ok(())
 }
 
-async fn cmd_dedup(strategy: DedupStrategyArg, normalize_titles: bool, dry_run: bool) -> Result<()> {
-    let store = tablitz_store::Store::open_default().await?;
+/// Shortest prefix of each id (at least 8 chars) that no other id shares,
+/// so listed ids can be passed back to commands like `tablitz tag`.
+fn unique_prefixes<'a>(ids: impl Iterator<Item = &'a str>) -> std::collections::HashMap<String, String> {
+    let mut sorted: Vec<&str> = ids.collect();
+    sorted.sort_unstable();
+    sorted.dedup();
+    let common = |a: &str, b: &str| a.chars().zip(b.chars()).take_while(|(x, y)| x == y).count();
+    (0..sorted.len())
+        .map(|i| {
+            let id = sorted[i];
+            let shared = [i.checked_sub(1), Some(i + 1)]
+                .into_iter()
+                .flatten()
+                .filter_map(|j| sorted.get(j))
+                .map(|other| common(id, other))
+                .max()
+                .unwrap_or(0);
+            let len = (shared + 1).max(8);
+            let end = id.char_indices().nth(len).map_or(id.len(), |(i, _)| i);
+            (id.to_string(), id[..end].to_string())
+        })
+        .collect()
+}
+
+async fn cmd_tag_add(tags: Vec<String>, target: tablitz_store::TagTarget) -> Result<()> {
+    let store = open_store().await?;
+    let target = store.resolve_tag_target(target).await?;
+    let added = store.add_tags(&target, &tags).await?;
+    println!("{} Added {} tag(s) to {}", "✓".green(), added, target);
+    Ok(())
+}
+
+async fn cmd_tag_remove(tags: Vec<String>, target: tablitz_store::TagTarget) -> Result<()> {
+    let store = open_store().await?;
+    let target = store.resolve_tag_target(target).await?;
+    let removed = store.remove_tags(&target, &tags).await?;
+    println!("{} Removed {} tag(s) from {}", "✓".green(), removed, target);
+    Ok(())
+}
+
+async fn cmd_tag_list() -> Result<()> {
+    let store = open_store().await?;
+    let tags = store.list_tags().await?;
+    if tags.is_empty() {
+        println!("No tags yet. Add one with `tablitz tag add <tag> --tab <id>`.");
+        return Ok(());
+    }
+    println!("{} tags:", tags.len().to_string().bold());
+    for t in &tags {
+        println!("  {} ({} tabs, {} groups)", t.tag.yellow(), t.tabs, t.groups);
+    }
+    Ok(())
+}
+
+async fn cmd_dedup(
+    rules: &Rules,
+    strategy: DedupStrategyArg,
+    threshold: Option<f32>,
+    normalize_titles: bool,
+    dry_run: bool,
+    review: bool,
+) -> Result<()> {
+    let near_duplicate = matches!(strategy, DedupStrategyArg::NearDuplicate);
+    if threshold.is_some() && !near_duplicate && !matches!(strategy, DedupStrategyArg::FuzzyUrl) {
+        anyhow::bail!("--threshold only applies to --strategy fuzzy-url or near-duplicate");
+    }
+    if review && !near_duplicate {
+        anyhow::bail!("--review only applies to --strategy near-duplicate");
+    }
+
+    let store = open_store_with(rules).await?;
     let session = store.get_session().await?;
+    let engine = tablitz_search::DedupEngine::new(rules.canonical.clone(), rules.titles.clone());
 
     let mut working = session.clone();
     if normalize_titles {
-        working = tablitz_search::TitleNormalizer::normalize_session(&working);
+        working = rules.titles.normalize_session(&working);
     }
 
+    let mut strategy_name = strategy
+        .to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default();
+    let threshold = threshold.unwrap_or(if near_duplicate {
+        tablitz_search::DedupEngine::DEFAULT_NEAR_DUPLICATE_THRESHOLD
+    } else {
+        tablitz_search::DedupEngine::DEFAULT_FUZZY_THRESHOLD
+    });
+    if matches!(strategy, DedupStrategyArg::FuzzyUrl | DedupStrategyArg::NearDuplicate) {
+        strategy_name.push_str(&format!(" {}", threshold));
+    }
+    if review {
+        strategy_name.push_str(", reviewed");
+    }
+    if normalize_titles {
+        strategy_name.push_str(", normalize-titles");
+    }
     let dedup_strategy = match strategy {
         DedupStrategyArg::ExactUrl => tablitz_search::DedupStrategy::ExactUrl,
         DedupStrategyArg::NormalizedUrl => tablitz_search::DedupStrategy::NormalizedUrl,
+        DedupStrategyArg::FuzzyUrl => tablitz_search::DedupStrategy::FuzzyUrl { threshold },
         DedupStrategyArg::UrlAndTitle => tablitz_search::DedupStrategy::UrlAndTitle,
+        DedupStrategyArg::NearDuplicate => tablitz_search::DedupStrategy::NearDuplicate { threshold },
     };
 
-    let result = tablitz_search::DedupEngine::dedup(&working, dedup_strategy);
+    let result = if near_duplicate {
+        let clusters = engine.near_duplicates(&working, threshold);
+        let clusters = if review {
+            review_clusters(&working, clusters)?
+        } else {
+            if dry_run {
+                let tabs = tabs_by_id(&working);
+                for (i, cluster) in clusters.iter().enumerate() {
+                    print_cluster(i, clusters.len(), cluster, &tabs);
+                }
+            }
+            clusters
+        };
+        tablitz_search::DedupEngine::merge_clusters(&working, &clusters)
+    } else {
+        engine.dedup(&working, dedup_strategy)
+    };
     println!(
         "Dedup: {} → {} tabs ({} removed)",
         result.original_count, result.deduplicated_count,
@@ -428,11 +1394,145 @@ async fn cmd_dedup(strategy: DedupStrategyArg, normalize_titles: bool, dry_run:
         return Ok(());
     }
 
-    let group_count = result.session.groups.len();
-    for group in &result.session.groups {
-        store.replace_tabs_for_group(group).await?;
+    store.refresh_canonical_ids().await?;
+    match store.apply_dedup(&strategy_name, &result).await? {
+        Some(run) => {
+            println!("{} Persisted deduplicated tabs ({} groups updated)", "✓".green(), run.groups_changed);
+            println!(
+                "  Recorded as dedup run {}; undo with `tablitz dedup --undo {}`",
+                run.id.to_string().cyan(),
+                run.id
+            );
+        }
+        None => println!("{} Nothing to change", "✓".green()),
+    }
+    Ok(())
+}
+
+/// Tabs by id, with their group's label.
+type TabsById<'a> = std::collections::HashMap<&'a str, (&'a tablitz_core::Tab, Option<&'a str>)>;
+
+fn tabs_by_id(session: &tablitz_core::TabSession) -> TabsById<'_> {
+    session
+        .groups
+        .iter()
+        .flat_map(|g| g.tabs.iter().map(move |t| (t.id.as_str(), (t, g.label.as_deref()))))
+        .collect()
+}
+
+fn print_cluster(
+    index: usize,
+    total: usize,
+    cluster: &tablitz_search::DuplicateCluster,
+    tabs: &TabsById,
+) {
+    let line = |tab_id: &str, note: &str| match tabs.get(tab_id) {
+        Some((tab, label)) => format!(
+            "{} {}\n        {}{}",
+            tab.title,
+            format!("[{}]", label.unwrap_or("unlabeled")).dimmed(),
+            tab.url.as_str().dimmed(),
+            note
+        ),
+        None => tab_id.to_string(),
+    };
+    println!("{} {}/{}", "Cluster".bold(), index + 1, total);
+    println!("  {}  {}", "keep".green(), line(&cluster.keep, ""));
+    for duplicate in &cluster.duplicates {
+        let reason = match duplicate.reason {
+            tablitz_search::MatchReason::SameUrl => " (same URL)",
+            tablitz_search::MatchReason::SimilarTitle => " (similar title)",
+        };
+        println!(
+            "  {}  {}",
+            format!("{:>3.0}%", duplicate.score * 100.0).yellow(),
+            line(&duplicate.tab_id, reason)
+        );
+    }
+}
+
+/// Asks about each cluster on stdin and returns the accepted ones.
+fn review_clusters(
+    session: &tablitz_core::TabSession,
+    clusters: Vec<tablitz_search::DuplicateCluster>,
+) -> Result<Vec<tablitz_search::DuplicateCluster>> {
+    use std::io::Write;
+
+    let tabs = tabs_by_id(session);
+    let total = clusters.len();
+    let mut accepted = Vec::new();
+    let mut accept_rest = false;
+    for (i, cluster) in clusters.into_iter().enumerate() {
+        if accept_rest {
+            accepted.push(cluster);
+            continue;
+        }
+        print_cluster(i, total, &cluster, &tabs);
+        let answer = loop {
+            print!("Merge? [y]es, [n]o, [a]ll remaining, [q]uit: ");
+            std::io::stdout().flush()?;
+            let mut line = String::new();
+            if std::io::stdin().read_line(&mut line)? == 0 {
+                break 'q';
+            }
+            match line.trim().to_lowercase().as_str() {
+                "y" | "yes" => break 'y',
+                "n" | "no" => break 'n',
+                "a" | "all" => break 'a',
+                "q" | "quit" => break 'q',
+                _ => continue,
+            }
+        };
+        match answer {
+            'y' => accepted.push(cluster),
+            'a' => {
+                accepted.push(cluster);
+                accept_rest = true;
+            }
+            'q' => break,
+            _ => {}
+        }
+        println!();
+    }
+    Ok(accepted)
+}
+
+async fn cmd_dedup_undo(rules: &Rules, run_id: i64) -> Result<()> {
+    let store = open_canonicalizing_store(rules).await?;
+    let merges = store.dedup_merges(run_id).await?;
+    let run = store.undo_dedup(run_id).await?;
+    println!(
+        "{} Undid dedup run {}: {} tabs restored in {} groups",
+        "✓".green(),
+        run.id,
+        merges.len(),
+        run.groups_changed
+    );
+    Ok(())
+}
+
+async fn cmd_dedup_history() -> Result<()> {
+    let store = open_store().await?;
+    let runs = store.dedup_runs().await?;
+    if runs.is_empty() {
+        println!("No dedup runs recorded");
+        return Ok(());
+    }
+    for run in runs {
+        let state = match run.undone_at {
+            Some(at) => format!("undone {}", at.format("%Y-%m-%d %H:%M")).dimmed(),
+            None => "applied".green(),
+        };
+        println!(
+            "{:>4}  {}  {}  {} tabs removed from {} groups  {}",
+            run.id.to_string().cyan(),
+            run.created_at.format("%Y-%m-%d %H:%M"),
+            run.strategy,
+            run.tabs_removed,
+            run.groups_changed,
+            state
+        );
     }
-    println!("{} Persisted deduplicated tabs ({} groups updated)", "✓".green(), group_count);
     Ok(())
 }
 
@@ -448,8 +1548,8 @@ async fn cmd_init() -> Result<()> {
     Ok(())
 }
 
-async fn cmd_stats() -> Result<()> {
-    let store = tablitz_store::Store::open_default().await?;
+async fn cmd_stats(rules: &Rules) -> Result<()> {
+    let store = open_store_with(rules).await?;
     let stats = store.get_stats().await?;
 
     println!("{}", "tablitz store stats".bold());
@@ -472,8 +1572,135 @@ async fn cmd_stats() -> Result<()> {
     Ok(())
 }
 
-async fn cmd_snapshot(repo: PathBuf, filename: Option<String>) -> Result<()> {
+#[cfg(feature = "full-text")]
+async fn cmd_reindex() -> Result<()> {
+    let mut store = tablitz_store::Store::open_default().await?;
+    let index_dir = tablitz_store::default_index_dir()?;
+    let count = store.rebuild_full_text_index(&index_dir).await?;
+    println!("{} Rebuilt full-text index: {} tabs indexed", "✓".green(), count);
+    println!("  Index dir: {}", index_dir.display());
+    Ok(())
+}
+
+#[cfg(not(feature = "full-text"))]
+async fn cmd_reindex() -> Result<()> {
+    eprintln!("The full-text index requires the 'full-text' feature. Rebuild with: cargo build --features full-text");
+    std::process::exit(1);
+}
+
+/// Opens the default store, warning if its full-text index can't be used.
+async fn open_store() -> Result<tablitz_store::Store> {
     let store = tablitz_store::Store::open_default().await?;
+    #[cfg(feature = "full-text")]
+    if let Some(error) = store.full_text_error() {
+        eprintln!(
+            "{} full-text index unavailable ({}); run `tablitz reindex` to rebuild it",
+            "warning:".yellow(),
+            error
+        );
+    }
+    Ok(store)
+}
+
+/// Opens the default store with the configured canonicalization rules, for
+/// commands that compare URLs or count domains.
+async fn open_store_with(rules: &Rules) -> Result<tablitz_store::Store> {
+    let mut store = open_store().await?;
+    store.set_canonical_rules(rules.canonical.clone());
+    Ok(store)
+}
+
+/// Opens the default store for a command that stores canonical IDs, first
+/// bringing the stored ones in line with the configured rules.
+///
+/// Only commands that refuse invalid rules may call this, so that the rules
+/// recorded with the store are never the defaults they fell back to.
+async fn open_canonicalizing_store(rules: &Rules) -> Result<tablitz_store::Store> {
+    let store = open_store_with(rules).await?;
+    store.refresh_canonical_ids().await?;
+    Ok(store)
+}
+
+/// Opens the store at `db_path` (or the default location) without migrating it.
+///
+/// Returns `None`, after saying so, if there is no database there: opening
+/// one would create an empty file.
+async fn open_store_unmigrated(db_path: Option<PathBuf>) -> Result<Option<tablitz_store::Store>> {
+    let path = match db_path {
+        Some(path) => path,
+        None => tablitz_store::default_db_path()?,
+    };
+    println!("Database: {}", path.display());
+    if !path.exists() {
+        println!("  {}", "No database (nothing has been imported yet)".yellow());
+        return Ok(None);
+    }
+    tablitz_store::Store::open_unmigrated(&path).await.map(Some)
+}
+
+async fn cmd_db_status(db_path: Option<PathBuf>) -> Result<()> {
+    let Some(store) = open_store_unmigrated(db_path).await? else {
+        return Ok(());
+    };
+    let version = store.schema_version().await?;
+    println!(
+        "  Schema version: {} (latest: {})",
+        version.to_string().cyan(),
+        tablitz_store::LATEST_SCHEMA_VERSION
+    );
+    for m in store.migration_status().await? {
+        match m.applied_at {
+            Some(at) => println!(
+                "  {} {:3} {:30} {}",
+                "✓".green(), m.version, m.name,
+                at.format("%Y-%m-%d %H:%M:%S UTC").to_string().dimmed()
+            ),
+            None => println!("  {} {:3} {:30} {}", "•".yellow(), m.version, m.name, "pending".yellow()),
+        }
+    }
+    if version > tablitz_store::LATEST_SCHEMA_VERSION {
+        println!("{}", "  Database is newer than this build of tablitz; upgrade tablitz before using it.".red());
+    }
+    Ok(())
+}
+
+async fn cmd_db_migrate(rules: &Rules, dry_run: bool, db_path: Option<PathBuf>) -> Result<()> {
+    let Some(mut store) = open_store_unmigrated(db_path).await? else {
+        return Ok(());
+    };
+    store.set_canonical_rules(rules.canonical.clone());
+    let pending = store.pending_migrations().await?;
+    if pending.is_empty() {
+        if !dry_run {
+            store.refresh_canonical_ids().await?;
+        }
+        println!("{} Schema is up to date (version {})", "✓".green(), store.schema_version().await?);
+        return Ok(());
+    }
+
+    if dry_run {
+        println!("{} pending migrations:", pending.len().to_string().bold());
+        for m in &pending {
+            println!("  {:3} {}", m.version, m.name);
+        }
+        println!("{}", "(dry run — nothing applied)".dimmed());
+        return Ok(());
+    }
+
+    let applied = store.migrate().await?;
+    store.refresh_canonical_ids().await?;
+    for m in &applied {
+        println!("  {:3} {}", m.version, m.name);
+    }
+    println!(
+        "{} Applied {} migrations (schema version {})",
+        "✓".green(), applied.len(), store.schema_version().await?
+    );
+    Ok(())
+}
+
+async fn cmd_snapshot(repo: PathBuf, filename: Option<String>) -> Result<()> {
+    let store = open_store().await?;
     let mut mgr = tablitz_sync::SyncManager::new(&repo);
     if let Some(name) = filename {
         mgr = tablitz_sync::SyncManager::with_filename(&repo, name);
@@ -483,8 +1710,8 @@ async fn cmd_snapshot(repo: PathBuf, filename: Option<String>) -> Result<()> {
     Ok(())
 }
 
-async fn cmd_restore(repo: PathBuf, commit: Option<String>, filename: Option<String>) -> Result<()> {
-    let store = tablitz_store::Store::open_default().await?;
+async fn cmd_restore(rules: &Rules, repo: PathBuf, commit: Option<String>, filename: Option<String>) -> Result<()> {
+    let store = open_canonicalizing_store(rules).await?;
     let mut mgr = tablitz_sync::SyncManager::new(&repo);
     if let Some(name) = filename {
         mgr = tablitz_sync::SyncManager::with_filename(&repo, name);
@@ -512,9 +1739,9 @@ async fn cmd_snapshots(repo: PathBuf, limit: usize) -> Result<()> {
 }
 
 #[cfg(feature = "mcp")]
-async fn cmd_serve() -> Result<()> {
+async fn cmd_serve(rules: &Rules) -> Result<()> {
     use rmcp::{ServiceExt, transport::stdio};
-    let store = tablitz_store::Store::open_default().await?;
+    let store = open_canonicalizing_store(rules).await?;
     let server = mcp::TablitzMcpServer::new(store);
     eprintln!("tablitz MCP server starting on stdio...");
     let service = server.serve(stdio()).await?;
@@ -523,7 +1750,7 @@ async fn cmd_serve() -> Result<()> {
 }
 
 #[cfg(not(feature = "mcp"))]
-async fn cmd_serve() -> Result<()> {
+async fn cmd_serve(_rules: &Rules) -> Result<()> {
     eprintln!("MCP server requires the 'mcp' feature. Rebuild with: cargo build --features mcp");
     std::process::exit(1);
 }
@@ -557,56 +1784,104 @@ mod mcp {
             }
         }
 
-        #[tool(name = "search_tabs", description = "Search tabs by query using fuzzy matching")]
+        #[tool(name = "search_tabs", description = "Search tabs using fuzzy matching (default) or the full-text index (mode: \"full-text\"). The query mixes free text with filters: domain:, url:, title:, label:, tag:, starred:/pinned:/locked:, after:/before: (YYYY-MM-DD), bookmarked:true/false, visits:N (at least N visits), visited-after:/visited-before: (YYYY-MM-DD, last visit), \"phrases\", OR, -term and parentheses. The activity filters need `tablitz enrich` to have been run")]
         async fn search_tabs(
             &self,
             Parameters(params): Parameters<SearchTabsParams>,
         ) -> Result<CallToolResult, McpError> {
             let limit = params.limit.unwrap_or(20);
-            let session = self.store.get_session().await
-                .map_err(|e| McpError::internal_error(e.to_string(), None))?;
-            let results = tablitz_search::FuzzySearcher::search(&params.query, &session);
-            let results: Vec<_> = results.into_iter().take(limit).collect();
-            let text = results.iter().map(|r| {
-                format!("[{:.0}] {}\n        {}", r.score, r.tab.title, r.tab.url)
+            let mode = match params.mode.as_deref().unwrap_or("fuzzy") {
+                "fuzzy" => super::SearchMode::Fuzzy,
+                "full-text" => super::SearchMode::FullText,
+                other => return Err(McpError::invalid_params(
+                    format!("Unknown search mode '{}'. Use: fuzzy, full-text", other), None
+                )),
+            };
+            let query = super::build_query(Some(&params.query), None, params.tag)
+                .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+            let hits = super::search_store(&self.store, &query, mode, limit).await
+                .map_err(|e| McpError::invalid_params(format!("{:#}", e), None))?;
+            let text = hits.iter().map(|h| {
+                let r = &h.result;
+                format!("{}{} (tab {})\n        {}", super::format_score(r), r.tab.title, r.tab.id, r.tab.url)
             }).collect::<Vec<_>>().join("\n");
             let output = if text.is_empty() {
                 format!("No results for '{}'", params.query)
             } else {
-                format!("{} results for '{}':\n{}", results.len(), params.query, text)
+                format!("{} results for '{}':\n{}", hits.len(), params.query, text)
             };
             Ok(CallToolResult::success(vec![Content::text(output)]))
         }
 
-        #[tool(name = "list_groups", description = "List tab groups with optional label filter")]
+        #[tool(name = "list_groups", description = "List tab groups, optionally narrowed by a search_tabs-style query, a label substring, or a tag")]
         async fn list_groups(
             &self,
             Parameters(params): Parameters<ListGroupsParams>,
         ) -> Result<CallToolResult, McpError> {
             let limit = params.limit.unwrap_or(50);
-            let groups = self.store.get_all_groups().await
+            let query = super::build_query(params.query.as_deref(), params.filter, params.tag)
+                .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+            let mut groups = self.store.get_groups_matching(&query).await
                 .map_err(|e| McpError::internal_error(e.to_string(), None))?;
-            let groups: Vec<_> = groups.into_iter()
-                .filter(|g| params.filter.as_deref()
-                    .map(|f| g.label.as_deref().unwrap_or("").contains(f))
-                    .unwrap_or(true))
-                .take(limit)
-                .collect();
+            groups.truncate(limit);
             if groups.is_empty() {
                 return Ok(CallToolResult::success(vec![Content::text("No groups found.")]));
             }
             let text = groups.iter().map(|g| {
-                format!("[{}] {} ({} tabs) — {}",
-                    g.id.get(..8).unwrap_or(&g.id),
+                let mut line = format!("[{}] {} ({} tabs) — {}",
+                    g.id,
                     g.label.as_deref().unwrap_or("(unlabeled)"),
                     g.tabs.len(),
-                    g.created_at.format("%Y-%m-%d"))
+                    g.created_at.format("%Y-%m-%d"));
+                if !g.tags.is_empty() {
+                    line.push_str(&format!(" [tags: {}]", g.tags.join(", ")));
+                }
+                line
             }).collect::<Vec<_>>().join("\n");
             Ok(CallToolResult::success(vec![Content::text(
                 format!("{} groups:\n{}", groups.len(), text)
             )]))
         }
 
+        #[tool(name = "add_tags", description = "Add tags to tabs and/or groups by id (unique id prefixes are accepted)")]
+        async fn add_tags(
+            &self,
+            Parameters(params): Parameters<TagParams>,
+        ) -> Result<CallToolResult, McpError> {
+            let mut added = 0;
+            for target in self.resolve_targets(&params).await? {
+                added += self.store.add_tags(&target, &params.tags).await
+                    .map_err(|e| McpError::internal_error(format!("{:#}", e), None))?;
+            }
+            Ok(CallToolResult::success(vec![Content::text(format!("Added {} tag(s)", added))]))
+        }
+
+        #[tool(name = "remove_tags", description = "Remove tags from tabs and/or groups by id (unique id prefixes are accepted)")]
+        async fn remove_tags(
+            &self,
+            Parameters(params): Parameters<TagParams>,
+        ) -> Result<CallToolResult, McpError> {
+            let mut removed = 0;
+            for target in self.resolve_targets(&params).await? {
+                removed += self.store.remove_tags(&target, &params.tags).await
+                    .map_err(|e| McpError::internal_error(format!("{:#}", e), None))?;
+            }
+            Ok(CallToolResult::success(vec![Content::text(format!("Removed {} tag(s)", removed))]))
+        }
+
+        #[tool(name = "list_tags", description = "List all tags with how many tabs and groups carry each")]
+        async fn list_tags(&self) -> Result<CallToolResult, McpError> {
+            let tags = self.store.list_tags().await
+                .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            if tags.is_empty() {
+                return Ok(CallToolResult::success(vec![Content::text("No tags.")]));
+            }
+            let text = tags.iter()
+                .map(|t| format!("{} ({} tabs, {} groups)", t.tag, t.tabs, t.groups))
+                .collect::<Vec<_>>().join("\n");
+            Ok(CallToolResult::success(vec![Content::text(text)]))
+        }
+
         #[tool(name = "get_stats", description = "Get store statistics: group count, tab count, top domains, date range")]
         async fn get_stats(&self) -> Result<CallToolResult, McpError> {
             let stats = self.store.get_stats().await
@@ -627,25 +1902,22 @@ mod mcp {
             Ok(CallToolResult::success(vec![Content::text(out)]))
         }
 
-        #[tool(name = "recover_from_browser", description = "Recover tabs from a browser OneTab LevelDB store and import to tablitz")]
+        #[tool(name = "recover_from_browser", description = "Recover tabs from a browser's OneTab store (Chromium LevelDB or Firefox IndexedDB) and import to tablitz")]
         async fn recover_from_browser(
             &self,
             Parameters(params): Parameters<RecoverFromBrowserParams>,
         ) -> Result<CallToolResult, McpError> {
-            let browser_enum = match params.browser.to_lowercase().as_str() {
-                "chrome" => tablitz_recover::Browser::Chrome,
-                "edge"   => tablitz_recover::Browser::Edge,
-                "brave"  => tablitz_recover::Browser::Brave,
-                "comet"  => tablitz_recover::Browser::Comet,
-                other    => return Err(McpError::invalid_params(
-                    format!("Unknown browser '{}'. Use: chrome, edge, brave, comet", other), None
-                )),
-            };
+            let registry = tablitz_recover::BrowserRegistry::load()
+                .map_err(|e| McpError::internal_error(format!("{:#}", e), None))?;
+            let browser = registry.browser(&params.browser)
+                .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
             let opts = tablitz_recover::RecoverOptions {
-                browser: browser_enum,
+                browser,
                 profile: params.profile.unwrap_or_else(|| "Default".to_string()),
                 dry_run: false,
                 db_path: None,
+                salvage: params.salvage.unwrap_or(false),
+                version: None,
             };
             let session = tablitz_recover::recover(opts)
                 .map_err(|e| McpError::internal_error(e.to_string(), None))?;
@@ -659,14 +1931,22 @@ mod mcp {
             ))]))
         }
 
-        #[tool(name = "import_onetab_export", description = "Import tabs from a OneTab export file (.txt pipe or .md markdown format)")]
+        #[tool(name = "import_onetab_export", description = "Import tabs from a OneTab export file (.txt pipe or .md markdown format) or a tablitz .json/.toml export")]
         async fn import_onetab_export(
             &self,
             Parameters(params): Parameters<ImportOnetabExportParams>,
         ) -> Result<CallToolResult, McpError> {
             let pb = std::path::PathBuf::from(&params.path);
-            let session = tablitz_recover::parse_onetab_export(&pb)
-                .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            let native = matches!(
+                pb.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase).as_deref(),
+                Some("json" | "toml")
+            );
+            let session = if native {
+                tablitz_recover::parse_tablitz_export(&pb)
+            } else {
+                tablitz_recover::parse_onetab_export(&pb)
+            }
+            .map_err(|e| McpError::internal_error(format!("{:#}", e), None))?;
             let stats = self.store.insert_session(&session).await
                 .map_err(|e| McpError::internal_error(e.to_string(), None))?;
             Ok(CallToolResult::success(vec![Content::text(format!(
@@ -678,23 +1958,59 @@ mod mcp {
         }
     }
 
+    impl TablitzMcpServer {
+        async fn resolve_targets(&self, params: &TagParams) -> Result<Vec<tablitz_store::TagTarget>, McpError> {
+            use tablitz_store::TagTarget;
+            let targets: Vec<_> = params.tab_ids.iter().flatten().cloned().map(TagTarget::Tab)
+                .chain(params.group_ids.iter().flatten().cloned().map(TagTarget::Group))
+                .collect();
+            if targets.is_empty() {
+                return Err(McpError::invalid_params("Provide tab_ids and/or group_ids", None));
+            }
+            let mut resolved = Vec::with_capacity(targets.len());
+            for target in targets {
+                resolved.push(self.store.resolve_tag_target(target).await
+                    .map_err(|e| McpError::invalid_params(format!("{:#}", e), None))?);
+            }
+            Ok(resolved)
+        }
+    }
+
     // Tool parameter schemas
     #[derive(Deserialize, JsonSchema)]
     struct SearchTabsParams {
         query: String,
         limit: Option<usize>,
+        /// "fuzzy" (default) or "full-text"
+        mode: Option<String>,
+        /// Only return tabs carrying this tag, directly or through their group
+        tag: Option<String>,
     }
 
     #[derive(Deserialize, JsonSchema)]
     struct ListGroupsParams {
+        /// Same query language as search_tabs
+        query: Option<String>,
+        /// Label substring
         filter: Option<String>,
         limit: Option<usize>,
+        tag: Option<String>,
+    }
+
+    #[derive(Deserialize, JsonSchema)]
+    struct TagParams {
+        tab_ids: Option<Vec<String>>,
+        group_ids: Option<Vec<String>>,
+        tags: Vec<String>,
     }
 
     #[derive(Deserialize, JsonSchema)]
     struct RecoverFromBrowserParams {
+        /// Browser id, e.g. chrome, edge, firefox, or one added in config.toml
         browser: String,
         profile: Option<String>,
+        /// Parse raw LevelDB files, for stores that fail to open
+        salvage: Option<bool>,
     }
 
     #[derive(Deserialize, JsonSchema)]
@@ -0,0 +1,118 @@
+use chrono::{DateTime, Utc};
+use tablitz_core::{SessionSource, Tab, TabActivity, TabGroup, TabSession, TablitzError};
+use url::Url;
+
+// ─── Helpers ───────────────────────────────────────────────────────────────
+
+fn fixture(name: &str) -> String {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name);
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+fn utc(rfc3339: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(rfc3339).unwrap().with_timezone(&Utc)
+}
+
+fn tab(id: &str, url: &str, title: &str, favicon_url: Option<&str>, added_at: &str) -> Tab {
+    Tab {
+        id: id.to_string(),
+        url: Url::parse(url).unwrap(),
+        title: title.to_string(),
+        favicon_url: favicon_url.map(str::to_string),
+        added_at: utc(added_at),
+        tags: Vec::new(),
+        activity: Default::default(),
+    }
+}
+
+/// The session every fixture describes, as a version 1 document could hold it.
+fn v1_session() -> TabSession {
+    TabSession {
+        version: TabSession::CURRENT_VERSION,
+        source: SessionSource::Chrome { profile: "Default".to_string() },
+        created_at: utc("2025-03-19T18:43:59Z"),
+        imported_at: utc("2026-02-24T10:00:00Z"),
+        groups: vec![TabGroup {
+            id: "abc123".to_string(),
+            label: Some("Reading".to_string()),
+            created_at: utc("2025-03-19T18:43:59Z"),
+            pinned: false,
+            locked: false,
+            starred: true,
+            tags: Vec::new(),
+            tabs: vec![
+                tab("tab-abc-0", "https://example.com/", "Example", None, "2025-03-19T18:43:59Z"),
+                tab(
+                    "tab-abc-1",
+                    "https://doc.rust-lang.org/book/",
+                    "The Rust Programming Language",
+                    Some("https://doc.rust-lang.org/favicon.ico"),
+                    "2025-03-20T22:08:46Z",
+                ),
+            ],
+        }],
+    }
+}
+
+/// [`v1_session`] plus the tags and activity version 2 added.
+fn v2_session() -> TabSession {
+    let mut session = v1_session();
+    let group = &mut session.groups[0];
+    group.tags = vec!["books".to_string()];
+    group.tabs[1].tags = vec!["rust".to_string()];
+    group.tabs[1].activity = TabActivity {
+        last_visited_at: Some(utc("2026-01-05T09:30:00Z")),
+        visit_count: 12,
+        bookmarked: true,
+    };
+    session
+}
+
+// ─── Format versions ───────────────────────────────────────────────────────
+
+/// `session-v1.json` is what tablitz wrote before format versions were read
+/// (byte for byte, dates in milliseconds); `session-v1-rfc3339.json` holds the
+/// same session with dates as the version 1 docs described them.
+#[test]
+fn test_v1_documents_upgrade_to_current() {
+    for name in ["session-v1.json", "session-v1-rfc3339.json"] {
+        let session = TabSession::from_json(&fixture(name)).unwrap();
+        assert_eq!(session, v1_session(), "{}", name);
+    }
+}
+
+#[test]
+fn test_v2_document_round_trips_byte_for_byte() {
+    let text = fixture("session-v2.json");
+    let session = TabSession::from_json(&text).unwrap();
+    assert_eq!(session, v2_session());
+    assert_eq!(session.to_json_pretty().unwrap(), text.trim_end());
+}
+
+#[test]
+fn test_to_json_pretty_writes_current_version() {
+    let mut session = v1_session();
+    session.version = 1;
+    let json = session.to_json_pretty().unwrap();
+    assert!(json.starts_with(&format!("{{\n  \"version\": {},", TabSession::CURRENT_VERSION)), "{}", json);
+}
+
+#[test]
+fn test_newer_and_unversioned_documents_are_refused() {
+    let newer = format!(r#"{{"version": {}, "groups": []}}"#, TabSession::CURRENT_VERSION + 1);
+    let err = TabSession::from_json(&newer).unwrap_err();
+    assert!(matches!(err, TablitzError::UnsupportedVersion { .. }), "{:?}", err);
+    assert!(err.to_string().contains("upgrade tablitz"), "{}", err);
+
+    for doc in [r#"{"groups": []}"#, r#"{"version": 0}"#, r#"{"version": "1"}"#, "[]"] {
+        let err = TabSession::from_json(doc).unwrap_err();
+        assert!(matches!(err, TablitzError::InvalidDocument(_)), "{}: {:?}", doc, err);
+    }
+}
+
+#[test]
+fn test_v1_bad_date_is_reported() {
+    let doc = fixture("session-v1-rfc3339.json").replace("2025-03-20T22:08:46Z", "yesterday");
+    let err = TabSession::from_json(&doc).unwrap_err();
+    assert!(err.to_string().contains("invalid date 'yesterday'"), "{}", err);
+}
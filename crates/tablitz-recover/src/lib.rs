@@ -1,9 +1,12 @@
 //! OneTab LevelDB recovery and import pipeline for tablitz.
 //!
 //! This crate provides functionality to:
-//! - Resolve OneTab LevelDB paths across browsers and platforms
+//! - Resolve OneTab LevelDB paths across browsers and platforms, from a
+//!   browser registry that config can extend
 //! - Safely read from browser LevelDB stores (handling lock contention)
 //! - Parse OneTab's internal JSON schema from LevelDB
+//! - Write tab sessions back into OneTab's LevelDB store
+//! - Read OneTab's Firefox IndexedDB storage (profiles.ini, snappy, structured clone)
 //! - Import from OneTab export files (both pipe-separated and markdown formats)
 //! - Provide a CLI-accessible API for tab recovery
 
@@ -12,149 +15,512 @@ use serde::Deserialize;
 use std::fs;
 use std::path::{Path, PathBuf};
 use chrono::Utc;
-use tablitz_core::{ms_timestamp_to_datetime, SessionSource, Tab, TabGroup, TabSession};
+use tablitz_core::{fnv1a_hash, ms_timestamp_to_datetime, SessionSource, Tab, TabGroup, TabSession};
 use tempfile::TempDir;
 use rusty_leveldb::LdbIterator;
 
-/// Supported browsers for OneTab extension recovery.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum Browser {
-    /// Google Chrome
-    Chrome,
-    /// Microsoft Edge
-    Edge,
-    /// Brave Browser
-    Brave,
-    /// Perplexity Comet
-    Comet,
+/// The built-in browser registry; see [`BrowserRegistry`].
+const BUILTIN_BROWSERS: &str = include_str!("browsers.toml");
+
+/// Where a browser keeps OneTab's extension storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StorageKind {
+    /// Chromium: a LevelDB store under `<profile>/Local Extension Settings/<extension id>`
+    #[default]
+    Leveldb,
+    /// Firefox: IndexedDB under `<profile>/storage/default/moz-extension+++<uuid>`
+    FirefoxIdb,
+}
+
+/// A browser OneTab can be recovered from, as described by a [`BrowserRegistry`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Browser {
+    /// Identifier used on the command line and in config (`chrome`, `arc`)
+    pub id: String,
+    /// Human-readable name
+    pub name: String,
+    pub storage: StorageKind,
+    /// OneTab extension (or Firefox add-on) IDs to probe, in order
+    pub extension_ids: Vec<String>,
+    /// User data directory templates on Windows, native install first
+    pub windows: Vec<String>,
+    /// User data directory templates on macOS
+    pub macos: Vec<String>,
+    /// User data directory templates on Linux: native, then Flatpak and Snap
+    pub linux: Vec<String>,
 }
 
 impl Browser {
-    /// Returns the OneTab extension ID for this browser.
+    /// Returns the human-readable display name for this browser.
+    pub fn display_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether this browser keeps OneTab data in Firefox-style IndexedDB.
+    pub fn is_firefox(&self) -> bool {
+        self.storage == StorageKind::FirefoxIdb
+    }
+
+    /// Returns the `SessionSource` recorded for data recovered from `profile`.
     ///
-    /// Chrome, Brave, and Comet (Chromium-based browsers) share the same
-    /// Web Store extension ID. Edge has a separate ID due to Microsoft's
-    /// Edge Add-ons store.
-    pub fn onetab_extension_id(&self) -> &'static str {
-        match self {
-            Browser::Chrome | Browser::Brave | Browser::Comet => {
-                "chphlpgkkbolifaimnlloiipkdnihall"
-            }
-            Browser::Edge => "hoimpamkkoehapgenciaoajfkfkpgfop",
+    /// Built-in browsers have their own variants; any other registry entry is
+    /// recorded as [`SessionSource::Browser`] with its id.
+    pub fn session_source(&self, profile: &str) -> SessionSource {
+        let profile = profile.to_string();
+        match self.id.as_str() {
+            "chrome" => SessionSource::Chrome { profile },
+            "edge" => SessionSource::Edge { profile },
+            "brave" => SessionSource::Brave { profile },
+            "comet" => SessionSource::Comet { profile },
+            "chromium" => SessionSource::Chromium { profile },
+            "vivaldi" => SessionSource::Vivaldi { profile },
+            "opera" => SessionSource::Opera { profile },
+            "firefox" => SessionSource::Firefox { profile },
+            id => SessionSource::Browser { browser: id.to_string(), profile },
         }
     }
 
-    /// Returns the human-readable display name for this browser.
-    pub fn display_name(&self) -> &'static str {
-        match self {
-            Browser::Chrome => "Chrome",
-            Browser::Edge => "Edge",
-            Browser::Brave => "Brave",
-            Browser::Comet => "Comet (Perplexity)",
+    /// Returns this browser's candidate user data directories on the current
+    /// platform, native install first. For Firefox-style browsers these are
+    /// the directories holding `profiles.ini`.
+    pub fn data_dirs(&self) -> Vec<PathBuf> {
+        let vars = PathVars::current();
+        self.platform_paths().iter().filter_map(|t| vars.expand(t)).collect()
+    }
+
+    fn platform_paths(&self) -> &[String] {
+        if cfg!(windows) {
+            &self.windows
+        } else if cfg!(target_os = "macos") {
+            &self.macos
+        } else {
+            &self.linux
         }
     }
 }
 
-/// Resolve the path to a browser's OneTab LevelDB store for a given profile.
+/// The base directories path templates are expanded against.
+struct PathVars {
+    home: Option<PathBuf>,
+    config: Option<PathBuf>,
+    data_local: Option<PathBuf>,
+}
+
+impl PathVars {
+    fn current() -> Self {
+        PathVars {
+            home: dirs::home_dir(),
+            config: dirs::config_dir(),
+            data_local: dirs::data_local_dir(),
+        }
+    }
+
+    /// Expands a path template, or returns `None` if its base directory is
+    /// unknown on this system.
+    fn expand(&self, template: &str) -> Option<PathBuf> {
+        let (base, rest) = split_template(template).ok()?;
+        let base = match base {
+            Some("home") => self.home.clone()?,
+            Some("config") => self.config.clone()?,
+            Some("data_local") => self.data_local.clone()?,
+            _ => PathBuf::from("/"),
+        };
+        Some(rest.split('/').filter(|c| !c.is_empty()).fold(base, |path, c| path.join(c)))
+    }
+}
+
+/// Splits a path template into its base placeholder (`None` for an absolute
+/// path) and the rest of the path.
+fn split_template(template: &str) -> Result<(Option<&str>, &str)> {
+    if let Some(rest) = template.strip_prefix("~/") {
+        return Ok((Some("home"), rest));
+    }
+    if let Some(after) = template.strip_prefix('{') {
+        let (name, rest) = after
+            .split_once('}')
+            .with_context(|| format!("Unclosed placeholder in path '{}'", template))?;
+        anyhow::ensure!(
+            matches!(name, "home" | "config" | "data_local"),
+            "Unknown placeholder '{{{}}}' in path '{}' (expected {{home}}, {{config}} or {{data_local}})",
+            name,
+            template
+        );
+        return Ok((Some(name), rest));
+    }
+    anyhow::ensure!(
+        template.starts_with('/'),
+        "Path '{}' must start with {{home}}, {{config}}, {{data_local}}, ~ or /",
+        template
+    );
+    Ok((None, template))
+}
+
+/// Returns the path of tablitz's config file (`<config dir>/tablitz/config.toml`).
+pub fn default_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("tablitz").join("config.toml"))
+}
+
+/// The `[canonical]` table of tablitz's config file.
+#[derive(Deserialize)]
+struct CanonicalFile {
+    #[serde(default)]
+    canonical: tablitz_core::CanonicalRules,
+}
+
+/// Reads the URL canonicalization rules from tablitz's config file (see
+/// [`default_config_path`]), or the defaults if it has none:
 ///
-/// # Browser-specific Paths
+/// ```toml
+/// [canonical]
+/// extra_tracking_params = ["share_id", "trk*"]
+/// fold_http = false
 ///
-/// **Windows:**
-/// ```text
-/// %LOCALAPPDATA%\<vendor>\<browser>\User Data\<profile>\Local Extension Settings\<ext_id>
+/// # Gmail keeps its view in the fragment
+/// [[canonical.domain]]
+/// domain = "mail.google.com"
+/// keep_fragment = true
+///
+/// # Only these parameters tell YouTube pages apart
+/// [[canonical.domain]]
+/// domain = "youtube.com"
+/// keep_query = ["v", "list"]
 /// ```
+pub fn load_canonical_rules() -> Result<tablitz_core::CanonicalRules> {
+    let Some(path) = default_config_path().filter(|p| p.is_file()) else {
+        return Ok(Default::default());
+    };
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    parse_canonical_rules(&content)
+        .with_context(|| format!("Invalid [canonical] rules in {}", path.display()))
+}
+
+/// Parses the `[canonical]` table from TOML text. Other tables are ignored.
+pub fn parse_canonical_rules(content: &str) -> Result<tablitz_core::CanonicalRules> {
+    let file: CanonicalFile = toml::from_str(content)?;
+    Ok(file.canonical)
+}
+
+/// The browsers tablitz can recover from.
 ///
-/// **macOS:**
-/// ```text
-/// ~/Library/Application Support/<vendor>/<browser>/<profile>/Local Extension Settings/<ext_id>
+/// The built-in entries live in this crate's `browsers.toml`. More can be
+/// added, and built-in ones extended, with `[[browser]]` tables in tablitz's
+/// config file (see [`default_config_path`]):
+///
+/// ```toml
+/// [[browser]]
+/// id = "thorium"
+/// name = "Thorium"
+/// extension_ids = ["chphlpgkkbolifaimnlloiipkdnihall"]
+/// linux = ["{config}/thorium"]
+///
+/// # Also probe an unpacked OneTab build in Chrome
+/// [[browser]]
+/// id = "chrome"
+/// extension_ids = ["abcdefghijklmnopabcdefghijklmnop"]
 /// ```
 ///
-/// **Linux:**
+/// Paths start with `{home}`, `{config}`, `{data_local}` or `~`, or are
+/// absolute. An entry whose `id` is already registered adds its extension
+/// IDs (probed first) and paths (tried last) to that browser, and replaces
+/// its name if it gives one.
+#[derive(Debug, Clone)]
+pub struct BrowserRegistry {
+    browsers: Vec<Browser>,
+}
+
+/// The `[[browser]]` tables of a registry file.
+#[derive(Deserialize)]
+struct RegistryFile {
+    #[serde(default)]
+    browser: Vec<BrowserEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BrowserEntry {
+    id: String,
+    name: Option<String>,
+    storage: Option<StorageKind>,
+    #[serde(default)]
+    extension_ids: Vec<String>,
+    #[serde(default)]
+    windows: Vec<String>,
+    #[serde(default)]
+    macos: Vec<String>,
+    #[serde(default)]
+    linux: Vec<String>,
+}
+
+impl BrowserRegistry {
+    /// The built-in browsers.
+    pub fn builtin() -> Self {
+        static BUILTIN: std::sync::OnceLock<BrowserRegistry> = std::sync::OnceLock::new();
+        BUILTIN
+            .get_or_init(|| {
+                let mut registry = BrowserRegistry { browsers: Vec::new() };
+                registry.add_toml(BUILTIN_BROWSERS).expect("built-in browsers.toml is valid");
+                registry
+            })
+            .clone()
+    }
+
+    /// The built-in browsers plus those in tablitz's config file, if it exists.
+    pub fn load() -> Result<Self> {
+        let mut registry = Self::builtin();
+        if let Some(path) = default_config_path().filter(|p| p.is_file()) {
+            registry.add_config_file(&path)?;
+        }
+        Ok(registry)
+    }
+
+    /// Adds the `[[browser]]` entries from a TOML file.
+    pub fn add_config_file(&mut self, path: &Path) -> Result<()> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        self.add_toml(&content)
+            .with_context(|| format!("Invalid browser registry in {}", path.display()))
+    }
+
+    /// Adds the `[[browser]]` entries from TOML text. Other tables are ignored.
+    pub fn add_toml(&mut self, content: &str) -> Result<()> {
+        let file: RegistryFile = toml::from_str(content)?;
+        for entry in file.browser {
+            self.add(entry)?;
+        }
+        Ok(())
+    }
+
+    fn add(&mut self, entry: BrowserEntry) -> Result<()> {
+        let id = entry.id.to_ascii_lowercase();
+        for template in entry.windows.iter().chain(&entry.macos).chain(&entry.linux) {
+            split_template(template).with_context(|| format!("Browser '{}'", id))?;
+        }
+
+        if let Some(existing) = self.browsers.iter_mut().find(|b| b.id == id) {
+            if let Some(name) = entry.name {
+                existing.name = name;
+            }
+            if let Some(storage) = entry.storage {
+                existing.storage = storage;
+            }
+            let mut ids = entry.extension_ids;
+            for ext_id in existing.extension_ids.drain(..) {
+                if !ids.contains(&ext_id) {
+                    ids.push(ext_id);
+                }
+            }
+            existing.extension_ids = ids;
+            existing.windows.extend(entry.windows);
+            existing.macos.extend(entry.macos);
+            existing.linux.extend(entry.linux);
+            return Ok(());
+        }
+
+        anyhow::ensure!(
+            !entry.extension_ids.is_empty(),
+            "Browser '{}' needs at least one extension ID",
+            id
+        );
+        self.browsers.push(Browser {
+            name: entry.name.unwrap_or_else(|| id.clone()),
+            id,
+            storage: entry.storage.unwrap_or_default(),
+            extension_ids: entry.extension_ids,
+            windows: entry.windows,
+            macos: entry.macos,
+            linux: entry.linux,
+        });
+        Ok(())
+    }
+
+    /// Every registered browser, built-ins first.
+    pub fn browsers(&self) -> &[Browser] {
+        &self.browsers
+    }
+
+    /// Looks a browser up by id, ignoring case.
+    pub fn get(&self, id: &str) -> Option<&Browser> {
+        self.browsers.iter().find(|b| b.id.eq_ignore_ascii_case(id))
+    }
+
+    /// Looks a browser up by id, with an error listing the known ids.
+    pub fn browser(&self, id: &str) -> Result<Browser> {
+        self.get(id).cloned().with_context(|| {
+            let ids: Vec<_> = self.browsers.iter().map(|b| b.id.as_str()).collect();
+            format!("Unknown browser '{}' (known: {})", id, ids.join(", "))
+        })
+    }
+}
+
+/// Returns a built-in browser by id (`chrome`, `firefox`, …).
+pub fn builtin_browser(id: &str) -> Option<Browser> {
+    BrowserRegistry::builtin().get(id).cloned()
+}
+
+/// Resolve the path to a browser's OneTab LevelDB store for a given profile.
+///
+/// Each of the browser's user data directories ([`Browser::data_dirs`]) is
+/// tried in turn, probing every extension ID; the first store that exists
+/// wins. If none does, the first candidate is returned.
+///
 /// ```text
-/// ~/.config/<vendor-lowercase>/<profile>/Local Extension Settings/<ext_id>
+/// <user data dir>/<profile>/Local Extension Settings/<ext_id>
 /// ```
 ///
-/// # Vendor/Browser Directory Names
-///
-/// | Browser | Windows | macOS | Linux |
-/// |---------|---------|-------|-------|
-/// | Chrome | `Google\Chrome` | `Google/Chrome` | `google-chrome` |
-/// | Edge | `Microsoft\Edge` | `Microsoft/Edge` | `microsoft-edge` |
-/// | Brave | `BraveSoftware\Brave-Browser` | `BraveSoftware/Brave-Browser` | `BraveSoftware/Brave-Browser` |
-/// | Comet | `Perplexity\Comet` | `Perplexity/Comet` | `perplexity-comet` |
+/// Some browsers (Opera) keep their only profile directly in the user data
+/// directory, so the `Default` profile also resolves there.
 ///
 /// # Errors
 ///
-/// Returns an error if the platform is unsupported, the base directory cannot be found,
-/// or path construction fails.
+/// Returns an error if the platform is unsupported or the base directory cannot
+/// be found. Firefox has no LevelDB store; use [`resolve_firefox_profile`] and
+/// [`resolve_firefox_idb_path`] instead.
 pub fn resolve_leveldb_path(browser: &Browser, profile: &str) -> Result<PathBuf> {
-    let ext_id = browser.onetab_extension_id();
+    if browser.is_firefox() {
+        anyhow::bail!("{} keeps OneTab data in IndexedDB, not LevelDB", browser.name);
+    }
+    let candidates: Vec<PathBuf> = browser
+        .data_dirs()
+        .iter()
+        .flat_map(|dir| leveldb_candidates(browser, dir, profile))
+        .collect();
+    candidates
+        .iter()
+        .find(|p| p.is_dir())
+        .or(candidates.first())
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("Unsupported platform or could not find base directory"))
+}
 
-    #[cfg(windows)]
-    {
-        if let Some(data_dir) = dirs::data_local_dir() {
-            return Ok(data_dir
-                .join(browser_subdir(browser, "windows", false))
-                .join(profile)
-                .join("Local Extension Settings")
-                .join(ext_id));
-        }
+/// The possible OneTab LevelDB paths for `profile` inside a Chromium user
+/// data directory, one per extension ID (and, for `Default`, per profile location).
+fn leveldb_candidates(browser: &Browser, data_dir: &Path, profile: &str) -> Vec<PathBuf> {
+    let mut profile_dirs = vec![data_dir.join(profile)];
+    if profile == "Default" {
+        profile_dirs.push(data_dir.to_path_buf());
     }
+    browser
+        .extension_ids
+        .iter()
+        .flat_map(|id| {
+            profile_dirs
+                .iter()
+                .map(move |dir| dir.join("Local Extension Settings").join(id))
+        })
+        .collect()
+}
+
+/// A browser profile with OneTab data, found by [`discover_onetab_stores`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredStore {
+    pub browser: Browser,
+    /// The profile as `RecoverOptions::profile` takes it: the directory name
+    /// (`Default`, `Profile 1`) for Chromium browsers, the profile name for Firefox
+    pub profile: String,
+    /// The name the browser shows for the profile, if it records one
+    pub display_name: Option<String>,
+    /// The OneTab store: a LevelDB directory, or a Firefox `idb` directory
+    pub path: PathBuf,
+}
 
-    #[cfg(target_os = "macos")]
-    {
-        if let Some(home_dir) = dirs::home_dir() {
-            return Ok(home_dir
-                .join("Library")
-                .join("Application Support")
-                .join(browser_subdir(browser, "macos", true))
-                .join(profile)
-                .join("Local Extension Settings")
-                .join(ext_id));
+impl DiscoveredStore {
+    /// Options that recover exactly this store.
+    pub fn recover_options(&self) -> RecoverOptions {
+        RecoverOptions {
+            browser: self.browser.clone(),
+            profile: self.profile.clone(),
+            db_path: Some(self.path.clone()),
+            ..Default::default()
         }
     }
+}
 
-    #[cfg(all(unix, not(target_os = "macos")))]
-    {
-        if let Some(config_dir) = dirs::config_dir() {
-            return Ok(config_dir
-                .join(browser_subdir(browser, "linux", false))
-                .join(profile)
-                .join("Local Extension Settings")
-                .join(ext_id));
-        }
+/// Lists the profiles in a Chromium user data directory as
+/// `(directory name, display name)`.
+///
+/// Profiles come from `profile.info_cache` in the directory's `Local State`
+/// file. Without one, `Default` and `Profile N` subdirectories are listed, and
+/// a user data directory that is itself a profile (Opera) lists as `Default`.
+pub fn list_chromium_profiles(data_dir: &Path) -> Vec<(String, Option<String>)> {
+    let mut profiles: Vec<(String, Option<String>)> = fs::read_to_string(data_dir.join("Local State"))
+        .ok()
+        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+        .and_then(|state| {
+            let cache = state.get("profile")?.get("info_cache")?.as_object()?.clone();
+            Some(
+                cache
+                    .into_iter()
+                    .map(|(dir, info)| {
+                        let name = info.get("name").and_then(|n| n.as_str()).map(str::to_string);
+                        (dir, name)
+                    })
+                    .collect(),
+            )
+        })
+        .unwrap_or_default();
+
+    if profiles.is_empty() {
+        profiles = fs::read_dir(data_dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|name| name == "Default" || name.starts_with("Profile "))
+            .map(|name| (name, None))
+            .collect();
+    }
+    if profiles.is_empty() && data_dir.join("Local Extension Settings").is_dir() {
+        profiles.push(("Default".to_string(), None));
     }
 
-    Err(anyhow::anyhow!("Unsupported platform or could not find base directory"))
+    // Default first, then Profile 1, Profile 2, …, Profile 10
+    profiles.sort_by_key(|(dir, _)| (dir != "Default", dir.len(), dir.clone()));
+    profiles
 }
 
-/// Returns the browser's vendor/browser directory name for the platform.
-fn browser_subdir(browser: &Browser, platform: &str, _vendor_first: bool) -> PathBuf {
-    let (vendor, browser_name) = match browser {
-        Browser::Chrome => ("Google", "Chrome"),
-        Browser::Edge => ("Microsoft", "Edge"),
-        Browser::Brave => ("BraveSoftware", "Brave-Browser"),
-        Browser::Comet => ("Perplexity", "Comet"),
-    };
+/// Finds every profile with OneTab data across the registry's browsers and
+/// their native, Flatpak and Snap installs.
+pub fn discover_onetab_stores(registry: &BrowserRegistry) -> Vec<DiscoveredStore> {
+    let mut stores = Vec::new();
+    for browser in registry.browsers() {
+        for data_dir in browser.data_dirs() {
+            stores.extend(discover_onetab_stores_in(browser, &data_dir));
+        }
+    }
+    stores
+}
 
-    if platform == "linux" {
-        // Linux paths are browser-specific and do not follow a simple vendor/browser formula.
-        // Chrome and Edge use a hyphenated lowercase format, while Brave and Comet keep their
-        // original casing because that is what the browsers use on the filesystem.
-        let path_str = match browser {
-            Browser::Chrome => "google-chrome".to_string(),
-            Browser::Edge => "microsoft-edge".to_string(),
-            Browser::Brave => "BraveSoftware/Brave-Browser".to_string(),
-            Browser::Comet => "perplexity-comet".to_string(),
-        };
-        PathBuf::from(path_str)
-    } else if platform == "windows" {
-        // Windows uses backslash-separated format
-        PathBuf::from(format!("{}\\{}", vendor, browser_name))
-    } else {
-        // macOS uses forward slash-separated format
-        PathBuf::from(format!("{}/{}", vendor, browser_name))
+/// Finds the profiles with OneTab data in one user data directory, one store
+/// per extension ID found.
+pub fn discover_onetab_stores_in(browser: &Browser, data_dir: &Path) -> Vec<DiscoveredStore> {
+    if browser.is_firefox() {
+        return list_firefox_profiles(data_dir)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|profile| {
+                let path = resolve_firefox_idb_path(&profile.path, &browser.extension_ids).ok()?;
+                Some(DiscoveredStore { browser: browser.clone(), profile: profile.name, display_name: None, path })
+            })
+            .collect();
     }
+
+    list_chromium_profiles(data_dir)
+        .into_iter()
+        .flat_map(|(profile, display_name)| {
+            leveldb_candidates(browser, data_dir, &profile)
+                .into_iter()
+                .filter(|path| path.is_dir())
+                .map(move |path| DiscoveredStore {
+                    browser: browser.clone(),
+                    profile: profile.clone(),
+                    display_name: display_name.clone(),
+                    path,
+                })
+        })
+        .collect()
 }
 
 /// Open a LevelDB database, handling lock contention gracefully.
@@ -212,6 +578,26 @@ fn open_leveldb_safe(path: &Path) -> Result<(rusty_leveldb::DB, Option<TempDir>)
     }
 }
 
+/// Opens a copy of a SQLite database, with its write-ahead log or rollback
+/// journal, so a running browser neither blocks the read nor is disturbed by
+/// it. The copy lives as long as the returned `TempDir`.
+fn open_sqlite_copy(db_file: &Path) -> Result<(rusqlite::Connection, TempDir)> {
+    let temp_dir = tempfile::tempdir().context("Failed to create temp directory")?;
+    let copy = temp_dir.path().join("copy.sqlite");
+    fs::copy(db_file, &copy)
+        .with_context(|| format!("Failed to copy {}", db_file.display()))?;
+    for suffix in ["-wal", "-journal"] {
+        let side = PathBuf::from(format!("{}{}", db_file.display(), suffix));
+        if side.exists() {
+            fs::copy(&side, temp_dir.path().join(format!("copy.sqlite{}", suffix)))
+                .with_context(|| format!("Failed to copy {}", side.display()))?;
+        }
+    }
+    let conn = rusqlite::Connection::open(&copy)
+        .with_context(|| format!("Failed to open {}", db_file.display()))?;
+    Ok((conn, temp_dir))
+}
+
 /// Recursively copies a directory from src to dst.
 fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
     fs::create_dir_all(dst).context("Failed to create destination directory")?;
@@ -236,22 +622,24 @@ fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
 
 mod onetab_schema {
     use super::*;
+    use serde::Serialize;
 
     /// OneTab's root JSON schema from LevelDB values.
-    #[derive(Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug)]
     pub struct OneTabRoot {
         #[serde(rename = "tabGroups")]
         pub tab_groups: Vec<OneTabGroup>,
     }
 
     /// A tab group in OneTab's internal schema.
-    #[derive(Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug)]
     pub struct OneTabGroup {
         pub id: String,
         #[serde(rename = "tabsMeta")]
         pub tabs_meta: Vec<OneTabTab>,
         #[serde(rename = "createDate")]
         pub create_date: i64,
+        #[serde(skip_serializing_if = "Option::is_none")]
         pub title: Option<String>,
         pub pinned: Option<bool>,
         pub locked: Option<bool>,
@@ -259,14 +647,96 @@ mod onetab_schema {
     }
 
     /// A tab in OneTab's internal schema.
-    #[derive(Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug)]
     pub struct OneTabTab {
         pub id: String,
         pub url: String,
         pub title: String,
-        #[serde(rename = "favicon")]
+        #[serde(rename = "favicon", skip_serializing_if = "Option::is_none")]
         pub favicon_url: Option<String>,
     }
+
+    impl From<&TabGroup> for OneTabGroup {
+        fn from(group: &TabGroup) -> Self {
+            OneTabGroup {
+                id: group.id.clone(),
+                tabs_meta: group
+                    .tabs
+                    .iter()
+                    .map(|tab| OneTabTab {
+                        id: tab.id.clone(),
+                        url: tab.url.to_string(),
+                        title: tab.title.clone(),
+                        favicon_url: tab.favicon_url.clone(),
+                    })
+                    .collect(),
+                create_date: group.created_at.timestamp_millis(),
+                title: group.label.clone(),
+                pinned: Some(group.pinned),
+                locked: Some(group.locked),
+                starred: Some(group.starred),
+            }
+        }
+    }
+}
+
+/// Parse OneTab's stored state into tab groups.
+///
+/// Returns `None` if `value` is not OneTab's `{"tabGroups": [...]}` document.
+/// Tabs with invalid URLs are skipped with a warning, as are groups left empty.
+fn parse_onetab_state(value: &str) -> Option<Vec<TabGroup>> {
+    // OneTab stores the value as a JSON-encoded string (double-encoded):
+    // the raw bytes are `"{\\"tabGroups\\":[...]}"` — parse as String first,
+    // then parse the inner JSON as OneTabRoot.
+    let json_to_parse = if value.starts_with('"') {
+        serde_json::from_str::<String>(value).unwrap_or_else(|_| value.to_string())
+    } else {
+        value.to_string()
+    };
+    let root = serde_json::from_str::<onetab_schema::OneTabRoot>(&json_to_parse).ok()?;
+
+    let mut groups = Vec::new();
+    for group in root.tab_groups {
+        // Parse URLs, skipping invalid ones
+        let tabs: Vec<Tab> = group
+            .tabs_meta
+            .into_iter()
+            .filter_map(|t| {
+                match url::Url::parse(&t.url) {
+                    Ok(parsed_url) => Some(Tab {
+                        id: t.id,
+                        url: parsed_url,
+                        title: t.title,
+                        favicon_url: t.favicon_url,
+                        added_at: ms_timestamp_to_datetime(group.create_date),
+                        tags: Vec::new(),
+                        activity: Default::default(),
+                    }),
+                    Err(e) => {
+                        eprintln!(
+                            "warning: skipping invalid URL '{}': {}",
+                            t.url, e
+                        );
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        if !tabs.is_empty() {
+            groups.push(TabGroup {
+                id: group.id,
+                label: group.title,
+                created_at: ms_timestamp_to_datetime(group.create_date),
+                tabs,
+                pinned: group.pinned.unwrap_or(false),
+                locked: group.locked.unwrap_or(false),
+                starred: group.starred.unwrap_or(false),
+                tags: Vec::new(),
+            });
+        }
+    }
+    Some(groups)
 }
 
 /// Parse OneTab's JSON schema from a LevelDB value and extract a TabSession.
@@ -317,55 +787,10 @@ pub fn extract_from_leveldb(path: &Path, source: SessionSource) -> Result<TabSes
             // Look for OneTab's tabGroups structure
             if value_str.contains("tabGroups") {
                 eprintln!("    -> Found tabGroups in entry {}", entry_count);
-                // OneTab stores the value as a JSON-encoded string (double-encoded):
-                // the raw bytes are `"{\\"tabGroups\\":[...]}"` — parse as String first,
-                // then parse the inner JSON as OneTabRoot.
-                let json_to_parse = if value_str.starts_with('"') {
-                    serde_json::from_str::<String>(value_str).unwrap_or_else(|_| value_str.to_string())
-                } else {
-                    value_str.to_string()
-                };
-                if let Ok(root) = serde_json::from_str::<onetab_schema::OneTabRoot>(&json_to_parse) {
-                    for group in root.tab_groups {
-                        // Parse URLs, skipping invalid ones
-                        let tabs: Vec<Tab> = group
-                            .tabs_meta
-                            .into_iter()
-                            .filter_map(|t| {
-                                match url::Url::parse(&t.url) {
-                                    Ok(parsed_url) => Some(Tab {
-                                        id: t.id,
-                                        url: parsed_url,
-                                        title: t.title,
-                                        favicon_url: t.favicon_url,
-                                        added_at: ms_timestamp_to_datetime(group.create_date),
-                                    }),
-                                    Err(e) => {
-                                        eprintln!(
-                                            "warning: skipping invalid URL '{}': {}",
-                                            t.url, e
-                                        );
-                                        None
-                                    }
-                                }
-                            })
-                            .collect();
-
-                        if !tabs.is_empty() {
-                            let tab_group = TabGroup {
-                                id: group.id,
-                                label: group.title,
-                                created_at: ms_timestamp_to_datetime(group.create_date),
-                                tabs,
-                                pinned: group.pinned.unwrap_or(false),
-                                locked: group.locked.unwrap_or(false),
-                                starred: group.starred.unwrap_or(false),
-                            };
-                            if seen_group_ids.insert(tab_group.id.clone()) {
-                                all_tabs_count += tab_group.tabs.len();
-                                found_groups.push(tab_group);
-                            }
-                        }
+                for tab_group in parse_onetab_state(value_str).unwrap_or_default() {
+                    if seen_group_ids.insert(tab_group.id.clone()) {
+                        all_tabs_count += tab_group.tabs.len();
+                        found_groups.push(tab_group);
                     }
                 }
             }
@@ -383,7 +808,7 @@ pub fn extract_from_leveldb(path: &Path, source: SessionSource) -> Result<TabSes
     eprintln!("Recovered {} tab groups, {} tabs total", found_groups.len(), all_tabs_count);
 
     Ok(TabSession {
-        version: 1,
+        version: TabSession::CURRENT_VERSION,
         source,
         groups: found_groups,
         created_at: Utc::now(), // We don't know the original creation time
@@ -391,156 +816,1432 @@ pub fn extract_from_leveldb(path: &Path, source: SessionSource) -> Result<TabSes
     })
 }
 
-/// Format detection for OneTab export files.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum ExportFormat {
-    /// OneTab's native pipe-separated format
-    Pipe,
-    /// tablitz/JS-gist markdown format
-    Markdown,
-}
+pub use writeback::{leveldb_is_locked, serialize_onetab_state, write_to_leveldb, WriteReport};
 
-/// Parse a OneTab export file, detecting format automatically.
-///
-/// Supports two formats:
-///
-/// **Format A - Pipe Format (OneTab native):**
-/// ```text
-/// https://url.com | Tab Title
-/// https://url2.com | Tab Title 2
-///
-/// https://url3.com | Tab Title 3
-/// ```
-/// Empty lines separate tab groups. No timestamps. No group labels.
-///
-/// **Format B - Markdown:**
-/// ```markdown
-/// ---
-/// ## 8 tabs
-/// > Created 3/20/2025, 10:08:46 PM
-///
-/// [Tab Title](https://url.com)
-/// [Tab Title 2](https://url2.com)
-/// ```
-///
-/// # Arguments
-///
-/// * `path` - Path to the export file
-///
-/// # Returns
-///
-/// A `TabSession` containing the tab groups from the export file.
-///
-/// # Errors
+/// Writing tabs back into OneTab's LevelDB store, the inverse of
+/// [`extract_from_leveldb`].
 ///
-/// Returns an error if:
-/// - The file cannot be read
-/// - The format cannot be determined
-/// - Parsing fails
-pub fn parse_onetab_export(path: &Path) -> Result<TabSession> {
-    let content = fs::read_to_string(path).context("Failed to read export file")?;
+/// The store is never modified in place: it is copied, the state is written
+/// into the copy, and the copy then replaces the original, which is kept next
+/// to it as a backup. Writing is refused while another process (the browser)
+/// holds the store's `LOCK` file.
+mod writeback {
+    use super::*;
 
-    let format = detect_format(&content);
+    /// The `chrome.storage.local` key OneTab keeps its state under.
+    const STATE_KEY: &[u8] = b"state";
 
-    let groups = match format {
-        ExportFormat::Pipe => parse_pipe_format(&content)?,
-        ExportFormat::Markdown => parse_markdown_format(&content)?,
-    };
+    /// What [`write_to_leveldb`] did.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct WriteReport {
+        /// The store, now holding the written session
+        pub path: PathBuf,
+        /// The original store, moved aside
+        pub backup: PathBuf,
+        /// The LevelDB key the state was written under
+        pub key: String,
+        /// Groups OneTab held before the write
+        pub groups_replaced: usize,
+        pub groups_written: usize,
+        pub tabs_written: usize,
+    }
 
-    let source = SessionSource::OneTabExport {
-        path: path.to_string_lossy().to_string(),
-    };
+    /// Serializes a session as OneTab's stored state: `{"tabGroups": [...]}`,
+    /// JSON-encoded a second time as a string, as OneTab's LevelDB values are.
+    /// Groups are ordered newest first, the way OneTab lists them.
+    pub fn serialize_onetab_state(session: &TabSession) -> Result<String> {
+        encode_state(session, None)
+    }
 
-    eprintln!(
-        "Imported {} tab groups from {} (format: {:?})",
-        groups.len(),
-        path.display(),
-        format
-    );
+    /// Encodes `session` as OneTab's state, keeping any fields besides
+    /// `tabGroups` from the `existing` value.
+    fn encode_state(session: &TabSession, existing: Option<&str>) -> Result<String> {
+        let mut root = existing
+            .and_then(decode_state)
+            .and_then(|value| match value {
+                serde_json::Value::Object(map) => Some(map),
+                _ => None,
+            })
+            .unwrap_or_default();
 
-    Ok(TabSession {
-        version: 1,
-        source,
-        groups,
-        created_at: Utc::now(),
-        imported_at: Utc::now(),
-    })
-}
+        let mut groups: Vec<&TabGroup> = session.groups.iter().collect();
+        groups.sort_by_key(|g| std::cmp::Reverse(g.created_at));
+        let groups: Vec<onetab_schema::OneTabGroup> = groups.into_iter().map(Into::into).collect();
+        root.insert("tabGroups".to_string(), serde_json::to_value(groups)?);
 
-/// Detect the format of a OneTab export file.
-fn detect_format(content: &str) -> ExportFormat {
-    // Markdown format has characteristic "---" and "## X tabs" patterns
-    if content.contains("---") && content.contains("##") && content.contains("tabs") {
-        return ExportFormat::Markdown;
+        let inner = serde_json::to_string(&serde_json::Value::Object(root))?;
+        Ok(serde_json::to_string(&inner)?)
     }
 
-    // Default to pipe format (OneTab's native export)
-    ExportFormat::Pipe
-}
-
-/// Compute a stable FNV-1a hash of a string (used for deterministic import IDs).
-fn fnv1a_hash(s: &str) -> u64 {
-    let mut hash: u64 = 14695981039346656037;
-    for byte in s.bytes() {
-        hash ^= byte as u64;
-        hash = hash.wrapping_mul(1099511628211);
+    /// Decodes a state value, double-encoded or not, to JSON.
+    fn decode_state(value: &str) -> Option<serde_json::Value> {
+        let inner = if value.starts_with('"') {
+            serde_json::from_str::<String>(value).ok()?
+        } else {
+            value.to_string()
+        };
+        serde_json::from_str(&inner).ok()
     }
-    hash
-}
-
-
-/// Parse OneTab's pipe-separated export format.
-///
-/// Format: `url | title` with blank lines separating groups.
-fn parse_pipe_format(content: &str) -> Result<Vec<TabGroup>> {
-    let file_hash = fnv1a_hash(content);
-    let mut groups = Vec::new();
-    let mut current_tabs = Vec::new();
-    let mut group_index = 0usize;
 
-    for line in content.lines() {
-        let line = line.trim();
+    /// Whether another process holds the `LOCK` file of the LevelDB store at
+    /// `dir`, as a running browser does.
+    ///
+    /// Chromium takes a POSIX record lock on Unix, while other LevelDB tools
+    /// use `flock`; both are checked. On Windows the lock is a `LockFileEx` lock.
+    pub fn leveldb_is_locked(dir: &Path) -> Result<bool> {
+        let lock_path = dir.join("LOCK");
+        let file = match fs::OpenOptions::new().read(true).write(true).open(&lock_path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e).with_context(|| format!("Failed to open {}", lock_path.display())),
+        };
 
-        // Skip empty lines - these separate groups
-        if line.is_empty() {
-            if !current_tabs.is_empty() {
-                let group = TabGroup {
-                    id: format!("pipe-{:x}-g{}", file_hash, group_index),
-                    label: None,
-                    created_at: Utc::now(), // Timestamp not available in pipe format
-                    tabs: current_tabs,
-                    pinned: false,
-                    locked: false,
-                    starred: false,
-                };
-                groups.push(group);
-                current_tabs = Vec::new();
-                group_index += 1;
+        #[cfg(unix)]
+        match rustix::fs::fcntl_lock(&file, rustix::fs::FlockOperation::NonBlockingLockExclusive) {
+            Ok(()) => {}
+            Err(e) if e == rustix::io::Errno::AGAIN || e == rustix::io::Errno::ACCESS => return Ok(true),
+            Err(e) => {
+                return Err(std::io::Error::from(e))
+                    .with_context(|| format!("Failed to check lock on {}", lock_path.display()))
             }
-            continue;
         }
 
-        // Parse "url | title" format
-        if let Some((url_part, title_part)) = line.split_once('|') {
-            let url_str = url_part.trim();
-            let title = title_part.trim().to_string();
-
-            if let Ok(parsed_url) = url::Url::parse(url_str) {
-                current_tabs.push(Tab {
-                    id: format!("pipe-{:x}-g{}-t{}", file_hash, group_index, current_tabs.len()),
-                    url: parsed_url,
-                    title,
-                    favicon_url: None,
-                    added_at: Utc::now(), // Timestamp not available
-                });
-            } else {
-                eprintln!("warning: skipping invalid URL in pipe format: '{}'", url_str);
+        // Closing the file releases whatever lock was taken here
+        match file.try_lock() {
+            Ok(()) => Ok(false),
+            Err(fs::TryLockError::WouldBlock) => Ok(true),
+            Err(fs::TryLockError::Error(e)) => {
+                Err(e).with_context(|| format!("Failed to check lock on {}", lock_path.display()))
             }
         }
     }
 
-    // Don't forget the last group
-    if !current_tabs.is_empty() {
+    /// Replaces the OneTab state in the LevelDB store at `path` with `session`.
+    ///
+    /// The original directory is kept as `<name>.tablitz-backup-<timestamp>`
+    /// beside it; restoring it is a matter of moving it back.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `path` is not a LevelDB store, if the browser (or
+    /// anything else) holds its lock, or if the copy cannot be written.
+    pub fn write_to_leveldb(path: &Path, session: &TabSession) -> Result<WriteReport> {
+        if !path.join("CURRENT").is_file() {
+            anyhow::bail!("Not a LevelDB store: {}", path.display());
+        }
+        if leveldb_is_locked(path)? {
+            anyhow::bail!(
+                "{} is locked by another process; close the browser and try again",
+                path.display()
+            );
+        }
+
+        let name = path
+            .file_name()
+            .context("Store path has no directory name")?
+            .to_string_lossy()
+            .to_string();
+        let parent = path.parent().context("Store path has no parent directory")?;
+        let staging = parent.join(format!("{}.tablitz-new", name));
+        let backup = parent.join(format!(
+            "{}.tablitz-backup-{}",
+            name,
+            Utc::now().format("%Y%m%dT%H%M%S")
+        ));
+        anyhow::ensure!(!backup.exists(), "Backup directory already exists: {}", backup.display());
+
+        if staging.exists() {
+            fs::remove_dir_all(&staging)
+                .with_context(|| format!("Failed to remove {}", staging.display()))?;
+        }
+        copy_dir_recursive(path, &staging).context("Failed to copy LevelDB store")?;
+        let (key, groups_replaced) = match write_state(&staging, session) {
+            Ok(written) => written,
+            Err(e) => {
+                let _ = fs::remove_dir_all(&staging);
+                return Err(e);
+            }
+        };
+
+        fs::rename(path, &backup)
+            .with_context(|| format!("Failed to move {} to {}", path.display(), backup.display()))?;
+        if let Err(e) = fs::rename(&staging, path) {
+            let _ = fs::rename(&backup, path);
+            return Err(e).with_context(|| format!("Failed to move the written store to {}", path.display()));
+        }
+
+        Ok(WriteReport {
+            path: path.to_path_buf(),
+            backup,
+            key: String::from_utf8_lossy(&key).to_string(),
+            groups_replaced,
+            groups_written: session.groups.len(),
+            tabs_written: session.total_tab_count(),
+        })
+    }
+
+    /// Writes `session` over the OneTab state in the LevelDB at `dir`,
+    /// returning the key written and how many groups it replaced.
+    fn write_state(dir: &Path, session: &TabSession) -> Result<(Vec<u8>, usize)> {
+        let opts = rusty_leveldb::Options { create_if_missing: false, ..Default::default() };
+        let mut db = rusty_leveldb::DB::open(dir, opts).context("Failed to open LevelDB copy")?;
+
+        // Overwrite whichever key already holds OneTab's state
+        let mut existing = None;
+        let mut iter = db.new_iter().context("Failed to iterate LevelDB copy")?;
+        let (mut key, mut value) = (Vec::new(), Vec::new());
+        iter.seek_to_first();
+        while iter.valid() {
+            iter.current(&mut key, &mut value);
+            if let Ok(value_str) = std::str::from_utf8(&value) {
+                if let Some(groups) = parse_onetab_state(value_str) {
+                    existing = Some((key.clone(), value_str.to_string(), groups.len()));
+                    break;
+                }
+            }
+            iter.advance();
+        }
+        drop(iter);
+
+        let (key, old_value, replaced) = match existing {
+            Some((key, value, replaced)) => (key, Some(value), replaced),
+            None => (STATE_KEY.to_vec(), None, 0),
+        };
+        let value = encode_state(session, old_value.as_deref())?;
+        db.put(&key, value.as_bytes()).context("Failed to write OneTab state")?;
+        db.flush().context("Failed to flush LevelDB copy")?;
+        Ok((key, replaced))
+    }
+}
+
+pub use salvage::{
+    extract_leveldb_version, salvage_from_leveldb, salvage_leveldb, SalvageFileKind,
+    SalvageFileReport, SalvageReport, SalvagedValue,
+};
+
+/// Salvage mode: reads a LevelDB directory's `.log` and `.ldb`/`.sst` files
+/// directly, without `MANIFEST` or `CURRENT`, for stores `rusty_leveldb`
+/// refuses to open.
+///
+/// Log files are 32 KiB blocks of checksummed records that reassemble into
+/// write batches; tables are checksummed (optionally snappy-compressed)
+/// blocks of prefix-compressed entries behind an index block. Anything whose
+/// checksum fails is skipped and counted, and every `tabGroups` value is kept,
+/// including versions a later write overwrote or deleted. Those versions are
+/// identified by their LevelDB sequence number, which is how
+/// `recover --version <seq>` picks one.
+mod salvage {
+    use super::*;
+
+    const LOG_BLOCK_SIZE: usize = 32 * 1024;
+    const LOG_HEADER_SIZE: usize = 7;
+    const LOG_FULL: u8 = 1;
+    const LOG_FIRST: u8 = 2;
+    const LOG_MIDDLE: u8 = 3;
+    const LOG_LAST: u8 = 4;
+
+    const TABLE_MAGIC: u64 = 0xdb47_7524_8b80_fb57;
+    const TABLE_FOOTER_SIZE: usize = 48;
+    const BLOCK_TRAILER_SIZE: usize = 5;
+    /// How far [`find_block`] looks for a block's end before giving up. Blocks
+    /// are cut at about 4 KiB, but one holding a single large value (such as
+    /// the whole OneTab state) can be far bigger.
+    const MAX_SCANNED_BLOCK_SIZE: usize = 64 * 1024 * 1024;
+
+    const VALUE_TYPE_DELETION: u8 = 0;
+    const VALUE_TYPE_VALUE: u8 = 1;
+
+    /// A decoded `(key, value)` entry from a table block.
+    type BlockEntry = (Vec<u8>, Vec<u8>);
+
+    /// The kind of LevelDB file a report covers.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum SalvageFileKind {
+        /// Write-ahead log (`*.log`)
+        Log,
+        /// Sorted table (`*.ldb` / `*.sst`)
+        Table,
+    }
+
+    /// What salvage found in one file.
+    #[derive(Debug, Clone)]
+    pub struct SalvageFileReport {
+        pub path: PathBuf,
+        pub kind: SalvageFileKind,
+        /// Key-value records read
+        pub records: usize,
+        /// Log records or table blocks skipped for a bad checksum or corrupt framing
+        pub skipped: usize,
+        /// Records holding a OneTab `tabGroups` value
+        pub tab_group_values: usize,
+        /// Why the rest of the file could not be read, if it couldn't
+        pub error: Option<String>,
+    }
+
+    /// One version of a OneTab `tabGroups` value.
+    #[derive(Debug, Clone)]
+    pub struct SalvagedValue {
+        /// The file the version was found in
+        pub path: PathBuf,
+        /// LevelDB sequence number; higher is newer
+        pub sequence: u64,
+        pub key: String,
+        pub groups: Vec<TabGroup>,
+    }
+
+    impl SalvagedValue {
+        /// Total number of tabs across this version's groups.
+        pub fn tab_count(&self) -> usize {
+            self.groups.iter().map(|g| g.tabs.len()).sum()
+        }
+    }
+
+    /// The result of salvaging a LevelDB directory.
+    #[derive(Debug, Clone, Default)]
+    pub struct SalvageReport {
+        pub files: Vec<SalvageFileReport>,
+        /// Every distinct `tabGroups` version found, oldest first
+        pub values: Vec<SalvagedValue>,
+    }
+
+    impl SalvageReport {
+        /// Merges all versions into one list of groups. The newest version of
+        /// each group wins; groups that only survive in stale versions are kept.
+        pub fn merged_groups(&self) -> Vec<TabGroup> {
+            let mut seen_group_ids = std::collections::HashSet::new();
+            let mut groups = Vec::new();
+            for value in self.values.iter().rev() {
+                for group in &value.groups {
+                    if seen_group_ids.insert(group.id.clone()) {
+                        groups.push(group.clone());
+                    }
+                }
+            }
+            groups.sort_by_key(|g| std::cmp::Reverse(g.created_at));
+            groups
+        }
+    }
+
+    /// Salvages every `tabGroups` value from the log and table files in `path`.
+    ///
+    /// Unlike [`extract_from_leveldb`], this never opens the database, so it
+    /// works without `CURRENT` / `MANIFEST` and survives truncated or
+    /// partially corrupted files.
+    pub fn salvage_leveldb(path: &Path) -> Result<SalvageReport> {
+        let mut files: Vec<(PathBuf, SalvageFileKind)> = fs::read_dir(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter_map(|p| {
+                let kind = match p.extension()?.to_str()? {
+                    "log" => SalvageFileKind::Log,
+                    "ldb" | "sst" => SalvageFileKind::Table,
+                    _ => return None,
+                };
+                Some((p, kind))
+            })
+            .collect();
+        if files.is_empty() {
+            anyhow::bail!("No .log, .ldb or .sst files in {}", path.display());
+        }
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut report = SalvageReport::default();
+        for (file, kind) in files {
+            let mut scan = FileScan {
+                report: SalvageFileReport {
+                    path: file.clone(),
+                    kind,
+                    records: 0,
+                    skipped: 0,
+                    tab_group_values: 0,
+                    error: None,
+                },
+                values: Vec::new(),
+            };
+            let result = fs::read(&file)
+                .with_context(|| format!("Failed to read {}", file.display()))
+                .and_then(|data| match kind {
+                    SalvageFileKind::Log => {
+                        scan.read_log(&data);
+                        Ok(())
+                    }
+                    SalvageFileKind::Table => scan.read_table(&data),
+                });
+            if let Err(e) = result {
+                scan.report.error = Some(format!("{:#}", e));
+            }
+            report.files.push(scan.report);
+            report.values.extend(scan.values);
+        }
+        report.values.sort_by_key(|v| v.sequence);
+        // A write can sit in both a log and the table it was flushed to
+        report.values.dedup_by_key(|v| v.sequence);
+        Ok(report)
+    }
+
+    /// Salvages a LevelDB directory into a TabSession, printing what was
+    /// found in each file.
+    pub fn salvage_from_leveldb(path: &Path, source: SessionSource) -> Result<TabSession> {
+        let report = salvage_leveldb(path)?;
+        for file in &report.files {
+            let name = file.path.file_name().unwrap_or_default().to_string_lossy();
+            eprintln!(
+                "  {}: {} records, {} tabGroups values, {} skipped",
+                name, file.records, file.tab_group_values, file.skipped
+            );
+            if let Some(error) = &file.error {
+                eprintln!("    stopped early: {}", error);
+            }
+        }
+
+        let groups = report.merged_groups();
+        let tab_count: usize = groups.iter().map(|g| g.tabs.len()).sum();
+        eprintln!(
+            "Salvaged {} tabGroups versions: {} tab groups, {} tabs total",
+            report.values.len(),
+            groups.len(),
+            tab_count
+        );
+
+        Ok(TabSession {
+            version: TabSession::CURRENT_VERSION,
+            source,
+            groups,
+            created_at: Utc::now(), // We don't know the original creation time
+            imported_at: Utc::now(),
+        })
+    }
+
+    /// Extracts one historical version of OneTab's state, by sequence number.
+    pub fn extract_leveldb_version(path: &Path, sequence: u64, source: SessionSource) -> Result<TabSession> {
+        let report = salvage_leveldb(path)?;
+        let Some(version) = report.values.iter().find(|v| v.sequence == sequence) else {
+            let available: Vec<String> = report.values.iter().map(|v| v.sequence.to_string()).collect();
+            anyhow::bail!(
+                "No tabGroups version with sequence {} (available: {})",
+                sequence,
+                if available.is_empty() { "none".to_string() } else { available.join(", ") }
+            );
+        };
+        eprintln!(
+            "Recovered version {}: {} tab groups, {} tabs total",
+            sequence,
+            version.groups.len(),
+            version.tab_count()
+        );
+
+        Ok(TabSession {
+            version: TabSession::CURRENT_VERSION,
+            source,
+            groups: version.groups.clone(),
+            created_at: Utc::now(), // We don't know the original creation time
+            imported_at: Utc::now(),
+        })
+    }
+
+    /// Scan state for one file.
+    struct FileScan {
+        report: SalvageFileReport,
+        values: Vec<SalvagedValue>,
+    }
+
+    impl FileScan {
+        fn record(&mut self, sequence: u64, key: &[u8], value: &[u8]) {
+            self.report.records += 1;
+            let Ok(value_str) = std::str::from_utf8(value) else { return };
+            if !value_str.contains("tabGroups") {
+                return;
+            }
+            if let Some(groups) = parse_onetab_state(value_str) {
+                self.report.tab_group_values += 1;
+                self.values.push(SalvagedValue {
+                    path: self.report.path.clone(),
+                    sequence,
+                    key: String::from_utf8_lossy(key).into_owned(),
+                    groups,
+                });
+            }
+        }
+
+        /// Reads a write-ahead log, skipping records that fail their checksum.
+        /// A bad record loses the rest of its 32 KiB block, as in LevelDB's
+        /// own recovery.
+        fn read_log(&mut self, data: &[u8]) {
+            let mut pos = 0;
+            let mut pending: Option<Vec<u8>> = None;
+            while pos + LOG_HEADER_SIZE <= data.len() {
+                let block_left = LOG_BLOCK_SIZE - pos % LOG_BLOCK_SIZE;
+                if block_left < LOG_HEADER_SIZE {
+                    // Block trailer padding
+                    pos += block_left;
+                    continue;
+                }
+                let header = &data[pos..pos + LOG_HEADER_SIZE];
+                let expected = u32::from_le_bytes(header[..4].try_into().unwrap());
+                let len = u16::from_le_bytes([header[4], header[5]]) as usize;
+                let kind = header[6];
+                if kind == 0 && len == 0 {
+                    // Zero-filled (preallocated) space
+                    pos += block_left;
+                    continue;
+                }
+
+                let start = pos + LOG_HEADER_SIZE;
+                let end = start + len;
+                if len > block_left - LOG_HEADER_SIZE || end > data.len() {
+                    self.report.skipped += 1;
+                    pending = None;
+                    pos += block_left;
+                    continue;
+                }
+                let payload = &data[start..end];
+                if unmask_crc(expected) != crc32c(&[&[kind], payload]) {
+                    self.report.skipped += 1;
+                    pending = None;
+                    pos += block_left;
+                    continue;
+                }
+                pos = end;
+
+                match kind {
+                    LOG_FULL => {
+                        pending = None;
+                        self.read_batch(payload);
+                    }
+                    LOG_FIRST => pending = Some(payload.to_vec()),
+                    LOG_MIDDLE => match &mut pending {
+                        Some(batch) => batch.extend_from_slice(payload),
+                        None => self.report.skipped += 1,
+                    },
+                    LOG_LAST => match pending.take() {
+                        Some(mut batch) => {
+                            batch.extend_from_slice(payload);
+                            self.read_batch(&batch);
+                        }
+                        None => self.report.skipped += 1,
+                    },
+                    _ => self.report.skipped += 1,
+                }
+            }
+        }
+
+        /// Reads a write batch: sequence, count, then tagged puts and deletes.
+        fn read_batch(&mut self, batch: &[u8]) {
+            if batch.len() < 12 {
+                self.report.skipped += 1;
+                return;
+            }
+            let sequence = u64::from_le_bytes(batch[..8].try_into().unwrap());
+            let count = u32::from_le_bytes(batch[8..12].try_into().unwrap()) as u64;
+            let mut rest = &batch[12..];
+            for i in 0..count {
+                let parsed = (|| {
+                    let (&tag, tail) = rest.split_first()?;
+                    rest = tail;
+                    let key = read_length_prefixed(&mut rest)?;
+                    let value = match tag {
+                        VALUE_TYPE_VALUE => Some(read_length_prefixed(&mut rest)?),
+                        VALUE_TYPE_DELETION => None,
+                        _ => return None,
+                    };
+                    Some((key, value))
+                })();
+                match parsed {
+                    Some((key, Some(value))) => self.record(sequence + i, key, value),
+                    Some((_, None)) => {}
+                    None => {
+                        self.report.skipped += 1;
+                        return;
+                    }
+                }
+            }
+        }
+
+        /// Reads a sorted table through its index block. If the footer or
+        /// index is unreadable, falls back to finding data blocks by checksum.
+        fn read_table(&mut self, data: &[u8]) -> Result<()> {
+            let handles = match table_index(data) {
+                Ok(handles) => handles,
+                Err(e) => {
+                    eprintln!("warning: {:#}; scanning blocks by checksum", e);
+                    self.report.skipped += 1;
+                    return self.scan_table_blocks(data);
+                }
+            };
+            for (offset, size) in handles {
+                match read_block(data, offset, size).and_then(|b| block_entries(&b)) {
+                    Ok(entries) => self.read_table_entries(entries),
+                    Err(_) => self.report.skipped += 1,
+                }
+            }
+            Ok(())
+        }
+
+        /// Data blocks are laid out back to back from the start of the file,
+        /// each followed by its trailer, so a block ends at the first position
+        /// whose trailer checksum matches.
+        fn scan_table_blocks(&mut self, data: &[u8]) -> Result<()> {
+            let mut start = 0;
+            while start + BLOCK_TRAILER_SIZE < data.len() {
+                let Some((end, entries)) = find_block(data, start) else {
+                    // Whatever is left may just be the index and footer
+                    if data.len() - start > TABLE_FOOTER_SIZE {
+                        anyhow::bail!("no valid block found after offset {}", start);
+                    }
+                    break;
+                };
+                self.read_table_entries(entries);
+                start = end + BLOCK_TRAILER_SIZE;
+            }
+            Ok(())
+        }
+
+        fn read_table_entries(&mut self, entries: Vec<BlockEntry>) {
+            for (internal_key, value) in entries {
+                // Internal keys end in an 8-byte (sequence << 8 | value type) tag
+                let Some(split) = internal_key.len().checked_sub(8) else {
+                    self.report.skipped += 1;
+                    continue;
+                };
+                let tag = u64::from_le_bytes(internal_key[split..].try_into().unwrap());
+                if tag as u8 == VALUE_TYPE_VALUE {
+                    self.record(tag >> 8, &internal_key[..split], &value);
+                }
+            }
+        }
+    }
+
+    /// Reads the footer and index block, returning each data block's
+    /// `(offset, size)`.
+    fn table_index(data: &[u8]) -> Result<Vec<(usize, usize)>> {
+        let footer_start = data
+            .len()
+            .checked_sub(TABLE_FOOTER_SIZE)
+            .context("file is too short to be a table")?;
+        let footer = &data[footer_start..];
+        let magic = u64::from_le_bytes(footer[40..].try_into().unwrap());
+        if magic != TABLE_MAGIC {
+            anyhow::bail!("bad table magic number");
+        }
+        let mut handles = &footer[..40];
+        read_block_handle(&mut handles).context("bad metaindex handle")?;
+        let (offset, size) = read_block_handle(&mut handles).context("bad index handle")?;
+        let index = read_block(data, offset, size).context("unreadable index block")?;
+        block_entries(&index)?
+            .into_iter()
+            .map(|(_, handle)| read_block_handle(&mut handle.as_slice()).context("bad block handle"))
+            .collect()
+    }
+
+    /// Finds the block starting at `start` by its checksum, returning where
+    /// its contents end and its entries.
+    ///
+    /// Only the first [`MAX_SCANNED_BLOCK_SIZE`] bytes are searched, so that
+    /// garbage doesn't cost a scan to the end of the file.
+    fn find_block(data: &[u8], start: usize) -> Option<(usize, Vec<BlockEntry>)> {
+        let mut crc = 0;
+        let limit = data
+            .len()
+            .checked_sub(BLOCK_TRAILER_SIZE)?
+            .min(start.saturating_add(MAX_SCANNED_BLOCK_SIZE));
+        for end in start..limit {
+            let trailer = &data[end..end + BLOCK_TRAILER_SIZE];
+            let expected = u32::from_le_bytes(trailer[1..].try_into().unwrap());
+            if unmask_crc(expected) == crc::crc32::update(crc, &crc::crc32::CASTAGNOLI_TABLE, &trailer[..1]) {
+                // Filter blocks checksum like any other but hold no entries
+                if let Ok(block) = read_block(data, start, end - start) {
+                    return Some((end, block_entries(&block).unwrap_or_default()));
+                }
+            }
+            crc = crc::crc32::update(crc, &crc::crc32::CASTAGNOLI_TABLE, &data[end..end + 1]);
+        }
+        None
+    }
+
+    /// Reads and checks one table block, decompressing it if needed.
+    fn read_block(data: &[u8], offset: usize, size: usize) -> Result<Vec<u8>> {
+        let end = offset.checked_add(size).context("block handle overflows")?;
+        let trailer_end = end.checked_add(BLOCK_TRAILER_SIZE).context("block handle overflows")?;
+        let trailer = data
+            .get(end..trailer_end)
+            .context("block runs past the end of the file")?;
+        let contents = data.get(offset..end).context("block runs past the end of the file")?;
+        let expected = u32::from_le_bytes(trailer[1..].try_into().unwrap());
+        if unmask_crc(expected) != crc32c(&[contents, &trailer[..1]]) {
+            anyhow::bail!("bad block checksum at offset {}", offset);
+        }
+        match trailer[0] {
+            0 => Ok(contents.to_vec()),
+            1 => snap::raw::Decoder::new()
+                .decompress_vec(contents)
+                .context("bad snappy block"),
+            other => anyhow::bail!("unknown block compression {}", other),
+        }
+    }
+
+    /// Decodes a block's prefix-compressed entries.
+    fn block_entries(block: &[u8]) -> Result<Vec<BlockEntry>> {
+        let corrupt = || anyhow::anyhow!("corrupt block");
+        let num_restarts_at = block.len().checked_sub(4).ok_or_else(corrupt)?;
+        let num_restarts = u32::from_le_bytes(block[num_restarts_at..].try_into().unwrap()) as usize;
+        let entries_end = num_restarts
+            .checked_mul(4)
+            .and_then(|n| num_restarts_at.checked_sub(n))
+            .ok_or_else(corrupt)?;
+
+        let mut rest = &block[..entries_end];
+        let mut key = Vec::new();
+        let mut entries = Vec::new();
+        while !rest.is_empty() {
+            let shared = read_varint(&mut rest).ok_or_else(corrupt)? as usize;
+            let non_shared = read_varint(&mut rest).ok_or_else(corrupt)? as usize;
+            let value_len = read_varint(&mut rest).ok_or_else(corrupt)? as usize;
+            if shared > key.len() || non_shared.saturating_add(value_len) > rest.len() {
+                return Err(corrupt());
+            }
+            key.truncate(shared);
+            key.extend_from_slice(&rest[..non_shared]);
+            let value = rest[non_shared..non_shared + value_len].to_vec();
+            rest = &rest[non_shared + value_len..];
+            entries.push((key.clone(), value));
+        }
+        Ok(entries)
+    }
+
+    fn read_block_handle(input: &mut &[u8]) -> Option<(usize, usize)> {
+        let offset = read_varint(input)?;
+        let size = read_varint(input)?;
+        Some((usize::try_from(offset).ok()?, usize::try_from(size).ok()?))
+    }
+
+    fn read_length_prefixed<'a>(input: &mut &'a [u8]) -> Option<&'a [u8]> {
+        let len = usize::try_from(read_varint(input)?).ok()?;
+        let bytes = input.get(..len)?;
+        *input = &input[len..];
+        Some(bytes)
+    }
+
+    fn read_varint(input: &mut &[u8]) -> Option<u64> {
+        let mut value = 0u64;
+        for (i, &byte) in input.iter().enumerate().take(10) {
+            value |= ((byte & 0x7f) as u64) << (7 * i);
+            if byte & 0x80 == 0 {
+                *input = &input[i + 1..];
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    fn crc32c(parts: &[&[u8]]) -> u32 {
+        parts
+            .iter()
+            .fold(0, |crc, part| crc::crc32::update(crc, &crc::crc32::CASTAGNOLI_TABLE, part))
+    }
+
+    /// LevelDB stores checksums masked, since CRCs of data containing CRCs are weak.
+    fn unmask_crc(masked: u32) -> u32 {
+        let rot = masked.wrapping_sub(0xa282_ead8);
+        rot.rotate_left(15)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn mask_crc(crc: u32) -> u32 {
+            crc.rotate_right(15).wrapping_add(0xa282_ead8)
+        }
+
+        #[test]
+        fn test_crc_mask_roundtrip() {
+            let crc = crc32c(&[b"hello world"]);
+            assert_eq!(crc, crc::crc32::checksum_castagnoli(b"hello world"));
+            assert_ne!(mask_crc(crc), crc);
+            assert_eq!(unmask_crc(mask_crc(crc)), crc);
+        }
+
+        #[test]
+        fn test_read_varint() {
+            let mut input: &[u8] = &[0xac, 0x02, 0x05];
+            assert_eq!(read_varint(&mut input), Some(300));
+            assert_eq!(input, &[0x05]);
+            assert_eq!(read_varint(&mut &[0x80u8][..]), None);
+        }
+
+        #[test]
+        fn test_block_entries_prefix_compression() {
+            // "state" -> "a", "stats" -> "b" (sharing "stat"), one restart at 0
+            let mut block = vec![0, 5, 1];
+            block.extend(b"statea");
+            block.extend([4, 1, 1]);
+            block.extend(b"sb");
+            block.extend(0u32.to_le_bytes());
+            block.extend(1u32.to_le_bytes());
+            let entries = block_entries(&block).unwrap();
+            assert_eq!(
+                entries,
+                vec![(b"state".to_vec(), b"a".to_vec()), (b"stats".to_vec(), b"b".to_vec())]
+            );
+            assert!(block_entries(&block[..6]).is_err());
+        }
+
+        #[test]
+        fn test_read_block_rejects_out_of_range_handles() {
+            let data = [0u8; 16];
+            assert!(read_block(&data, 4, 20).is_err());
+            assert!(read_block(&data, 20, 0).is_err());
+            assert!(read_block(&data, usize::MAX - 2, 1).is_err());
+            assert!(read_block(&data, 1, usize::MAX - 2).is_err());
+        }
+
+        #[test]
+        fn test_find_block_in_garbage() {
+            assert!(find_block(&[0xa5; 4096], 0).is_none());
+            assert!(find_block(&[0xa5; 3], 0).is_none());
+        }
+    }
+}
+
+/// Decoder for SpiderMonkey's structured clone format, which Firefox uses for
+/// IndexedDB values.
+///
+/// The data is a sequence of little-endian 64-bit words. Each word is either a
+/// raw `f64` or a `(tag << 32) | data` pair; strings are followed by their
+/// characters padded to a word boundary, and objects / arrays by key-value
+/// pairs up to an end-of-keys marker. Only the JSON-like subset is supported.
+mod structured_clone {
+    use anyhow::{bail, Context, Result};
+    use serde_json::{Map, Number, Value};
+
+    const SCTAG_FLOAT_MAX: u32 = 0xFFF0_0000;
+    const SCTAG_HEADER: u32 = 0xFFF1_0000;
+    const SCTAG_NULL: u32 = 0xFFFF_0000;
+    const SCTAG_UNDEFINED: u32 = 0xFFFF_0001;
+    const SCTAG_BOOLEAN: u32 = 0xFFFF_0002;
+    const SCTAG_INT32: u32 = 0xFFFF_0003;
+    const SCTAG_STRING: u32 = 0xFFFF_0004;
+    const SCTAG_DATE_OBJECT: u32 = 0xFFFF_0005;
+    const SCTAG_ARRAY_OBJECT: u32 = 0xFFFF_0007;
+    const SCTAG_OBJECT_OBJECT: u32 = 0xFFFF_0008;
+    const SCTAG_BOOLEAN_OBJECT: u32 = 0xFFFF_000A;
+    const SCTAG_STRING_OBJECT: u32 = 0xFFFF_000B;
+    const SCTAG_NUMBER_OBJECT: u32 = 0xFFFF_000C;
+    const SCTAG_BACK_REFERENCE_OBJECT: u32 = 0xFFFF_000D;
+    const SCTAG_END_OF_KEYS: u32 = 0xFFFF_0013;
+
+    /// Set on a string's length when its characters are Latin-1 rather than UTF-16.
+    const LATIN1_FLAG: u32 = 0x8000_0000;
+
+    /// Largest array index accepted, to refuse absurd allocations on corrupt input.
+    const MAX_ARRAY_INDEX: u64 = 10_000_000;
+
+    /// Decodes a structured clone buffer into a JSON value.
+    pub fn decode(bytes: &[u8]) -> Result<Value> {
+        let mut reader = Reader { bytes, pos: 0, objects: Vec::new() };
+        if reader.peek_tag()? == SCTAG_HEADER {
+            reader.read_word()?;
+        }
+        reader.read_value()
+    }
+
+    struct Reader<'a> {
+        bytes: &'a [u8],
+        pos: usize,
+        /// Objects and arrays in the order they started, for back references.
+        objects: Vec<Option<Value>>,
+    }
+
+    impl Reader<'_> {
+        fn read_word(&mut self) -> Result<u64> {
+            let chunk = self
+                .bytes
+                .get(self.pos..self.pos + 8)
+                .context("structured clone data ends mid-value")?;
+            self.pos += 8;
+            Ok(u64::from_le_bytes(chunk.try_into().unwrap()))
+        }
+
+        fn peek_tag(&self) -> Result<u32> {
+            let chunk = self
+                .bytes
+                .get(self.pos..self.pos + 8)
+                .context("structured clone data ends mid-value")?;
+            Ok((u64::from_le_bytes(chunk.try_into().unwrap()) >> 32) as u32)
+        }
+
+        fn read_value(&mut self) -> Result<Value> {
+            let word = self.read_word()?;
+            let (tag, data) = ((word >> 32) as u32, word as u32);
+            if tag <= SCTAG_FLOAT_MAX {
+                return Ok(number(f64::from_bits(word)));
+            }
+            Ok(match tag {
+                SCTAG_NULL | SCTAG_UNDEFINED => Value::Null,
+                SCTAG_BOOLEAN | SCTAG_BOOLEAN_OBJECT => Value::Bool(data != 0),
+                SCTAG_INT32 => Value::from(data as i32),
+                SCTAG_STRING | SCTAG_STRING_OBJECT => Value::String(self.read_string(data)?),
+                SCTAG_DATE_OBJECT | SCTAG_NUMBER_OBJECT => number(f64::from_bits(self.read_word()?)),
+                SCTAG_ARRAY_OBJECT => self.read_array()?,
+                SCTAG_OBJECT_OBJECT => self.read_object()?,
+                SCTAG_BACK_REFERENCE_OBJECT => self
+                    .objects
+                    .get(data as usize)
+                    .cloned()
+                    .flatten()
+                    .context("structured clone back reference to an unknown object")?,
+                other => bail!("unsupported structured clone tag {:#x}", other),
+            })
+        }
+
+        fn read_string(&mut self, data: u32) -> Result<String> {
+            let len = (data & !LATIN1_FLAG) as usize;
+            let latin1 = data & LATIN1_FLAG != 0;
+            let byte_len = if latin1 { len } else { len * 2 };
+            let chars = self
+                .bytes
+                .get(self.pos..self.pos + byte_len)
+                .context("structured clone string runs past the end of the data")?;
+            self.pos += byte_len.div_ceil(8) * 8;
+            Ok(if latin1 {
+                chars.iter().map(|&b| b as char).collect()
+            } else {
+                let units: Vec<u16> = chars
+                    .chunks_exact(2)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                    .collect();
+                String::from_utf16_lossy(&units)
+            })
+        }
+
+        /// Reads key-value pairs up to the end-of-keys marker.
+        fn read_entries(&mut self) -> Result<Vec<(Value, Value)>> {
+            let mut entries = Vec::new();
+            while self.peek_tag()? != SCTAG_END_OF_KEYS {
+                let key = self.read_value()?;
+                let value = self.read_value()?;
+                entries.push((key, value));
+            }
+            self.read_word()?;
+            Ok(entries)
+        }
+
+        fn read_array(&mut self) -> Result<Value> {
+            let slot = self.start_object();
+            let mut items = Vec::new();
+            for (key, value) in self.read_entries()? {
+                let index = key
+                    .as_u64()
+                    .filter(|&i| i <= MAX_ARRAY_INDEX)
+                    .context("structured clone array has an invalid index")? as usize;
+                if items.len() <= index {
+                    items.resize(index + 1, Value::Null);
+                }
+                items[index] = value;
+            }
+            Ok(self.finish_object(slot, Value::Array(items)))
+        }
+
+        fn read_object(&mut self) -> Result<Value> {
+            let slot = self.start_object();
+            let mut map = Map::new();
+            for (key, value) in self.read_entries()? {
+                let key = match key {
+                    Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                map.insert(key, value);
+            }
+            Ok(self.finish_object(slot, Value::Object(map)))
+        }
+
+        fn start_object(&mut self) -> usize {
+            self.objects.push(None);
+            self.objects.len() - 1
+        }
+
+        fn finish_object(&mut self, slot: usize, value: Value) -> Value {
+            self.objects[slot] = Some(value.clone());
+            value
+        }
+    }
+
+    /// JS numbers are doubles; keep integral ones integral so they
+    /// deserialize into integer fields such as OneTab's `createDate`.
+    fn number(n: f64) -> Value {
+        if n.fract() == 0.0 && n.abs() < 9.007_199_254_740_992e15 {
+            Value::from(n as i64)
+        } else {
+            Number::from_f64(n).map_or(Value::Null, Value::Number)
+        }
+    }
+}
+
+pub use firefox::{
+    extract_from_firefox_idb, firefox_root_dir, list_firefox_profiles, resolve_firefox_idb_path, resolve_firefox_profile,
+    FirefoxProfile,
+};
+
+/// Recovery from Firefox, where OneTab keeps `browser.storage.local` in an
+/// IndexedDB SQLite database inside the profile:
+///
+/// ```text
+/// <profile>/storage/default/moz-extension+++<uuid>^userContextId=…/idb/*.sqlite
+/// ```
+///
+/// `<uuid>` is the per-profile internal ID Firefox assigned to the add-on,
+/// recorded in `prefs.js`. Values are snappy-compressed structured clones;
+/// large ones live in a sibling `*.files` directory instead.
+mod firefox {
+    use super::*;
+    use std::collections::HashMap;
+    use std::io::Read;
+
+    /// Magic bytes that open a snappy framed stream.
+    const SNAPPY_STREAM_MAGIC: &[u8] = b"\xff\x06\x00\x00sNaPpY";
+
+    /// A profile listed in Firefox's `profiles.ini`.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct FirefoxProfile {
+        /// The profile's display name (`Name=`)
+        pub name: String,
+        /// Absolute path to the profile directory
+        pub path: PathBuf,
+        /// Whether Firefox opens this profile by default
+        pub is_default: bool,
+    }
+
+    /// Returns the directory holding a Firefox-style browser's `profiles.ini`:
+    /// the first of its [`Browser::data_dirs`] that has one, else the first.
+    pub fn firefox_root_dir(browser: &Browser) -> Result<PathBuf> {
+        let roots = browser.data_dirs();
+        roots
+            .iter()
+            .find(|root| root.join("profiles.ini").is_file())
+            .or(roots.first())
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Unsupported platform or could not find base directory"))
+    }
+
+    /// Lists the profiles in `<root>/profiles.ini`.
+    ///
+    /// The default profile is the one named by an `[Install…]` section, which
+    /// is what current Firefox versions open, falling back to `Default=1`.
+    pub fn list_firefox_profiles(root: &Path) -> Result<Vec<FirefoxProfile>> {
+        let ini_path = root.join("profiles.ini");
+        let content = fs::read_to_string(&ini_path)
+            .with_context(|| format!("Failed to read {}", ini_path.display()))?;
+        let sections = parse_ini(&content);
+
+        let install_default = sections
+            .iter()
+            .filter(|(name, _)| name.starts_with("Install"))
+            .find_map(|(_, keys)| keys.get("Default"));
+
+        let mut profiles = Vec::new();
+        for (name, keys) in &sections {
+            if !name.starts_with("Profile") {
+                continue;
+            }
+            let Some(rel_path) = keys.get("Path") else { continue };
+            let path = if keys.get("IsRelative").is_none_or(|v| v == "1") {
+                root.join(rel_path)
+            } else {
+                PathBuf::from(rel_path)
+            };
+            let is_default = match install_default {
+                Some(default) => default == rel_path,
+                None => keys.get("Default").is_some_and(|v| v == "1"),
+            };
+            profiles.push(FirefoxProfile {
+                name: keys.get("Name").unwrap_or(rel_path).clone(),
+                path,
+                is_default,
+            });
+        }
+        Ok(profiles)
+    }
+
+    /// Finds a profile by name or directory name. `"Default"` selects the
+    /// default profile unless one is actually named that.
+    pub fn resolve_firefox_profile(root: &Path, profile: &str) -> Result<FirefoxProfile> {
+        let profiles = list_firefox_profiles(root)?;
+        let by_name = profiles.iter().find(|p| {
+            p.name.eq_ignore_ascii_case(profile)
+                || p.path.file_name().is_some_and(|f| f.to_string_lossy() == profile)
+        });
+        let found = match by_name {
+            Some(p) => Some(p),
+            None if profile.eq_ignore_ascii_case("default") => {
+                profiles.iter().find(|p| p.is_default).or(profiles.first())
+            }
+            None => None,
+        };
+        found.cloned().with_context(|| {
+            let names: Vec<_> = profiles.iter().map(|p| p.name.as_str()).collect();
+            format!("No Firefox profile '{}' (available: {})", profile, names.join(", "))
+        })
+    }
+
+    /// Resolves the IndexedDB directory holding OneTab's storage in a profile,
+    /// using the first of `addon_ids` installed there.
+    pub fn resolve_firefox_idb_path(profile_dir: &Path, addon_ids: &[String]) -> Result<PathBuf> {
+        let uuid = onetab_internal_uuid(profile_dir, addon_ids)?;
+        let storage = profile_dir.join("storage").join("default");
+        let prefix = format!("moz-extension+++{}", uuid);
+        let entries = fs::read_dir(&storage)
+            .with_context(|| format!("Failed to read {}", storage.display()))?;
+        let mut candidates: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().starts_with(&prefix))
+            .map(|e| e.path().join("idb"))
+            .filter(|p| p.is_dir())
+            .collect();
+        candidates.sort();
+        candidates.into_iter().next().with_context(|| {
+            format!("OneTab has no IndexedDB storage in {}", storage.display())
+        })
+    }
+
+    /// Reads OneTab's internal add-on UUID from `prefs.js`.
+    fn onetab_internal_uuid(profile_dir: &Path, addon_ids: &[String]) -> Result<String> {
+        let prefs_path = profile_dir.join("prefs.js");
+        let prefs = fs::read_to_string(&prefs_path)
+            .with_context(|| format!("Failed to read {}", prefs_path.display()))?;
+        // user_pref("extensions.webextensions.uuids", "{\"extension@one-tab.com\":\"…\"}");
+        let literal = prefs
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("user_pref(\"extensions.webextensions.uuids\","))
+            .find_map(|rest| rest.trim().strip_suffix(");"))
+            .context("prefs.js does not list any extension UUIDs")?;
+        let json: String = serde_json::from_str(literal.trim())
+            .context("Failed to parse extensions.webextensions.uuids")?;
+        let uuids: HashMap<String, String> = serde_json::from_str(&json)
+            .context("Failed to parse extensions.webextensions.uuids")?;
+        addon_ids
+            .iter()
+            .find_map(|id| uuids.get(id))
+            .cloned()
+            .with_context(|| {
+                format!("OneTab ({}) is not installed in {}", addon_ids.join(", "), profile_dir.display())
+            })
+    }
+
+    /// Extracts a TabSession from OneTab's Firefox IndexedDB storage.
+    ///
+    /// `path` is either one IndexedDB `.sqlite` file or a directory of them
+    /// (the `idb` directory returned by [`resolve_firefox_idb_path`]).
+    pub fn extract_from_firefox_idb(path: &Path, source: SessionSource) -> Result<TabSession> {
+        let files: Vec<PathBuf> = if path.is_dir() {
+            let mut files: Vec<PathBuf> = fs::read_dir(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|ext| ext == "sqlite"))
+                .collect();
+            files.sort();
+            files
+        } else {
+            vec![path.to_path_buf()]
+        };
+        if files.is_empty() {
+            anyhow::bail!("No IndexedDB .sqlite files in {}", path.display());
+        }
+
+        let mut found_groups = Vec::new();
+        let mut seen_group_ids = std::collections::HashSet::new();
+        for file in &files {
+            for value in read_idb_values(file)? {
+                let text = match value {
+                    serde_json::Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                if !text.contains("tabGroups") {
+                    continue;
+                }
+                for group in parse_onetab_state(&text).unwrap_or_default() {
+                    if seen_group_ids.insert(group.id.clone()) {
+                        found_groups.push(group);
+                    }
+                }
+            }
+        }
+
+        let tab_count: usize = found_groups.iter().map(|g: &TabGroup| g.tabs.len()).sum();
+        eprintln!("Recovered {} tab groups, {} tabs total", found_groups.len(), tab_count);
+
+        Ok(TabSession {
+            version: TabSession::CURRENT_VERSION,
+            source,
+            groups: found_groups,
+            created_at: Utc::now(), // We don't know the original creation time
+            imported_at: Utc::now(),
+        })
+    }
+
+    /// Decodes every value stored in an IndexedDB database file.
+    ///
+    /// The database is read from a copy (with its write-ahead log) so a
+    /// running Firefox neither blocks the read nor is disturbed by it.
+    fn read_idb_values(db_file: &Path) -> Result<Vec<serde_json::Value>> {
+        let (conn, _copy) = open_sqlite_copy(db_file)?;
+        let mut stmt = conn
+            .prepare("SELECT data, file_ids FROM object_data")
+            .with_context(|| format!("{} is not an IndexedDB database", db_file.display()))?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, rusqlite::types::Value>(0)?, row.get::<_, Option<String>>(1)?))
+        })?;
+
+        let mut values = Vec::new();
+        for row in rows {
+            let (data, file_ids) = row?;
+            let bytes = match data {
+                rusqlite::types::Value::Blob(compressed) => snap::raw::Decoder::new()
+                    .decompress_vec(&compressed)
+                    .unwrap_or(compressed),
+                // Values over Firefox's inline threshold are stored in a file and
+                // the column only holds a reference to it
+                rusqlite::types::Value::Integer(_) => read_external_value(db_file, file_ids.as_deref())?,
+                _ => continue,
+            };
+            match structured_clone::decode(&bytes) {
+                Ok(value) => values.push(value),
+                Err(e) => eprintln!("warning: skipping undecodable IndexedDB value: {:#}", e),
+            }
+        }
+        Ok(values)
+    }
+
+    /// Reads a value Firefox stored outside the database, in `<db>.files/<id>`.
+    ///
+    /// `file_ids` is space-separated; the structured clone data is the entry
+    /// prefixed with `.`.
+    fn read_external_value(db_file: &Path, file_ids: Option<&str>) -> Result<Vec<u8>> {
+        let id = file_ids
+            .unwrap_or("")
+            .split_whitespace()
+            .find_map(|id| id.strip_prefix('.'))
+            .context("IndexedDB value refers to a file but names none")?;
+        let path = db_file.with_extension("files").join(id);
+        let raw = fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        if !raw.starts_with(SNAPPY_STREAM_MAGIC) {
+            return Ok(raw);
+        }
+        let mut bytes = Vec::new();
+        snap::read::FrameDecoder::new(raw.as_slice())
+            .read_to_end(&mut bytes)
+            .with_context(|| format!("Failed to decompress {}", path.display()))?;
+        Ok(bytes)
+    }
+
+    /// Parses an INI file into `(section, keys)` pairs, in file order.
+    fn parse_ini(content: &str) -> Vec<(String, HashMap<String, String>)> {
+        let mut sections: Vec<(String, HashMap<String, String>)> = Vec::new();
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                sections.push((name.to_string(), HashMap::new()));
+            } else if let (Some((key, value)), Some((_, keys))) = (line.split_once('='), sections.last_mut()) {
+                keys.insert(key.trim().to_string(), value.trim().to_string());
+            }
+        }
+        sections
+    }
+}
+
+/// Format detection for OneTab export files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportFormat {
+    /// OneTab's native pipe-separated format
+    Pipe,
+    /// tablitz/JS-gist markdown format
+    Markdown,
+}
+
+/// Parse a OneTab export file, detecting format automatically.
+///
+/// Supports two formats:
+///
+/// **Format A - Pipe Format (OneTab native):**
+/// ```text
+/// https://url.com | Tab Title
+/// https://url2.com | Tab Title 2
+///
+/// https://url3.com | Tab Title 3
+/// ```
+/// Empty lines separate tab groups. No timestamps. No group labels.
+///
+/// **Format B - Markdown:**
+/// ```markdown
+/// ---
+/// ## 8 tabs
+/// > Created 3/20/2025, 10:08:46 PM
+///
+/// [Tab Title](https://url.com)
+/// [Tab Title 2](https://url2.com)
+/// ```
+///
+/// # Arguments
+///
+/// * `path` - Path to the export file
+///
+/// # Returns
+///
+/// A `TabSession` containing the tab groups from the export file.
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The file cannot be read
+/// - The format cannot be determined
+/// - Parsing fails
+pub fn parse_onetab_export(path: &Path) -> Result<TabSession> {
+    parse_onetab_export_with_timezone(path, Timezone::Local)
+}
+
+/// Like [`parse_onetab_export`], reading markdown `Created` times in `timezone`
+/// instead of the system's.
+pub fn parse_onetab_export_with_timezone(path: &Path, timezone: Timezone) -> Result<TabSession> {
+    let content = fs::read_to_string(path).context("Failed to read export file")?;
+
+    let format = detect_format(&content);
+
+    let groups = match format {
+        ExportFormat::Pipe => parse_pipe_format(&content)?,
+        ExportFormat::Markdown => markdown::parse_markdown_format(&content, timezone)?,
+    };
+
+    let source = SessionSource::OneTabExport {
+        path: path.to_string_lossy().to_string(),
+    };
+
+    eprintln!(
+        "Imported {} tab groups from {} (format: {:?})",
+        groups.len(),
+        path.display(),
+        format
+    );
+
+    Ok(TabSession {
+        version: TabSession::CURRENT_VERSION,
+        source,
+        groups,
+        created_at: Utc::now(),
+        imported_at: Utc::now(),
+    })
+}
+
+/// Parses a file tablitz wrote itself: the `TabSession` JSON that
+/// `recover --out` saves, or the list of groups that `export --format json`
+/// or `--format toml` writes. The shape is detected from the content.
+///
+/// Both go through [`TabSession::from_json_value`], so older versions are
+/// upgraded and newer ones refused. Group lists without a `version` were
+/// written before exports had one, and are read as version 1.
+///
+/// Sessions keep the source they were recorded with; group lists are
+/// recorded as [`SessionSource::TablitzNative`].
+pub fn parse_tablitz_export(path: &Path) -> Result<TabSession> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let value: serde_json::Value = serde_json::from_str(&content)
+        .or_else(|json_err| toml::from_str(&content).map_err(|_| json_err))
+        .with_context(|| format!("{} is not a tablitz JSON or TOML export", path.display()))?;
+
+    let mut doc = match value {
+        serde_json::Value::Object(map) if map.contains_key("version") || map.contains_key("groups") => map,
+        serde_json::Value::Array(groups) => {
+            serde_json::Map::from_iter([("groups".to_string(), serde_json::Value::Array(groups))])
+        }
+        _ => anyhow::bail!("{} holds neither a tablitz session nor a list of groups", path.display()),
+    };
+    // A group list has none of the session's own fields
+    let now = Utc::now().timestamp_millis();
+    doc.entry("version").or_insert(1.into());
+    doc.entry("source").or_insert(serde_json::to_value(SessionSource::Unknown)?);
+    doc.entry("created_at").or_insert(now.into());
+    doc.entry("imported_at").or_insert(now.into());
+
+    let mut session = TabSession::from_json_value(serde_json::Value::Object(doc))
+        .with_context(|| format!("{} is not a readable tablitz export", path.display()))?;
+    if session.source == SessionSource::Unknown {
+        session.source = SessionSource::TablitzNative { path: path.to_string_lossy().to_string() };
+    }
+    Ok(session)
+}
+
+/// Detect the format of a OneTab export file.
+fn detect_format(content: &str) -> ExportFormat {
+    // Markdown format has characteristic "---" and "## X tabs" lines; pipe
+    // lines start with a URL, so titles containing those never match
+    let has_rule = content.lines().any(|l| l.trim() == "---");
+    let has_heading = content
+        .lines()
+        .any(|l| l.trim_start().starts_with("## ") && l.contains("tabs"));
+    if has_rule && has_heading {
+        return ExportFormat::Markdown;
+    }
+
+    // Default to pipe format (OneTab's native export)
+    ExportFormat::Pipe
+}
+
+/// Parse OneTab's pipe-separated export format.
+///
+/// Format: `url | title` with blank lines separating groups.
+fn parse_pipe_format(content: &str) -> Result<Vec<TabGroup>> {
+    let file_hash = fnv1a_hash(content);
+    let mut groups = Vec::new();
+    let mut current_tabs = Vec::new();
+    let mut group_index = 0usize;
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        // Skip empty lines - these separate groups
+        if line.is_empty() {
+            if !current_tabs.is_empty() {
+                let group = TabGroup {
+                    id: format!("pipe-{:x}-g{}", file_hash, group_index),
+                    label: None,
+                    created_at: Utc::now(), // Timestamp not available in pipe format
+                    tabs: current_tabs,
+                    pinned: false,
+                    locked: false,
+                    starred: false,
+                    tags: Vec::new(),
+                };
+                groups.push(group);
+                current_tabs = Vec::new();
+                group_index += 1;
+            }
+            continue;
+        }
+
+        // Parse "url | title" format
+        if let Some((url_part, title_part)) = line.split_once('|') {
+            let url_str = url_part.trim();
+            let title = unescape_pipe_title(title_part.trim());
+
+            if let Ok(parsed_url) = url::Url::parse(url_str) {
+                current_tabs.push(Tab {
+                    id: format!("pipe-{:x}-g{}-t{}", file_hash, group_index, current_tabs.len()),
+                    url: parsed_url,
+                    title,
+                    favicon_url: None,
+                    added_at: Utc::now(), // Timestamp not available
+                    tags: Vec::new(),
+                    activity: Default::default(),
+                });
+            } else {
+                eprintln!("warning: skipping invalid URL in pipe format: '{}'", url_str);
+            }
+        }
+    }
+
+    // Don't forget the last group
+    if !current_tabs.is_empty() {
         let group = TabGroup {
             id: format!("pipe-{:x}-g{}", file_hash, group_index),
             label: None,
@@ -549,107 +2250,1479 @@ fn parse_pipe_format(content: &str) -> Result<Vec<TabGroup>> {
             pinned: false,
             locked: false,
             starred: false,
+            tags: Vec::new(),
         };
         groups.push(group);
     }
 
-    Ok(groups)
+    Ok(groups)
+}
+
+/// Formats tab groups in OneTab's pipe-separated format (`url | title`, with
+/// a blank line between groups), which OneTab's "Import URLs" box accepts and
+/// [`parse_onetab_export`] reads back.
+///
+/// Titles are trimmed, and backslashes, `|` and line breaks in them are
+/// escaped as `\\`, `\|`, `\n` and `\r`; a `|` in a URL is percent-encoded.
+/// Groups without tabs are left out. Parsing the output and formatting it
+/// again gives the same text.
+pub fn format_pipe_export(groups: &[TabGroup]) -> String {
+    let mut out = String::new();
+    for group in groups.iter().filter(|g| !g.tabs.is_empty()) {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        for tab in &group.tabs {
+            out.push_str(&tab.url.as_str().replace('|', "%7C"));
+            out.push_str(" | ");
+            out.push_str(&escape_pipe_title(tab.title.trim()));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Escapes a title for one line of the pipe format.
+fn escape_pipe_title(title: &str) -> String {
+    let mut out = String::with_capacity(title.len());
+    for c in title.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '|' => out.push_str("\\|"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Reverses [`escape_pipe_title`]. Other backslashes, as in titles from
+/// OneTab's own exports, are kept as they are.
+fn unescape_pipe_title(title: &str) -> String {
+    let mut out = String::with_capacity(title.len());
+    let mut chars = title.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => out.push('\\'),
+            Some('|') => out.push('|'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+pub use markdown::{format_markdown_export, Timezone};
+
+/// The markdown format written by the DevTools JS exporter and by
+/// `tablitz export --format markdown`:
+///
+/// ```text
+/// ---
+/// ## 2 tabs
+/// > Created 3/20/2025, 10:08:46 PM
+/// > Optional label
+///
+/// [Title](https://…)
+/// ```
+///
+/// The exporter writes `Created` with the browser's `toLocaleString`, so the
+/// date order depends on the locale and no timezone is recorded.
+///
+/// Only `> Created` and `> Tags:` lines are metadata. tablitz escapes a label
+/// that would read as one with a leading `\`, and `\`, `[` and `]` in titles.
+mod markdown {
+    use super::*;
+    use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone};
+
+    /// How `Created` times are written by tablitz's markdown export.
+    const CREATED_FORMAT: &str = "%-m/%-d/%Y, %-I:%M:%S %p";
+
+    /// The timezone markdown `Created` times are read and written in.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum Timezone {
+        /// The system's timezone, which the browser also used when exporting
+        #[default]
+        Local,
+        /// A fixed offset from UTC
+        Fixed(FixedOffset),
+    }
+
+    impl Timezone {
+        pub fn utc() -> Self {
+            Timezone::Fixed(FixedOffset::east_opt(0).expect("zero offset"))
+        }
+
+        fn utc_time(self, local: NaiveDateTime) -> DateTime<Utc> {
+            let resolved = match self {
+                Timezone::Local => Local.from_local_datetime(&local).earliest().map(|t| t.with_timezone(&Utc)),
+                Timezone::Fixed(offset) => offset.from_local_datetime(&local).single().map(|t| t.with_timezone(&Utc)),
+            };
+            // A time skipped by a DST change has no local reading
+            resolved.unwrap_or_else(|| local.and_utc())
+        }
+
+        fn local_time(self, at: DateTime<Utc>) -> NaiveDateTime {
+            match self {
+                Timezone::Local => at.with_timezone(&Local).naive_local(),
+                Timezone::Fixed(offset) => at.with_timezone(&offset).naive_local(),
+            }
+        }
+    }
+
+    impl std::str::FromStr for Timezone {
+        type Err = anyhow::Error;
+
+        /// Parses `local`, `utc`, or an offset such as `+02:00` or `-0500`.
+        fn from_str(s: &str) -> Result<Self> {
+            match s.to_ascii_lowercase().as_str() {
+                "local" => Ok(Timezone::Local),
+                "utc" | "z" => Ok(Timezone::utc()),
+                _ => s.parse().map(Timezone::Fixed).map_err(|_| {
+                    anyhow::anyhow!("expected `local`, `utc` or an offset like `+02:00`, got '{}'", s)
+                }),
+            }
+        }
+    }
+
+    /// A `Created` time as written, before its day/month order is known.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct RawStamp {
+        year: i32,
+        /// The two other date fields in written order
+        first: u32,
+        second: u32,
+        /// `2025/3/20`: the year leads and month comes before day
+        year_first: bool,
+        hms: (u32, u32, u32),
+    }
+
+    impl RawStamp {
+        /// Reads `toLocaleString` output such as `3/20/2025, 10:08:46 PM`,
+        /// `20/03/2025, 22:08:46`, `20.3.2025, 22:08:46`, `2025/3/20 22:08:46`
+        /// or `2025-03-20 22:08:46`, with or without a leading `Created`.
+        fn parse(text: &str) -> Option<RawStamp> {
+            let mut numbers: Vec<&str> = Vec::new();
+            let mut pm = None;
+            for token in text.split(|c: char| !c.is_alphanumeric()).filter(|t| !t.is_empty()) {
+                if token.bytes().all(|b| b.is_ascii_digit()) {
+                    numbers.push(token);
+                } else if numbers.len() >= 4 {
+                    // `PM`, `pm` and `p.m.`, only after the hour
+                    match token.to_ascii_lowercase().as_str() {
+                        "am" | "a" => pm = Some(false),
+                        "pm" | "p" => pm = Some(true),
+                        _ => {}
+                    }
+                }
+            }
+            let num = |i: usize| numbers.get(i).and_then(|n| n.parse::<u32>().ok());
+            let year_first = match numbers.as_slice() {
+                [y, ..] if y.len() == 4 => true,
+                [_, _, y, ..] if y.len() == 4 => false,
+                _ => return None,
+            };
+            let (year, first, second) = if year_first {
+                (num(0)?, num(1)?, num(2)?)
+            } else {
+                (num(2)?, num(0)?, num(1)?)
+            };
+            let mut hour = num(3).unwrap_or(0);
+            match pm {
+                Some(true) if hour < 12 => hour += 12,
+                Some(false) if hour == 12 => hour = 0,
+                _ => {}
+            }
+            Some(RawStamp {
+                year: year as i32,
+                first,
+                second,
+                year_first,
+                hms: (hour, num(4).unwrap_or(0), num(5).unwrap_or(0)),
+            })
+        }
+
+        /// The written local time, reading ambiguous dates as day/month when
+        /// `day_first`. A field over 12 settles the order for its own date.
+        fn naive(&self, day_first: bool) -> Option<NaiveDateTime> {
+            let day_first = !self.year_first && (self.first > 12 || (day_first && self.second <= 12));
+            let (month, day) = if day_first { (self.second, self.first) } else { (self.first, self.second) };
+            let (h, m, s) = self.hms;
+            NaiveDate::from_ymd_opt(self.year, month, day)?.and_hms_opt(h, m, s)
+        }
+    }
+
+    /// A group as read, before its `Created` time can be resolved.
+    #[derive(Default)]
+    struct RawGroup {
+        /// Every line of the group, hashed into its ID
+        text: String,
+        stamp: Option<RawStamp>,
+        label: Option<String>,
+        tags: Vec<String>,
+        tabs: Vec<(String, url::Url)>,
+    }
+
+    /// Parses the markdown format, reading `Created` times in `timezone`.
+    ///
+    /// One file uses one locale, so `3/4/2025` is read day-first only if some
+    /// other date in the file (`20/3/2025`) shows that order. Groups get
+    /// `markdown-{hash}` IDs from their own lines, so the same group imported
+    /// from two files is recognized and different files never collide.
+    pub(crate) fn parse_markdown_format(content: &str, timezone: Timezone) -> Result<Vec<TabGroup>> {
+        let mut raw: Vec<RawGroup> = Vec::new();
+        let mut current = RawGroup::default();
+
+        for line in content.lines().map(str::trim) {
+            // "---" separates groups; "## N tabs" is the tab count, not a label
+            if line == "---" || (line.starts_with("##") && !current.tabs.is_empty()) {
+                raw.push(std::mem::take(&mut current));
+                if line == "---" {
+                    continue;
+                }
+            }
+            if line.is_empty() {
+                continue;
+            }
+            current.text.push_str(line);
+            current.text.push('\n');
+
+            if line.starts_with("##") {
+                continue;
+            }
+
+            // "> Created …" is the timestamp and "> Tags: …" the tags; any
+            // other "> " line is the label
+            if let Some(text) = line.strip_prefix('>').map(str::trim) {
+                if text.starts_with("Created") {
+                    current.stamp = RawStamp::parse(text);
+                    if current.stamp.is_none() {
+                        eprintln!("warning: unrecognized date in markdown format: '{}'", text);
+                    }
+                } else if let Some(tags) = text.strip_prefix("Tags:") {
+                    current.tags = tags.split(',').filter_map(tablitz_core::normalize_tag).collect();
+                } else if let Some(label) = text.strip_prefix('\\') {
+                    current.label = Some(label.to_string());
+                } else if !text.is_empty() {
+                    current.label = Some(text.to_string());
+                }
+                continue;
+            }
+
+            // Tab link: "[Title](url)"
+            if let Some(rest) = line.strip_prefix('[') {
+                if let Some((title, url_part)) = split_link(rest) {
+                    if let Some(url_str) = url_part.strip_suffix(')') {
+                        match url::Url::parse(url_str) {
+                            Ok(url) => current.tabs.push((title, url)),
+                            Err(_) => eprintln!(
+                                "warning: skipping invalid URL in markdown format: '{}'",
+                                url_str
+                            ),
+                        }
+                    }
+                }
+            }
+        }
+        raw.push(current);
+
+        let stamps = || raw.iter().filter_map(|g| g.stamp).filter(|s| !s.year_first);
+        let day_first = stamps().any(|s| s.first > 12) && !stamps().any(|s| s.second > 12);
+        let now = Utc::now();
+
+        Ok(raw
+            .into_iter()
+            .filter(|g| !g.tabs.is_empty())
+            .map(|g| {
+                let id = format!("markdown-{:x}", fnv1a_hash(&g.text));
+                let created_at = g
+                    .stamp
+                    .and_then(|s| s.naive(day_first))
+                    .map_or(now, |local| timezone.utc_time(local));
+                let tabs = g
+                    .tabs
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, (title, url))| Tab {
+                        id: format!("{}-t{}", id, i),
+                        url,
+                        title,
+                        favicon_url: None,
+                        added_at: created_at,
+                        tags: Vec::new(),
+                        activity: Default::default(),
+                    })
+                    .collect();
+                TabGroup {
+                    id,
+                    label: g.label,
+                    created_at,
+                    tabs,
+                    pinned: false,
+                    locked: false,
+                    starred: false,
+                    tags: g.tags,
+                }
+            })
+            .collect())
+    }
+
+    /// Formats groups as markdown with `Created` times in `timezone`.
+    /// Parsing the output in the same timezone gives back each group's date
+    /// (to the second), label and tags.
+    pub fn format_markdown_export(groups: &[TabGroup], timezone: Timezone) -> String {
+        let mut md = String::new();
+        for group in groups {
+            md.push_str(&format!("---\n## {} tabs\n", group.tabs.len()));
+            md.push_str(&format!("> Created {}\n", timezone.local_time(group.created_at).format(CREATED_FORMAT)));
+            if let Some(label) = &group.label {
+                md.push_str(&format!("> {}\n", escape_label(label)));
+            }
+            if !group.tags.is_empty() {
+                md.push_str(&format!("> Tags: {}\n", group.tags.join(", ")));
+            }
+            md.push('\n');
+            for tab in &group.tabs {
+                md.push_str(&format!("[{}]({})\n", escape_title(&tab.title), tab.url));
+            }
+            md.push('\n');
+        }
+        md
+    }
+
+    /// Prefixes a label that would otherwise read as metadata with `\`.
+    fn escape_label(label: &str) -> String {
+        if label.starts_with("Created") || label.starts_with("Tags:") || label.starts_with('\\') {
+            format!("\\{}", label)
+        } else {
+            label.to_string()
+        }
+    }
+
+    /// Escapes `\`, `[` and `]` so that a title can't end its link early.
+    fn escape_title(title: &str) -> String {
+        let mut escaped = String::with_capacity(title.len());
+        for c in title.chars() {
+            if matches!(c, '\\' | '[' | ']') {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped
+    }
+
+    /// Splits the text after a link's `[` at the first unescaped `](`,
+    /// returning the unescaped title and the rest of the line. Backslashes
+    /// before anything but `\`, `[` and `]` are kept, as the JS exporter
+    /// doesn't escape titles.
+    fn split_link(rest: &str) -> Option<(String, &str)> {
+        let mut title = String::new();
+        let mut chars = rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => match rest[i + 1..].chars().next() {
+                    Some(next @ ('\\' | '[' | ']')) => {
+                        title.push(next);
+                        chars.next();
+                    }
+                    _ => title.push(c),
+                },
+                ']' if rest[i + 1..].starts_with('(') => return Some((title, &rest[i + 2..])),
+                _ => title.push(c),
+            }
+        }
+        None
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_raw_stamp_locale_formats() {
+            let at = |s: &str, day_first| RawStamp::parse(s).and_then(|r| r.naive(day_first)).map(|t| t.to_string());
+            let expected = Some("2025-03-20 22:08:46".to_string());
+            assert_eq!(at("Created 3/20/2025, 10:08:46 PM", false), expected);
+            assert_eq!(at("20/03/2025, 22:08:46", false), expected, "day > 12 settles the order");
+            assert_eq!(at("Erstellt am 20.3.2025, 22:08:46", false), expected);
+            assert_eq!(at("2025/3/20 22:08:46", true), expected);
+            assert_eq!(at("2025-03-20 10:08:46 p.m.", false), expected);
+            assert_eq!(at("3/4/2025, 12:05:00 AM", false), Some("2025-03-04 00:05:00".to_string()));
+            assert_eq!(at("3/4/2025", true), Some("2025-04-03 00:00:00".to_string()));
+            assert_eq!(at("Created yesterday", false), None);
+            assert_eq!(at("13/13/2025", false), None);
+        }
+
+        #[test]
+        fn test_timezone_from_str() {
+            assert_eq!("local".parse::<Timezone>().unwrap(), Timezone::Local);
+            assert_eq!("UTC".parse::<Timezone>().unwrap(), Timezone::utc());
+            let east = FixedOffset::east_opt(5 * 3600 + 1800).unwrap();
+            assert_eq!("+05:30".parse::<Timezone>().unwrap(), Timezone::Fixed(east));
+            assert!("Mars/Olympus".parse::<Timezone>().is_err());
+        }
+    }
+}
+
+pub use bookmarks::{format_bookmarks_html, parse_bookmarks_html};
+
+/// The Netscape `bookmarks.html` format that browsers, Pocket, Raindrop and
+/// Pinboard exchange:
+///
+/// ```text
+/// <DL><p>
+///     <DT><H3 ADD_DATE="1700000000">Folder</H3>
+///     <DL><p>
+///         <DT><A HREF="https://…" ADD_DATE="1700000000" TAGS="a,b">Title</A>
+///     </DL><p>
+/// </DL><p>
+/// ```
+///
+/// Files in the wild are loosely formed HTML, so parsing is a tolerant scan
+/// over tags rather than a full HTML parser.
+mod bookmarks {
+    use super::*;
+    use chrono::DateTime;
+
+    /// An HTML token.
+    #[derive(Debug, PartialEq)]
+    enum Token<'a> {
+        /// An opening tag, with its lowercased name and attributes
+        Start(String, Vec<(String, String)>),
+        /// A closing tag's lowercased name
+        End(String),
+        Text(&'a str),
+    }
+
+    /// Splits HTML into tags and text, skipping comments and `<!DOCTYPE>`.
+    fn tokenize(html: &str) -> Vec<Token<'_>> {
+        let mut tokens = Vec::new();
+        let mut rest = html;
+        while !rest.is_empty() {
+            let Some(open) = rest.find('<') else {
+                tokens.push(Token::Text(rest));
+                break;
+            };
+            if open > 0 {
+                tokens.push(Token::Text(&rest[..open]));
+                rest = &rest[open..];
+            }
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+                continue;
+            }
+            let Some(close) = tag_end(rest) else {
+                tokens.push(Token::Text(rest));
+                break;
+            };
+            let inner = &rest[1..close];
+            rest = &rest[close + 1..];
+            if let Some(name) = inner.strip_prefix('/') {
+                tokens.push(Token::End(name.trim().to_ascii_lowercase()));
+            } else if !inner.starts_with('!') && !inner.starts_with('?') {
+                tokens.push(parse_start_tag(inner));
+            }
+        }
+        tokens
+    }
+
+    /// The index of the `>` closing the tag at the start of `s`, skipping
+    /// quoted attribute values.
+    fn tag_end(s: &str) -> Option<usize> {
+        let mut quote = None;
+        for (i, c) in s.char_indices().skip(1) {
+            match (quote, c) {
+                (None, '"' | '\'') => quote = Some(c),
+                (Some(q), c) if c == q => quote = None,
+                (None, '>') => return Some(i),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn parse_start_tag(inner: &str) -> Token<'_> {
+        let inner = inner.trim_end_matches('/').trim();
+        let name_end = inner.find(char::is_whitespace).unwrap_or(inner.len());
+        let name = inner[..name_end].to_ascii_lowercase();
+
+        let mut attrs = Vec::new();
+        let mut rest = inner[name_end..].trim_start();
+        while !rest.is_empty() {
+            let key_end = rest
+                .find(|c: char| c.is_whitespace() || c == '=')
+                .unwrap_or(rest.len());
+            let key = rest[..key_end].to_ascii_lowercase();
+            rest = rest[key_end..].trim_start();
+            let value = if let Some(after_eq) = rest.strip_prefix('=') {
+                let after_eq = after_eq.trim_start();
+                match after_eq.chars().next() {
+                    Some(q @ ('"' | '\'')) => {
+                        let body = &after_eq[1..];
+                        let end = body.find(q).unwrap_or(body.len());
+                        rest = body.get(end + 1..).unwrap_or("");
+                        &body[..end]
+                    }
+                    _ => {
+                        let end = after_eq.find(char::is_whitespace).unwrap_or(after_eq.len());
+                        rest = &after_eq[end..];
+                        &after_eq[..end]
+                    }
+                }
+            } else {
+                ""
+            };
+            if !key.is_empty() {
+                attrs.push((key, decode_entities(value)));
+            }
+            rest = rest.trim_start();
+        }
+        Token::Start(name, attrs)
+    }
+
+    /// Decodes HTML character references; unknown ones are left as written.
+    fn decode_entities(s: &str) -> String {
+        let mut out = String::with_capacity(s.len());
+        let mut rest = s;
+        while let Some(amp) = rest.find('&') {
+            out.push_str(&rest[..amp]);
+            rest = &rest[amp..];
+            let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+                let entity = &rest[1..end];
+                let c = match entity {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    "nbsp" => Some('\u{a0}'),
+                    _ => entity
+                        .strip_prefix("#x")
+                        .or_else(|| entity.strip_prefix("#X"))
+                        .map(|hex| u32::from_str_radix(hex, 16))
+                        .or_else(|| entity.strip_prefix('#').map(str::parse))
+                        .and_then(|n| n.ok())
+                        .and_then(char::from_u32),
+                };
+                c.map(|c| (c, end))
+            });
+            match decoded {
+                Some((c, end)) => {
+                    out.push(c);
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    out.push('&');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    fn escape_html(s: &str) -> String {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    }
+
+    /// Parses an `ADD_DATE` value: seconds since the epoch, or milli- or
+    /// microseconds as some tools write.
+    fn parse_add_date(value: &str) -> Option<DateTime<Utc>> {
+        let n: i64 = value.trim().parse().ok().filter(|&n| n > 0)?;
+        let ms = if n >= 100_000_000_000_000 {
+            n / 1000
+        } else if n >= 100_000_000_000 {
+            n
+        } else {
+            n * 1000
+        };
+        Some(ms_timestamp_to_datetime(ms))
+    }
+
+    fn attr<'a>(attrs: &'a [(String, String)], name: &str) -> Option<&'a str> {
+        attrs.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+
+    /// A folder (or anonymous list) and the bookmarks directly inside it.
+    struct Folder {
+        label: Option<String>,
+        added_at: Option<DateTime<Utc>>,
+        tabs: Vec<Tab>,
+    }
+
+    /// Reads a Netscape bookmarks file into a `TabSession`.
+    ///
+    /// Every folder holding bookmarks becomes a `TabGroup`, labelled with its
+    /// path (`Bookmarks bar / Rust`); bookmarks outside any folder form an
+    /// unlabelled group. `ADD_DATE` (or Pocket's `time_added`) sets
+    /// `added_at` and the folder's sets `created_at`, and `TAGS` become tab
+    /// tags. `place:` and `javascript:` bookmarks are skipped.
+    pub fn parse_bookmarks_html(path: &Path) -> Result<TabSession> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(TabSession {
+            version: TabSession::CURRENT_VERSION,
+            source: SessionSource::BookmarksHtml { path: path.to_string_lossy().to_string() },
+            groups: parse_bookmarks(&content),
+            created_at: Utc::now(),
+            imported_at: Utc::now(),
+        })
+    }
+
+    fn parse_bookmarks(content: &str) -> Vec<TabGroup> {
+        let now = Utc::now();
+        let mut folders: Vec<Folder> = Vec::new();
+        let mut open: Vec<usize> = Vec::new();
+        // A folder heading waiting for its list, and the heading or link being read
+        let mut pending: Option<(String, Option<DateTime<Utc>>)> = None;
+        let mut heading: Option<(Option<DateTime<Utc>>, String)> = None;
+        let mut link: Option<(Vec<(String, String)>, String)> = None;
+
+        for token in tokenize(content) {
+            match token {
+                Token::Start(name, attrs) => match name.as_str() {
+                    "dl" | "ul" | "ol" => {
+                        let parent = open.last().and_then(|&i| folders[i].label.clone());
+                        let (label, added_at) = match pending.take() {
+                            Some((name, added_at)) => {
+                                let label = match parent {
+                                    Some(parent) => format!("{} / {}", parent, name),
+                                    None => name,
+                                };
+                                (Some(label), added_at)
+                            }
+                            None => (parent, None),
+                        };
+                        folders.push(Folder { label, added_at, tabs: Vec::new() });
+                        open.push(folders.len() - 1);
+                    }
+                    "h2" | "h3" | "h4" | "h5" | "h6" => {
+                        heading = Some((attr(&attrs, "add_date").and_then(parse_add_date), String::new()));
+                    }
+                    "a" if attr(&attrs, "href").is_some() => link = Some((attrs, String::new())),
+                    _ => {}
+                },
+                Token::Text(text) => {
+                    if let Some((_, buf)) = heading.as_mut() {
+                        buf.push_str(text);
+                    } else if let Some((_, buf)) = link.as_mut() {
+                        buf.push_str(text);
+                    }
+                }
+                Token::End(name) => match name.as_str() {
+                    "dl" | "ul" | "ol" => {
+                        open.pop();
+                    }
+                    "h2" | "h3" | "h4" | "h5" | "h6" => {
+                        if let Some((added_at, text)) = heading.take() {
+                            pending = Some((decode_entities(text.trim()), added_at));
+                        }
+                    }
+                    "a" => {
+                        let Some((attrs, text)) = link.take() else { continue };
+                        let href = attr(&attrs, "href").unwrap_or_default();
+                        let Ok(url) = url::Url::parse(href.trim()) else {
+                            eprintln!("warning: skipping invalid bookmark URL '{}'", href);
+                            continue;
+                        };
+                        if matches!(url.scheme(), "place" | "javascript") {
+                            continue;
+                        }
+                        let folder = match open.last() {
+                            Some(&i) => i,
+                            None => {
+                                folders.push(Folder { label: None, added_at: None, tabs: Vec::new() });
+                                open.push(folders.len() - 1);
+                                folders.len() - 1
+                            }
+                        };
+                        let added_at = attr(&attrs, "add_date")
+                            .or_else(|| attr(&attrs, "time_added"))
+                            .and_then(parse_add_date)
+                            .or(folders[folder].added_at)
+                            .unwrap_or(now);
+                        let mut tags: Vec<String> = Vec::new();
+                        for tag in attr(&attrs, "tags").unwrap_or_default().split(',') {
+                            if let Some(tag) = tablitz_core::normalize_tag(tag) {
+                                if !tags.contains(&tag) {
+                                    tags.push(tag);
+                                }
+                            }
+                        }
+                        let title = decode_entities(text.trim());
+                        folders[folder].tabs.push(Tab {
+                            id: String::new(),
+                            title: if title.is_empty() { url.to_string() } else { title },
+                            url,
+                            favicon_url: attr(&attrs, "icon_uri")
+                                .or_else(|| attr(&attrs, "icon"))
+                                .map(str::to_string),
+                            added_at,
+                            tags,
+                            activity: Default::default(),
+                        });
+                    }
+                    _ => {}
+                },
+            }
+        }
+
+        let file_hash = fnv1a_hash(content);
+        folders
+            .into_iter()
+            .filter(|f| !f.tabs.is_empty())
+            .enumerate()
+            .map(|(g, folder)| {
+                let created_at = folder
+                    .added_at
+                    .or_else(|| folder.tabs.iter().map(|t| t.added_at).min())
+                    .unwrap_or(now);
+                let tabs = folder
+                    .tabs
+                    .into_iter()
+                    .enumerate()
+                    .map(|(t, tab)| Tab { id: format!("bookmarks-{:x}-g{}-t{}", file_hash, g, t), ..tab })
+                    .collect();
+                TabGroup {
+                    id: format!("bookmarks-{:x}-g{}", file_hash, g),
+                    label: folder.label,
+                    created_at,
+                    tabs,
+                    pinned: false,
+                    locked: false,
+                    starred: false,
+                    tags: Vec::new(),
+                }
+            })
+            .collect()
+    }
+
+    /// Writes tab groups as a Netscape bookmarks file that any browser's
+    /// bookmark manager imports.
+    ///
+    /// Each group becomes a folder named after its label (or creation time),
+    /// starred groups first. Tab and group tags go in each bookmark's `TAGS`
+    /// attribute, and `ADD_DATE` is in seconds.
+    pub fn format_bookmarks_html(groups: &[TabGroup]) -> String {
+        let mut ordered: Vec<&TabGroup> = groups.iter().filter(|g| !g.tabs.is_empty()).collect();
+        ordered.sort_by_key(|g| !g.starred);
+
+        let mut out = String::from(
+            "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+             <!-- This is an automatically generated file.\n     \
+             It will be read and overwritten.\n     \
+             DO NOT EDIT! -->\n\
+             <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n\
+             <TITLE>Bookmarks</TITLE>\n\
+             <H1>Bookmarks</H1>\n\
+             <DL><p>\n",
+        );
+        for group in ordered {
+            let name = group
+                .label
+                .clone()
+                .unwrap_or_else(|| group.created_at.format("%Y-%m-%d %H:%M").to_string());
+            out.push_str(&format!(
+                "    <DT><H3 ADD_DATE=\"{}\">{}</H3>\n    <DL><p>\n",
+                group.created_at.timestamp(),
+                escape_html(&name)
+            ));
+            for tab in &group.tabs {
+                out.push_str(&format!(
+                    "        <DT><A HREF=\"{}\" ADD_DATE=\"{}\"",
+                    escape_html(tab.url.as_str()),
+                    tab.added_at.timestamp()
+                ));
+                if let Some(icon) = &tab.favicon_url {
+                    let attr = if icon.starts_with("data:") { "ICON" } else { "ICON_URI" };
+                    out.push_str(&format!(" {}=\"{}\"", attr, escape_html(icon)));
+                }
+                let mut tags: Vec<&str> = tab.tags.iter().map(String::as_str).collect();
+                for tag in &group.tags {
+                    if !tags.contains(&tag.as_str()) {
+                        tags.push(tag);
+                    }
+                }
+                if !tags.is_empty() {
+                    out.push_str(&format!(" TAGS=\"{}\"", escape_html(&tags.join(","))));
+                }
+                out.push_str(&format!(">{}</A>\n", escape_html(&tab.title)));
+            }
+            out.push_str("    </DL><p>\n");
+        }
+        out.push_str("</DL><p>\n");
+        out
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_tokenize_attributes_and_entities() {
+            let tokens = tokenize(
+                "<!DOCTYPE x><!-- <A HREF=\"no\"> --><DT><a href='https://a.com/?x=1&amp;y=>' ADD_DATE=5 private>T&#39;s &amp; co</A>",
+            );
+            assert_eq!(
+                tokens,
+                vec![
+                    Token::Start("dt".to_string(), vec![]),
+                    Token::Start(
+                        "a".to_string(),
+                        vec![
+                            ("href".to_string(), "https://a.com/?x=1&y=>".to_string()),
+                            ("add_date".to_string(), "5".to_string()),
+                            ("private".to_string(), String::new()),
+                        ]
+                    ),
+                    Token::Text("T&#39;s &amp; co"),
+                    Token::End("a".to_string()),
+                ]
+            );
+            assert_eq!(decode_entities("T&#39;s &amp; &#x41; &bogus; & co"), "T's & A &bogus; & co");
+        }
+
+        #[test]
+        fn test_parse_add_date_units() {
+            let expected = Some(ms_timestamp_to_datetime(1_700_000_000_000));
+            assert_eq!(parse_add_date("1700000000"), expected);
+            assert_eq!(parse_add_date("1700000000000"), expected);
+            assert_eq!(parse_add_date("1700000000000000"), expected);
+            assert_eq!(parse_add_date("0"), None);
+            assert_eq!(parse_add_date("soon"), None);
+        }
+    }
 }
 
-/// Parse the markdown format (from the JS-gist script).
-fn parse_markdown_format(content: &str) -> Result<Vec<TabGroup>> {
-    let mut groups = Vec::new();
-    let mut current_header: Option<(String, String)> = None; // (title, timestamp_str)
-    let mut current_tabs = Vec::new();
-    let mut group_index = 0;
+/// Microseconds from `base::Time`'s epoch (1601-01-01) to the Unix epoch.
+const WINDOWS_EPOCH_OFFSET_US: i64 = 11_644_473_600_000_000;
 
-    for line in content.lines().map(|l| l.trim()).collect::<Vec<_>>() {
-        // Group separator
-        if line == "---" {
-            // Save previous group if exists
-            if !current_tabs.is_empty() {
-                let (label, _timestamp) = current_header.take().unwrap_or_default();
+/// Converts a Chromium `base::Time` internal value (µs since 1601) to UTC.
+/// Zero, Chromium's null time, gives `None`.
+fn chromium_time(value: i64) -> Option<chrono::DateTime<Utc>> {
+    if value <= 0 {
+        return None;
+    }
+    chrono::DateTime::from_timestamp_micros(value - WINDOWS_EPOCH_OFFSET_US)
+}
 
-                // Try to parse timestamp from header if available
-                let created_at = Utc::now(); // Could parse timestamp from markdown header
+pub use sessions::{parse_chromium_session, parse_firefox_session};
 
-                let group = TabGroup {
-                    id: format!("markdown-import-{}", group_index),
-                    label: if label.is_empty() { None } else { Some(label) },
-                    created_at,
-                    tabs: current_tabs,
-                    pinned: false,
-                    locked: false,
-                    starred: false,
-                };
-                groups.push(group);
-                current_tabs = Vec::new();
-                group_index += 1;
+/// Open-tab snapshots from browser session files, one group per window:
+///
+/// - Chromium-family browsers append SNSS command logs to `Sessions/Session_*`
+///   (open windows) and `Sessions/Tabs_*` (recently closed tabs). Replaying
+///   the commands rebuilds each window's tabs and their current navigation.
+/// - Firefox writes `sessionstore.jsonlz4` on shutdown and
+///   `sessionstore-backups/recovery.jsonlz4` while running: JSON compressed as
+///   one LZ4 block behind a `mozLz40\0` header.
+mod sessions {
+    use super::*;
+    use chrono::DateTime;
+    use serde_json::Value;
+    use std::collections::{BTreeMap, HashMap, HashSet};
+
+    const SNSS_MAGIC: &[u8] = b"SNSS";
+    const MOZLZ4_MAGIC: &[u8] = b"mozLz40\0";
+
+    // `Session_*` command ids, from components/sessions/core/session_service_commands.cc
+    const SET_TAB_WINDOW: u8 = 0;
+    const SET_TAB_INDEX_IN_WINDOW: u8 = 2;
+    const UPDATE_TAB_NAVIGATION: u8 = 6;
+    const SET_SELECTED_NAVIGATION_INDEX: u8 = 7;
+    const TAB_CLOSED: u8 = 16;
+    const WINDOW_CLOSED: u8 = 17;
+    const LAST_ACTIVE_TIME: u8 = 21;
+
+    // `Tabs_*` command ids, from components/sessions/core/tab_restore_service_impl.cc
+    const RESTORE_UPDATE_TAB_NAVIGATION: u8 = 1;
+    const RESTORE_RESTORED_ENTRY: u8 = 2;
+    const RESTORE_SELECTED_NAVIGATION_IN_TAB: u8 = 4;
+
+    /// A navigation entry from an `UpdateTabNavigation` command.
+    #[derive(Debug, Clone)]
+    struct Navigation {
+        url: String,
+        title: String,
+        timestamp: Option<DateTime<Utc>>,
+    }
+
+    #[derive(Debug, Default)]
+    struct SessionTab {
+        window: Option<i32>,
+        index: i32,
+        navigations: BTreeMap<i32, Navigation>,
+        selected: Option<i32>,
+        last_active: Option<DateTime<Utc>>,
+    }
+
+    impl SessionTab {
+        /// The navigation the tab is showing: the selected one, else the newest.
+        fn current(&self) -> Option<&Navigation> {
+            self.selected
+                .and_then(|i| self.navigations.get(&i))
+                .or_else(|| self.navigations.values().next_back())
+        }
+    }
+
+    /// Reads fields from a `base::Pickle`: a `u32` payload size, then
+    /// little-endian fields each padded to four bytes.
+    struct Pickle<'a> {
+        data: &'a [u8],
+        pos: usize,
+    }
+
+    impl<'a> Pickle<'a> {
+        fn new(bytes: &'a [u8]) -> Option<Self> {
+            let size = u32::from_le_bytes(bytes.get(..4)?.try_into().ok()?) as usize;
+            let data = &bytes[4..];
+            Some(Pickle { data: &data[..size.min(data.len())], pos: 0 })
+        }
+
+        fn read_bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+            let bytes = self.data.get(self.pos..self.pos.checked_add(len)?)?;
+            self.pos += len.div_ceil(4) * 4;
+            Some(bytes)
+        }
+
+        fn read_i32(&mut self) -> Option<i32> {
+            Some(i32::from_le_bytes(self.read_bytes(4)?.try_into().ok()?))
+        }
+
+        fn read_i64(&mut self) -> Option<i64> {
+            Some(i64::from_le_bytes(self.read_bytes(8)?.try_into().ok()?))
+        }
+
+        fn read_string(&mut self) -> Option<String> {
+            let len = usize::try_from(self.read_i32()?).ok()?;
+            Some(String::from_utf8_lossy(self.read_bytes(len)?).into_owned())
+        }
+
+        /// A UTF-16 string, whose length prefix counts code units.
+        fn read_string16(&mut self) -> Option<String> {
+            let len = usize::try_from(self.read_i32()?).ok()?;
+            let units: Vec<u16> = self
+                .read_bytes(len.checked_mul(2)?)?
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .collect();
+            Some(String::from_utf16_lossy(&units))
+        }
+    }
+
+    /// Splits an SNSS file into `(command id, payload)` pairs.
+    fn snss_commands(bytes: &[u8]) -> Result<Vec<(u8, &[u8])>> {
+        if bytes.get(..4) != Some(SNSS_MAGIC) {
+            anyhow::bail!("Not a Chromium session file (missing SNSS header)");
+        }
+        let version = bytes.get(4..8).map(|v| i32::from_le_bytes(v.try_into().unwrap()));
+        if version == Some(2) {
+            anyhow::bail!("Encrypted Chromium session files are not supported");
+        }
+        let mut commands = Vec::new();
+        let mut pos = 8;
+        // Each command is a u16 size (covering the id byte), the id, then the
+        // payload; a truncated final command is dropped
+        while let Some(size) = bytes.get(pos..pos + 2) {
+            let size = u16::from_le_bytes([size[0], size[1]]) as usize;
+            let Some(command) = bytes.get(pos + 2..pos + 2 + size) else { break };
+            if let Some((&id, payload)) = command.split_first() {
+                commands.push((id, payload));
             }
-            current_header = None;
-            continue;
+            pos += 2 + size;
         }
+        Ok(commands)
+    }
 
-        // Group header: "## X tabs" — this is the tab count header, not a user label
-        if line.starts_with("##") {
-            current_header = Some((String::new(), String::new()));
-            continue;
+    fn payload_i32(payload: &[u8], offset: usize) -> Option<i32> {
+        Some(i32::from_le_bytes(payload.get(offset..offset + 4)?.try_into().ok()?))
+    }
+
+    fn payload_i64(payload: &[u8], offset: usize) -> Option<i64> {
+        Some(i64::from_le_bytes(payload.get(offset..offset + 8)?.try_into().ok()?))
+    }
+
+    /// Parses an `UpdateTabNavigation` pickle into its tab id, navigation
+    /// index and entry. Fields after the title are only read for the timestamp.
+    fn parse_navigation(payload: &[u8]) -> Option<(i32, i32, Navigation)> {
+        let mut pickle = Pickle::new(payload)?;
+        let tab_id = pickle.read_i32()?;
+        let index = pickle.read_i32()?;
+        let url = pickle.read_string()?;
+        let title = pickle.read_string16().unwrap_or_default();
+        let timestamp = (|| {
+            pickle.read_string()?; // encoded page state
+            pickle.read_i32()?; // transition type
+            pickle.read_i32()?; // type mask
+            pickle.read_string()?; // referrer URL
+            pickle.read_i32()?; // referrer policy
+            pickle.read_string()?; // original request URL
+            pickle.read_i32()?; // is overriding user agent
+            chromium_time(pickle.read_i64()?)
+        })();
+        Some((tab_id, index, Navigation { url, title, timestamp }))
+    }
+
+    /// Finds the session file to read under a profile or `Sessions` directory:
+    /// the newest `Session_*` file, else the pre-2022 `Current Session`.
+    fn resolve_chromium_session_file(path: &Path) -> Result<PathBuf> {
+        if path.is_file() {
+            return Ok(path.to_path_buf());
         }
+        let sessions = path.join("Sessions");
+        let dir = if sessions.is_dir() { sessions } else { path.to_path_buf() };
+        let newest = fs::read_dir(&dir)
+            .with_context(|| format!("Failed to read {}", dir.display()))?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.file_name().is_some_and(|f| f.to_string_lossy().starts_with("Session_")))
+            .max_by_key(|p| p.metadata().and_then(|m| m.modified()).ok());
+        newest
+            .or_else(|| Some(path.join("Current Session")).filter(|p| p.is_file()))
+            .with_context(|| format!("No Chromium session files in {}", dir.display()))
+    }
 
-        // "> Created ..." → timestamp; "> anything else" → user label
-        if line.starts_with('>') {
-            if let Some(header) = &mut current_header {
-                let text = line.strip_prefix('>').unwrap_or("").trim().to_string();
-                if text.starts_with("Created") {
-                    header.1 = text;
-                } else {
-                    header.0 = text;
+    fn modified_time(path: &Path) -> DateTime<Utc> {
+        path.metadata()
+            .and_then(|m| m.modified())
+            .map(DateTime::<Utc>::from)
+            .unwrap_or_else(|_| Utc::now())
+    }
+
+    /// Builds a group from `(url, title, added_at)` entries, with an ID derived
+    /// from its URLs so the same window imported twice is skipped.
+    fn window_group(prefix: &str, label: Option<String>, entries: Vec<(String, String, DateTime<Utc>)>) -> Option<TabGroup> {
+        let entries: Vec<_> = entries
+            .into_iter()
+            .filter_map(|(url, title, added_at)| Some((url::Url::parse(&url).ok()?, title, added_at)))
+            .collect();
+        let created_at = entries.iter().map(|(_, _, added_at)| *added_at).min()?;
+        let urls: Vec<&str> = entries.iter().map(|(url, _, _)| url.as_str()).collect();
+        let id = format!("{}-{:x}", prefix, fnv1a_hash(&urls.join("\n")));
+        let tabs = entries
+            .into_iter()
+            .enumerate()
+            .map(|(i, (url, title, added_at))| Tab {
+                id: format!("{}-t{}", id, i),
+                title: if title.trim().is_empty() { url.to_string() } else { title },
+                url,
+                favicon_url: None,
+                added_at,
+                tags: Vec::new(),
+                activity: Default::default(),
+            })
+            .collect();
+        Some(TabGroup {
+            id,
+            label,
+            created_at,
+            tabs,
+            pinned: false,
+            locked: false,
+            starred: false,
+            tags: Vec::new(),
+        })
+    }
+
+    /// Reads the open windows from a Chromium `Session_*` file, or recently
+    /// closed tabs from a `Tabs_*` file, as a TabSession.
+    ///
+    /// `path` may also be a profile or `Sessions` directory, in which case the
+    /// newest `Session_*` file is read. Windows and tabs closed later in the
+    /// log are dropped; each tab shows its selected navigation, dated by when
+    /// it was last active.
+    pub fn parse_chromium_session(path: &Path, source: SessionSource) -> Result<TabSession> {
+        let file = resolve_chromium_session_file(path)?;
+        let bytes = fs::read(&file).with_context(|| format!("Failed to read {}", file.display()))?;
+        let commands = snss_commands(&bytes)?;
+        let fallback = modified_time(&file);
+        let is_tab_restore = file.file_name().is_some_and(|f| f.to_string_lossy().starts_with("Tabs_"));
+        let groups = if is_tab_restore {
+            replay_tab_restore(&commands, fallback)
+        } else {
+            replay_session(&commands, fallback)
+        };
+        Ok(TabSession {
+            version: TabSession::CURRENT_VERSION,
+            source,
+            groups,
+            created_at: fallback,
+            imported_at: Utc::now(),
+        })
+    }
+
+    fn replay_session(commands: &[(u8, &[u8])], fallback: DateTime<Utc>) -> Vec<TabGroup> {
+        let mut tabs: HashMap<i32, SessionTab> = HashMap::new();
+        let mut windows: Vec<i32> = Vec::new();
+        let mut closed_windows: HashSet<i32> = HashSet::new();
+
+        for &(id, payload) in commands {
+            match id {
+                SET_TAB_WINDOW => {
+                    if let (Some(window), Some(tab)) = (payload_i32(payload, 0), payload_i32(payload, 4)) {
+                        tabs.entry(tab).or_default().window = Some(window);
+                        if !windows.contains(&window) {
+                            windows.push(window);
+                        }
+                    }
                 }
+                SET_TAB_INDEX_IN_WINDOW => {
+                    if let (Some(tab), Some(index)) = (payload_i32(payload, 0), payload_i32(payload, 4)) {
+                        tabs.entry(tab).or_default().index = index;
+                    }
+                }
+                UPDATE_TAB_NAVIGATION => {
+                    if let Some((tab, index, navigation)) = parse_navigation(payload) {
+                        tabs.entry(tab).or_default().navigations.insert(index, navigation);
+                    }
+                }
+                SET_SELECTED_NAVIGATION_INDEX => {
+                    if let (Some(tab), Some(index)) = (payload_i32(payload, 0), payload_i32(payload, 4)) {
+                        tabs.entry(tab).or_default().selected = Some(index);
+                    }
+                }
+                TAB_CLOSED => {
+                    if let Some(tab) = payload_i32(payload, 0) {
+                        tabs.remove(&tab);
+                    }
+                }
+                WINDOW_CLOSED => {
+                    if let Some(window) = payload_i32(payload, 0) {
+                        closed_windows.insert(window);
+                    }
+                }
+                LAST_ACTIVE_TIME => {
+                    if let (Some(tab), Some(time)) = (payload_i32(payload, 0), payload_i64(payload, 8)) {
+                        tabs.entry(tab).or_default().last_active = chromium_time(time);
+                    }
+                }
+                _ => {}
             }
-            continue;
         }
 
-        // Tab link: "[Title](url)"
-        if let Some(rest) = line.strip_prefix('[') {
-            if let Some((title_part, url_part)) = rest.split_once("](") {
-                if let Some(url_str) = url_part.strip_suffix(')') {
-                    let title = title_part.to_string();
+        windows
+            .into_iter()
+            .filter(|window| !closed_windows.contains(window))
+            .filter_map(|window| {
+                let mut in_window: Vec<(&i32, &SessionTab)> =
+                    tabs.iter().filter(|(_, tab)| tab.window == Some(window)).collect();
+                in_window.sort_by_key(|(id, tab)| (tab.index, **id));
+                let entries = in_window
+                    .into_iter()
+                    .filter_map(|(_, tab)| {
+                        let nav = tab.current()?;
+                        let added_at = tab.last_active.or(nav.timestamp).unwrap_or(fallback);
+                        Some((nav.url.clone(), nav.title.clone(), added_at))
+                    })
+                    .collect();
+                window_group("chromium-session", None, entries)
+            })
+            .collect()
+    }
 
-                    if let Ok(parsed_url) = url::Url::parse(url_str) {
-                        current_tabs.push(Tab {
-                            id: format!("tab-{}-{}", group_index, current_tabs.len()),
-                            url: parsed_url,
-                            title,
-                            favicon_url: None,
-                            added_at: Utc::now(),
-                        });
-                    } else {
-                        eprintln!(
-                            "warning: skipping invalid URL in markdown format: '{}'",
-                            url_str
-                        );
+    /// Recently closed tabs, oldest first, as one "Recently closed" group.
+    fn replay_tab_restore(commands: &[(u8, &[u8])], fallback: DateTime<Utc>) -> Vec<TabGroup> {
+        let mut order: Vec<i32> = Vec::new();
+        let mut tabs: HashMap<i32, SessionTab> = HashMap::new();
+        for &(id, payload) in commands {
+            match id {
+                RESTORE_SELECTED_NAVIGATION_IN_TAB => {
+                    if let (Some(tab), Some(index)) = (payload_i32(payload, 0), payload_i32(payload, 4)) {
+                        let entry = tabs.entry(tab).or_default();
+                        entry.selected = Some(index);
+                        entry.last_active = payload_i64(payload, 8).and_then(chromium_time);
+                        order.push(tab);
+                    }
+                }
+                RESTORE_UPDATE_TAB_NAVIGATION => {
+                    if let Some((tab, index, navigation)) = parse_navigation(payload) {
+                        tabs.entry(tab).or_default().navigations.insert(index, navigation);
+                    }
+                }
+                RESTORE_RESTORED_ENTRY => {
+                    if let Some(tab) = payload_i32(payload, 0) {
+                        tabs.remove(&tab);
                     }
                 }
+                _ => {}
             }
         }
+        let entries = order
+            .iter()
+            .filter_map(|id| {
+                let tab = tabs.get(id)?;
+                let nav = tab.current()?;
+                let added_at = tab.last_active.or(nav.timestamp).unwrap_or(fallback);
+                Some((nav.url.clone(), nav.title.clone(), added_at))
+            })
+            .collect();
+        window_group("chromium-closed", Some("Recently closed".to_string()), entries)
+            .into_iter()
+            .collect()
     }
 
-    // Last group
-    if !current_tabs.is_empty() {
-        let (label, _timestamp) = current_header.take().unwrap_or_default();
-        let group = TabGroup {
-            id: format!("markdown-import-{}", group_index),
-            label: if label.is_empty() { None } else { Some(label) },
-            created_at: Utc::now(),
-            tabs: current_tabs,
-            pinned: false,
-            locked: false,
-            starred: false,
-        };
-        groups.push(group);
+    /// Decompresses a mozLz4 file: the magic, the decompressed size as a
+    /// little-endian `u32`, then one LZ4 block.
+    fn decode_mozlz4(bytes: &[u8]) -> Result<Vec<u8>> {
+        let header = bytes.get(..12).context("mozLz4 file is truncated")?;
+        if &header[..8] != MOZLZ4_MAGIC {
+            anyhow::bail!("Not a mozLz4 file");
+        }
+        let size = u32::from_le_bytes(header[8..12].try_into().unwrap()) as usize;
+        lz4_flex::block::decompress(&bytes[12..], size).context("Failed to decompress mozLz4 data")
     }
 
-    Ok(groups)
+    /// Finds the session file to read in a Firefox profile directory: the newer
+    /// of `sessionstore.jsonlz4` and `sessionstore-backups/recovery.jsonlz4`.
+    fn resolve_firefox_session_file(path: &Path) -> Result<PathBuf> {
+        if path.is_file() {
+            return Ok(path.to_path_buf());
+        }
+        [
+            path.join("sessionstore.jsonlz4"),
+            path.join("sessionstore-backups").join("recovery.jsonlz4"),
+        ]
+        .into_iter()
+        .filter(|p| p.is_file())
+        .max_by_key(|p| p.metadata().and_then(|m| m.modified()).ok())
+        .with_context(|| format!("No Firefox session file in {}", path.display()))
+    }
+
+    /// Reads the open windows from a Firefox session file (`*.jsonlz4`, or
+    /// uncompressed JSON) as a TabSession.
+    ///
+    /// `path` may also be a profile directory. Each tab shows its current
+    /// history entry (`index` is 1-based) and is dated by `lastAccessed`;
+    /// closed windows are not included.
+    pub fn parse_firefox_session(path: &Path, source: SessionSource) -> Result<TabSession> {
+        let file = resolve_firefox_session_file(path)?;
+        let bytes = fs::read(&file).with_context(|| format!("Failed to read {}", file.display()))?;
+        let json = if bytes.starts_with(MOZLZ4_MAGIC) { decode_mozlz4(&bytes)? } else { bytes };
+        let state: Value = serde_json::from_slice(&json)
+            .with_context(|| format!("Failed to parse session JSON in {}", file.display()))?;
+
+        let fallback = state
+            .pointer("/session/lastUpdate")
+            .and_then(Value::as_i64)
+            .map(ms_timestamp_to_datetime)
+            .unwrap_or_else(|| modified_time(&file));
+        let windows = state.get("windows").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+        let groups = windows
+            .iter()
+            .filter_map(|window| {
+                let tabs = window.get("tabs").and_then(Value::as_array)?;
+                let entries = tabs
+                    .iter()
+                    .filter_map(|tab| {
+                        let history = tab.get("entries").and_then(Value::as_array)?;
+                        let index = tab.get("index").and_then(Value::as_u64).unwrap_or(history.len() as u64);
+                        let entry = history.get((index as usize).checked_sub(1)?).or(history.last())?;
+                        let url = entry.get("url").and_then(Value::as_str)?;
+                        let title = entry.get("title").and_then(Value::as_str).unwrap_or_default();
+                        let added_at = tab
+                            .get("lastAccessed")
+                            .and_then(Value::as_i64)
+                            .map(ms_timestamp_to_datetime)
+                            .unwrap_or(fallback);
+                        Some((url.to_string(), title.to_string(), added_at))
+                    })
+                    .collect();
+                window_group("firefox-session", None, entries)
+            })
+            .collect();
+
+        Ok(TabSession {
+            version: TabSession::CURRENT_VERSION,
+            source,
+            groups,
+            created_at: fallback,
+            imported_at: Utc::now(),
+        })
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_pickle_fields_are_padded_to_four_bytes() {
+            // size, then "abcde" (len 5, padded to 8), then UTF-16 "hé", then an i64
+            let mut bytes = vec![0u8; 4];
+            bytes.extend(5i32.to_le_bytes());
+            bytes.extend(b"abcde\0\0\0");
+            bytes.extend(2i32.to_le_bytes());
+            bytes.extend([b'h', 0, 0xe9, 0]);
+            bytes.extend(42i64.to_le_bytes());
+            let size = (bytes.len() - 4) as u32;
+            bytes[..4].copy_from_slice(&size.to_le_bytes());
+
+            let mut pickle = Pickle::new(&bytes).unwrap();
+            assert_eq!(pickle.read_string().as_deref(), Some("abcde"));
+            assert_eq!(pickle.read_string16().as_deref(), Some("hé"));
+            assert_eq!(pickle.read_i64(), Some(42));
+            assert_eq!(pickle.read_i32(), None);
+        }
+
+        #[test]
+        fn test_decode_mozlz4_rejects_other_files() {
+            assert!(decode_mozlz4(b"{\"windows\": []}").is_err());
+            let mut bytes = MOZLZ4_MAGIC.to_vec();
+            bytes.extend(4u32.to_le_bytes());
+            bytes.extend(lz4_flex::block::compress(b"null"));
+            assert_eq!(decode_mozlz4(&bytes).unwrap(), b"null");
+        }
+    }
+}
+
+pub use history::{
+    read_chromium_bookmarks, read_chromium_history, read_firefox_places, read_profile_activity, resolve_profile_dir,
+};
+
+/// Browser history and bookmarks, read to tell when and how often a stored
+/// tab's URL was visited and whether it is bookmarked:
+///
+/// - Chromium keeps visits in the `History` SQLite database (`urls` table,
+///   times in µs since 1601) and bookmarks in the `Bookmarks` JSON file.
+/// - Firefox keeps both in `places.sqlite` (`moz_places`, times in µs since
+///   1970, and `moz_bookmarks`).
+///
+/// Databases are read from a copy, as the browser may hold them open.
+mod history {
+    use super::*;
+    use serde_json::Value;
+    use tablitz_core::{TabActivity, UrlActivity};
+
+    /// Reads visit counts and last visit times from a Chromium `History` database.
+    pub fn read_chromium_history(path: &Path) -> Result<Vec<UrlActivity>> {
+        let (conn, _copy) = open_sqlite_copy(path)?;
+        let mut stmt = conn
+            .prepare("SELECT url, visit_count, last_visit_time FROM urls")
+            .with_context(|| format!("{} is not a Chromium History database", path.display()))?;
+        let rows = stmt.query_map([], |row| {
+            Ok(UrlActivity {
+                url: row.get(0)?,
+                activity: TabActivity {
+                    last_visited_at: chromium_time(row.get(2)?),
+                    visit_count: row.get::<_, i64>(1)?.max(0) as u64,
+                    bookmarked: false,
+                },
+            })
+        })?;
+        rows.collect::<rusqlite::Result<_>>()
+            .with_context(|| format!("Failed to read {}", path.display()))
+    }
+
+    /// Reads the bookmarked URLs from a Chromium `Bookmarks` file.
+    pub fn read_chromium_bookmarks(path: &Path) -> Result<Vec<UrlActivity>> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let json: Value = serde_json::from_str(&content)
+            .with_context(|| format!("{} is not a Chromium Bookmarks file", path.display()))?;
+
+        fn walk(node: &Value, out: &mut Vec<UrlActivity>) {
+            if node.get("type").and_then(Value::as_str) == Some("url") {
+                if let Some(url) = node.get("url").and_then(Value::as_str) {
+                    out.push(UrlActivity {
+                        url: url.to_string(),
+                        activity: TabActivity { bookmarked: true, ..Default::default() },
+                    });
+                }
+            }
+            for child in node.get("children").and_then(Value::as_array).into_iter().flatten() {
+                walk(child, out);
+            }
+        }
+
+        let mut bookmarks = Vec::new();
+        // `roots` holds `bookmark_bar`, `other` and `synced`, plus a checksum
+        for root in json.get("roots").and_then(Value::as_object).into_iter().flat_map(|r| r.values()) {
+            walk(root, &mut bookmarks);
+        }
+        Ok(bookmarks)
+    }
+
+    /// Reads visit counts, last visit times and bookmarks from a Firefox
+    /// `places.sqlite` database.
+    pub fn read_firefox_places(path: &Path) -> Result<Vec<UrlActivity>> {
+        let (conn, _copy) = open_sqlite_copy(path)?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT p.url, p.visit_count, p.last_visit_date,
+                        EXISTS (SELECT 1 FROM moz_bookmarks b WHERE b.fk = p.id)
+                    FROM moz_places p",
+            )
+            .with_context(|| format!("{} is not a Firefox places database", path.display()))?;
+        let rows = stmt.query_map([], |row| {
+            let last_visit: Option<i64> = row.get(2)?;
+            Ok(UrlActivity {
+                url: row.get(0)?,
+                activity: TabActivity {
+                    last_visited_at: last_visit.filter(|us| *us > 0).and_then(chrono::DateTime::from_timestamp_micros),
+                    visit_count: row.get::<_, i64>(1)?.max(0) as u64,
+                    bookmarked: row.get(3)?,
+                },
+            })
+        })?;
+        let places: Vec<UrlActivity> = rows
+            .collect::<rusqlite::Result<_>>()
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        // Places also holds URLs that were only ever prefetched or typed
+        Ok(places.into_iter().filter(|p| !p.activity.is_empty()).collect())
+    }
+
+    /// Finds a browser profile's directory: a Chromium profile directory under
+    /// one of the browser's data directories (the data directory itself for
+    /// single-profile browsers like Opera), or a Firefox profile from `profiles.ini`.
+    pub fn resolve_profile_dir(browser: &Browser, profile: &str) -> Result<PathBuf> {
+        if browser.is_firefox() {
+            return Ok(resolve_firefox_profile(&firefox_root_dir(browser)?, profile)?.path);
+        }
+        let has_profile_data = |dir: &Path| ["History", "Bookmarks", "Preferences"].iter().any(|f| dir.join(f).is_file());
+        let data_dirs = browser.data_dirs();
+        data_dirs
+            .iter()
+            .map(|dir| dir.join(profile))
+            .chain(data_dirs.iter().filter(|_| profile == "Default").cloned())
+            .find(|dir| has_profile_data(dir))
+            .with_context(|| format!("No {} profile '{}' found", browser.name, profile))
+    }
+
+    /// Reads every history and bookmarks source in a profile directory:
+    /// `History` and `Bookmarks` for Chromium-family browsers, `places.sqlite`
+    /// for Firefox. Fails if the profile has none of them.
+    pub fn read_profile_activity(browser: &Browser, profile_dir: &Path) -> Result<Vec<UrlActivity>> {
+        type Reader = fn(&Path) -> Result<Vec<UrlActivity>>;
+        let sources: &[(&str, Reader)] = if browser.is_firefox() {
+            &[("places.sqlite", read_firefox_places)]
+        } else {
+            &[("History", read_chromium_history), ("Bookmarks", read_chromium_bookmarks)]
+        };
+        let mut found = false;
+        let mut activity = Vec::new();
+        for (file, read) in sources {
+            let path = profile_dir.join(file);
+            if path.is_file() {
+                found = true;
+                activity.extend(read(&path)?);
+            }
+        }
+        if !found {
+            anyhow::bail!("No history or bookmarks in {}", profile_dir.display());
+        }
+        Ok(activity)
+    }
 }
 
 /// Configuration options for the recovery process.
@@ -657,21 +3730,30 @@ fn parse_markdown_format(content: &str) -> Result<Vec<TabGroup>> {
 pub struct RecoverOptions {
     /// The browser to recover from
     pub browser: Browser,
-    /// The profile name (e.g., "Default", "Profile 1")
+    /// The profile name (e.g., "Default", "Profile 1"; for Firefox, the name from `profiles.ini`)
     pub profile: String,
     /// If true, only validates the path without reading data
     pub dry_run: bool,
-    /// Optional override for the auto-resolved LevelDB path
+    /// Optional override for the auto-resolved LevelDB path (for Firefox, an
+    /// IndexedDB `.sqlite` file or `idb` directory)
     pub db_path: Option<PathBuf>,
+    /// If true, parse the LevelDB files directly instead of opening the
+    /// database (see [`salvage_leveldb`])
+    pub salvage: bool,
+    /// Recover this historical version of OneTab's state (a LevelDB sequence
+    /// number from [`list_versions`]) instead of the current one
+    pub version: Option<u64>,
 }
 
 impl Default for RecoverOptions {
     fn default() -> Self {
         Self {
-            browser: Browser::Chrome,
+            browser: builtin_browser("chrome").expect("chrome is built in"),
             profile: "Default".to_string(),
             dry_run: false,
             db_path: None,
+            salvage: false,
+            version: None,
         }
     }
 }
@@ -695,14 +3777,13 @@ impl Default for RecoverOptions {
 /// Returns an error if:
 /// - The LevelDB path cannot be resolved (when not overridden)
 /// - The path does not exist or is not a directory
-/// - The LevelDB cannot be opened (even after handling lock contention)
+/// - The LevelDB cannot be opened (even after handling lock contention), unless
+///   `salvage` is set
 /// - Data parsing fails
 pub fn recover(opts: RecoverOptions) -> Result<TabSession> {
-    let db_path = if let Some(custom_path) = opts.db_path {
-        custom_path
-    } else {
-        resolve_leveldb_path(&opts.browser, &opts.profile)?
-    };
+    let db_path = store_path(&opts)?;
+
+    let source = opts.browser.session_source(&opts.profile);
 
     if opts.dry_run {
         println!(
@@ -710,27 +3791,27 @@ pub fn recover(opts: RecoverOptions) -> Result<TabSession> {
             db_path.display()
         );
         return Ok(TabSession {
-            version: 1,
-            source: match opts.browser {
-                Browser::Chrome => SessionSource::Chrome {
-                    profile: opts.profile.clone(),
-                },
-                Browser::Edge => SessionSource::Edge {
-                    profile: opts.profile.clone(),
-                },
-                Browser::Brave => SessionSource::Brave {
-                    profile: opts.profile.clone(),
-                },
-                Browser::Comet => SessionSource::Comet {
-                    profile: opts.profile.clone(),
-                },
-            },
+            version: TabSession::CURRENT_VERSION,
+            source,
             groups: Vec::new(),
             created_at: Utc::now(),
             imported_at: Utc::now(),
         });
     }
 
+    if opts.browser.is_firefox() {
+        if opts.salvage || opts.version.is_some() {
+            anyhow::bail!("Salvage mode and version history only apply to LevelDB stores");
+        }
+        if !db_path.exists() {
+            return Err(anyhow::anyhow!(
+                "OneTab IndexedDB path does not exist: {}",
+                db_path.display()
+            ));
+        }
+        return extract_from_firefox_idb(&db_path, source);
+    }
+
     if !db_path.exists() {
         return Err(anyhow::anyhow!(
             "OneTab LevelDB path does not exist: {}",
@@ -745,84 +3826,255 @@ pub fn recover(opts: RecoverOptions) -> Result<TabSession> {
         ));
     }
 
-    let source = match opts.browser {
-        Browser::Chrome => SessionSource::Chrome {
-            profile: opts.profile.clone(),
-        },
-        Browser::Edge => SessionSource::Edge {
-            profile: opts.profile.clone(),
-        },
-        Browser::Brave => SessionSource::Brave {
-            profile: opts.profile.clone(),
-        },
-        Browser::Comet => SessionSource::Comet {
-            profile: opts.profile.clone(),
-        },
-    };
+    if let Some(sequence) = opts.version {
+        return extract_leveldb_version(&db_path, sequence, source);
+    }
+
+    if opts.salvage {
+        return salvage_from_leveldb(&db_path, source);
+    }
 
     extract_from_leveldb(&db_path, source)
 }
 
-/// List all auto-detected OneTab LevelDB paths on this system across all supported browsers.
-///
-/// Attempts to resolve the default profile ("Default") for each browser and returns
-/// all paths that exist. This is useful for discovering which browsers have OneTab
-/// installed and have data available for recovery.
-///
-/// # Returns
+/// Lists every distinct version of OneTab's state in a LevelDB store, oldest
+/// first, so one can be picked with [`RecoverOptions::version`].
 ///
-/// A vector of tuples containing (browser, profile, path) for each detected store.
-pub fn detect_all_onetab_stores() -> Vec<(Browser, String, PathBuf)> {
-    let browsers = [
-        Browser::Chrome,
-        Browser::Edge,
-        Browser::Brave,
-        Browser::Comet,
-    ];
-
-    let mut detected = Vec::new();
-
-    for browser in &browsers {
-        // Try common profile names
-        let profiles = vec!["Default".to_string(), "Profile 1".to_string()];
-
-        for profile in &profiles {
-            if let Ok(path) = resolve_leveldb_path(browser, profile) {
-                if path.exists() && path.is_dir() {
-                    detected.push((*browser, profile.clone(), path));
-                }
-            }
-        }
+/// Old versions survive in older tables and log files until LevelDB compacts
+/// them away, so this is how to undo a bad overwrite.
+pub fn list_versions(opts: &RecoverOptions) -> Result<Vec<SalvagedValue>> {
+    if opts.browser.is_firefox() {
+        anyhow::bail!("Version history is only available for LevelDB stores");
+    }
+    let db_path = store_path(opts)?;
+    if !db_path.is_dir() {
+        return Err(anyhow::anyhow!(
+            "OneTab LevelDB path is not a directory: {}",
+            db_path.display()
+        ));
     }
+    Ok(salvage_leveldb(&db_path)?.values)
+}
 
-    detected
+/// The store path from `opts`, resolving it when not overridden.
+fn store_path(opts: &RecoverOptions) -> Result<PathBuf> {
+    if let Some(custom_path) = &opts.db_path {
+        Ok(custom_path.clone())
+    } else if opts.browser.is_firefox() {
+        let profile = resolve_firefox_profile(&firefox_root_dir(&opts.browser)?, &opts.profile)?;
+        resolve_firefox_idb_path(&profile.path, &opts.browser.extension_ids)
+    } else {
+        resolve_leveldb_path(&opts.browser, &opts.profile)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn browser(id: &str) -> Browser {
+        builtin_browser(id).unwrap()
+    }
+
     #[test]
     fn test_browser_extension_ids() {
+        for id in ["chrome", "brave", "chromium", "vivaldi", "opera", "arc"] {
+            assert_eq!(browser(id).extension_ids, ["chphlpgkkbolifaimnlloiipkdnihall"]);
+        }
+        assert_eq!(browser("edge").extension_ids, ["hoimpamkkoehapgenciaoajfkfkpgfop"]);
+        assert_eq!(browser("firefox").extension_ids, ["extension@one-tab.com"]);
+        assert!(browser("firefox").is_firefox());
+    }
+
+    #[test]
+    fn test_browser_display_names() {
+        assert_eq!(browser("chrome").display_name(), "Chrome");
+        assert_eq!(browser("edge").display_name(), "Edge");
+        assert!(browser("comet").display_name().contains("Perplexity"));
+        assert_eq!(browser("firefox").display_name(), "Firefox");
+    }
+
+    #[test]
+    fn test_builtin_path_templates_expand() {
+        let vars = PathVars {
+            home: Some(PathBuf::from("/home/u")),
+            config: Some(PathBuf::from("/home/u/.config")),
+            data_local: None,
+        };
+        let linux = |id: &str| -> Vec<PathBuf> {
+            browser(id).linux.iter().filter_map(|t| vars.expand(t)).collect()
+        };
+
         assert_eq!(
-            Browser::Chrome.onetab_extension_id(),
-            "chphlpgkkbolifaimnlloiipkdnihall"
+            linux("chromium"),
+            vec![
+                PathBuf::from("/home/u/.config/chromium"),
+                PathBuf::from("/home/u/.var/app/org.chromium.Chromium/config/chromium"),
+                PathBuf::from("/home/u/snap/chromium/common/chromium"),
+            ]
         );
         assert_eq!(
-            Browser::Brave.onetab_extension_id(),
-            "chphlpgkkbolifaimnlloiipkdnihall"
+            linux("brave")[2],
+            PathBuf::from("/home/u/snap/brave/current/.config/BraveSoftware/Brave-Browser")
         );
+        assert_eq!(linux("firefox")[0], PathBuf::from("/home/u/.mozilla/firefox"));
+        assert!(linux("arc").is_empty());
+        // Templates whose base directory is unknown are skipped
+        assert!(vars.expand("{data_local}/Google/Chrome/User Data").is_none());
+        assert_eq!(vars.expand("~/x/y"), Some(PathBuf::from("/home/u/x/y")));
+        assert_eq!(vars.expand("/opt/b"), Some(PathBuf::from("/opt/b")));
+
+        // Every built-in template is well formed
+        for b in BrowserRegistry::builtin().browsers() {
+            for t in b.windows.iter().chain(&b.macos).chain(&b.linux) {
+                assert!(split_template(t).is_ok(), "{}", t);
+            }
+        }
+    }
+
+    #[test]
+    fn test_registry_config_adds_and_extends_browsers() {
+        let mut registry = BrowserRegistry::builtin();
+        registry
+            .add_toml(
+                r#"
+                [[browser]]
+                id = "Thorium"
+                extension_ids = ["chphlpgkkbolifaimnlloiipkdnihall"]
+                linux = ["{config}/thorium"]
+
+                [[browser]]
+                id = "chrome"
+                extension_ids = ["abcdefghijklmnopabcdefghijklmnop"]
+                linux = ["/opt/chrome-profile"]
+
+                [other]
+                ignored = true
+                "#,
+            )
+            .unwrap();
+
+        let thorium = registry.browser("thorium").unwrap();
+        assert_eq!(thorium.name, "thorium");
+        assert_eq!(thorium.storage, StorageKind::Leveldb);
+        assert!(matches!(
+            thorium.session_source("Default"),
+            SessionSource::Browser { browser, profile } if browser == "thorium" && profile == "Default"
+        ));
+
+        let chrome = registry.get("CHROME").unwrap();
+        assert_eq!(chrome.name, "Chrome");
         assert_eq!(
-            Browser::Edge.onetab_extension_id(),
-            "hoimpamkkoehapgenciaoajfkfkpgfop"
+            chrome.extension_ids,
+            ["abcdefghijklmnopabcdefghijklmnop", "chphlpgkkbolifaimnlloiipkdnihall"]
         );
+        assert_eq!(chrome.linux.last().unwrap(), "/opt/chrome-profile");
+        assert_eq!(registry.browsers().len(), BrowserRegistry::builtin().browsers().len() + 1);
+
+        let err = registry.browser("netscape").unwrap_err().to_string();
+        assert!(err.contains("thorium"), "{}", err);
     }
 
     #[test]
-    fn test_browser_display_names() {
-        assert_eq!(Browser::Chrome.display_name(), "Chrome");
-        assert_eq!(Browser::Edge.display_name(), "Edge");
-        assert!(Browser::Comet.display_name().contains("Perplexity"));
+    fn test_registry_rejects_bad_entries() {
+        let mut registry = BrowserRegistry::builtin();
+        assert!(registry.add_toml("[[browser]]\nid = \"x\"\n").is_err());
+        assert!(registry
+            .add_toml("[[browser]]\nid = \"x\"\nextension_ids = [\"a\"]\nlinux = [\"{appdata}/x\"]\n")
+            .is_err());
+        assert!(registry
+            .add_toml("[[browser]]\nid = \"x\"\nextension_ids = [\"a\"]\nlinux = [\"relative/x\"]\n")
+            .is_err());
+        assert!(registry
+            .add_toml("[[browser]]\nid = \"x\"\nextension_ids = [\"a\"]\nextension_id = \"b\"\n")
+            .is_err());
+        assert!(registry.get("x").is_none());
+    }
+
+    #[test]
+    fn test_canonical_rules_share_the_config_file() {
+        let config = "[[browser]]\nid = \"thorium\"\nextension_ids = [\"a\"]\n\n\
+                      [canonical]\nextra_tracking_params = [\"share_id\"]\nfold_www = false\n\n\
+                      [[canonical.domain]]\ndomain = \"youtube.com\"\nkeep_query = [\"v\"]\n";
+        let rules = parse_canonical_rules(config).unwrap();
+        assert_eq!(rules.extra_tracking_params, ["share_id"]);
+        assert!(!rules.fold_www && rules.fold_http);
+        assert_eq!(rules.domain[0].keep_query.as_deref(), Some(&["v".to_string()][..]));
+        assert!(BrowserRegistry::builtin().add_toml(config).is_ok());
+
+        assert_eq!(parse_canonical_rules("").unwrap(), tablitz_core::CanonicalRules::default());
+        assert!(parse_canonical_rules("[canonical]\nfold_wwww = false\n").is_err());
+    }
+
+    #[test]
+    fn test_leveldb_candidates_probe_root_profile_and_every_extension_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut opera = browser("opera");
+        opera.extension_ids.insert(0, "forked".to_string());
+        assert_eq!(
+            leveldb_candidates(&opera, dir.path(), "Default"),
+            vec![
+                dir.path().join("Default/Local Extension Settings/forked"),
+                dir.path().join("Local Extension Settings/forked"),
+                dir.path().join("Default/Local Extension Settings/chphlpgkkbolifaimnlloiipkdnihall"),
+                dir.path().join("Local Extension Settings/chphlpgkkbolifaimnlloiipkdnihall"),
+            ]
+        );
+        assert_eq!(
+            leveldb_candidates(&browser("chrome"), dir.path(), "Profile 1"),
+            vec![dir.path().join("Profile 1/Local Extension Settings/chphlpgkkbolifaimnlloiipkdnihall")]
+        );
+    }
+
+    #[test]
+    fn test_firefox_has_no_leveldb_path() {
+        let firefox = browser("firefox");
+        assert!(resolve_leveldb_path(&firefox, "Default").is_err());
+        assert!(matches!(
+            firefox.session_source("work"),
+            SessionSource::Firefox { profile } if profile == "work"
+        ));
+    }
+
+    /// Encodes a `(tag, data)` pair as a structured clone word.
+    fn sc_pair(tag: u32, data: u32) -> [u8; 8] {
+        (((tag as u64) << 32) | data as u64).to_le_bytes()
+    }
+
+    #[test]
+    fn test_structured_clone_decodes_values() {
+        let mut bytes = Vec::new();
+        bytes.extend(sc_pair(0xFFF1_0000, 0)); // header
+        bytes.extend(sc_pair(0xFFFF_0008, 0)); // object
+        bytes.extend(sc_pair(0xFFFF_0004, 0x8000_0001)); // latin1 "n"
+        bytes.extend(b"n\0\0\0\0\0\0\0");
+        bytes.extend(1_760_074_389_851f64.to_le_bytes());
+        bytes.extend(sc_pair(0xFFFF_0004, 2)); // UTF-16 "é!"
+        bytes.extend([0xE9, 0x00, b'!', 0x00, 0, 0, 0, 0]);
+        bytes.extend(sc_pair(0xFFFF_0007, 2)); // array of length 2
+        bytes.extend(sc_pair(0xFFFF_0003, 0));
+        bytes.extend(sc_pair(0xFFFF_0002, 1));
+        bytes.extend(sc_pair(0xFFFF_0003, 1));
+        bytes.extend(sc_pair(0xFFFF_0003, (-5i32) as u32));
+        bytes.extend(sc_pair(0xFFFF_0013, 0)); // end of array
+        bytes.extend(sc_pair(0xFFFF_0004, 0x8000_0001)); // "b"
+        bytes.extend(b"b\0\0\0\0\0\0\0");
+        bytes.extend(sc_pair(0xFFFF_000D, 1)); // back reference to the array
+        bytes.extend(sc_pair(0xFFFF_0013, 0)); // end of object
+
+        let value = structured_clone::decode(&bytes).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({"n": 1_760_074_389_851i64, "é!": [true, -5], "b": [true, -5]})
+        );
+    }
+
+    #[test]
+    fn test_structured_clone_rejects_truncated_and_unknown() {
+        let mut bytes = Vec::new();
+        bytes.extend(sc_pair(0xFFFF_0004, 0x8000_0010)); // 16-char string, no data
+        assert!(structured_clone::decode(&bytes).is_err());
+        assert!(structured_clone::decode(&sc_pair(0xFFFF_0006, 0)).is_err()); // RegExp
     }
 
     #[test]
@@ -839,6 +4091,18 @@ mod tests {
         assert_eq!(detect_format(markdown_content), ExportFormat::Markdown);
     }
 
+    #[test]
+    fn test_pipe_title_escaping() {
+        for title in ["plain", "a | b", "line\nbreak\r\n", "C:\\path\\n", "trailing \\", "\\|"] {
+            let escaped = escape_pipe_title(title);
+            assert!(!escaped.contains('\n') && !escaped.contains('\r'));
+            assert_eq!(unescape_pipe_title(&escaped), title);
+        }
+        assert_eq!(escape_pipe_title("a | b\nc"), "a \\| b\\nc");
+        // Backslashes in OneTab's own exports are not escape sequences
+        assert_eq!(unescape_pipe_title("C:\\Users\\x"), "C:\\Users\\x");
+    }
+
     #[test]
     fn test_parse_pipe_format() {
         let content = r#"https://example.com | Example Site
@@ -862,19 +4126,41 @@ https://example.org | Third Site"#;
 [Example Site](https://example.com)
 [Other Site](https://other.com)
 "#;
-        let groups = parse_markdown_format(content).unwrap();
+        let groups = markdown::parse_markdown_format(content, Timezone::utc()).unwrap();
         assert_eq!(groups.len(), 1);
         assert_eq!(groups[0].tabs.len(), 2);
         assert_eq!(groups[0].tabs[0].title, "Example Site");
         assert_eq!(groups[0].tabs[0].url.as_str(), "https://example.com/");
     }
 
+    #[test]
+    fn test_serialized_state_round_trips() {
+        let value = r#"{"tabGroups":[{"id":"g1","createDate":1700000000000,"title":"Reading","starred":true,"tabsMeta":[{"id":"t1","url":"https://example.com/","title":"Example","favicon":"https://example.com/f.ico"}]},{"id":"g2","createDate":1710000000000,"tabsMeta":[{"id":"t2","url":"https://rust-lang.org/","title":"Rust"}]}]}"#;
+        let groups = parse_onetab_state(value).unwrap();
+        let session = TabSession {
+            version: 1,
+            source: SessionSource::Unknown,
+            groups: groups.clone(),
+            created_at: Utc::now(),
+            imported_at: Utc::now(),
+        };
+
+        let encoded = serialize_onetab_state(&session).unwrap();
+        assert!(encoded.starts_with("\"{\\\"tabGroups\\\":"), "double-encoded: {}", encoded);
+        let mut decoded = parse_onetab_state(&encoded).unwrap();
+        assert_eq!(decoded[0].id, "g2", "newest group first");
+        decoded.reverse();
+        assert_eq!(decoded, groups);
+    }
+
     #[test]
     fn test_recover_options_default() {
         let opts = RecoverOptions::default();
-        assert!(matches!(opts.browser, Browser::Chrome));
+        assert_eq!(opts.browser.id, "chrome");
         assert_eq!(opts.profile, "Default");
         assert!(!opts.dry_run);
         assert!(opts.db_path.is_none());
+        assert!(!opts.salvage);
+        assert!(opts.version.is_none());
     }
 }
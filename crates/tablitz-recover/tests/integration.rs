@@ -102,6 +102,110 @@ fn test_parse_pipe_format_real_data_shape() {
     assert_eq!(session.groups[2].tabs.len(), 1);
 }
 
+// ─── Pipe format – export round trip ───────────────────────────────────────
+
+/// Parses `content` as an export file.
+fn parse_str(content: &str) -> tablitz_core::TabSession {
+    let f = write_tmp(content);
+    parse_onetab_export(f.path()).unwrap()
+}
+
+/// A group of `(url, title)` tabs.
+fn pipe_group(id: &str, tabs: &[(&str, &str)]) -> tablitz_core::TabGroup {
+    tablitz_core::TabGroup {
+        id: id.to_string(),
+        label: Some(format!("label {}", id)),
+        created_at: chrono::Utc::now(),
+        tabs: tabs
+            .iter()
+            .enumerate()
+            .map(|(i, (url, title))| tablitz_core::Tab {
+                id: format!("{}-t{}", id, i),
+                url: url::Url::parse(url).unwrap(),
+                title: title.to_string(),
+                favicon_url: None,
+                added_at: chrono::Utc::now(),
+                tags: Vec::new(),
+                activity: Default::default(),
+            })
+            .collect(),
+        pinned: false,
+        locked: false,
+        starred: false,
+        tags: Vec::new(),
+    }
+}
+
+#[test]
+fn test_pipe_export_round_trip_is_identical() {
+    use tablitz_recover::format_pipe_export;
+
+    let groups = vec![
+        pipe_group(
+            "g1",
+            &[
+                ("https://example.com/one", "First | with a pipe"),
+                ("https://example.com/a|b?q=x|y", "URL with pipes"),
+                ("https://example.com/two", "Multi\nline\r\ntitle"),
+            ],
+        ),
+        pipe_group("empty", &[]),
+        pipe_group(
+            "g2",
+            &[
+                ("https://example.com/three", "  padded  "),
+                ("https://example.com/four", r"C:\Users\me\notes.txt \n"),
+                ("https://example.com/five", ""),
+            ],
+        ),
+        pipe_group("g3", &[("https://example.com/six", "--- ## 3 tabs ---")]),
+    ];
+
+    let exported = format_pipe_export(&groups);
+    assert_eq!(exported.lines().filter(|l| l.is_empty()).count(), 2, "one blank line between groups");
+    assert!(exported.contains("https://example.com/a%7Cb?q=x%7Cy | URL with pipes\n"));
+    assert!(exported.contains(r"Multi\nline\r\ntitle"));
+
+    let parsed = parse_str(&exported);
+    assert_eq!(parsed.groups.len(), 3, "group boundaries survive; empty groups are dropped");
+    let sizes: Vec<usize> = parsed.groups.iter().map(|g| g.tabs.len()).collect();
+    assert_eq!(sizes, vec![3, 3, 1]);
+    assert_eq!(parsed.groups[0].tabs[0].title, "First | with a pipe");
+    assert_eq!(parsed.groups[0].tabs[2].title, "Multi\nline\r\ntitle");
+    assert_eq!(parsed.groups[1].tabs[0].title, "padded");
+    assert_eq!(parsed.groups[1].tabs[1].title, r"C:\Users\me\notes.txt \n");
+    assert_eq!(parsed.groups[1].tabs[2].title, "");
+
+    assert_eq!(format_pipe_export(&parsed.groups), exported);
+}
+
+#[test]
+fn test_pipe_export_of_onetab_file_is_stable() {
+    use tablitz_recover::format_pipe_export;
+
+    // Shaped like OneTab's own export: unescaped pipes in titles, odd spacing
+    let original = concat!(
+        "https://x.com/status/1 | #pragma omp ⟨ε|Δ⟩ on X\n",
+        "https://github.com/bkataru |   bkataru (Baalateja Kataru)\n",
+        "\n",
+        "\n",
+        "https://mail.google.com/mail/u/0/#inbox | Inbox (385) - Gmail\n",
+    );
+    let first = format_pipe_export(&parse_str(original).groups);
+    assert_eq!(
+        first,
+        concat!(
+            "https://x.com/status/1 | #pragma omp ⟨ε\\|Δ⟩ on X\n",
+            "https://github.com/bkataru | bkataru (Baalateja Kataru)\n",
+            "\n",
+            "https://mail.google.com/mail/u/0/#inbox | Inbox (385) - Gmail\n",
+        )
+    );
+    let reparsed = parse_str(&first);
+    assert_eq!(reparsed.groups[0].tabs[0].title, "#pragma omp ⟨ε|Δ⟩ on X");
+    assert_eq!(format_pipe_export(&reparsed.groups), first);
+}
+
 // ─── Markdown format – basic ────────────────────────────────────────────────
 
 #[test]
@@ -200,6 +304,227 @@ fn test_parse_markdown_format_special_chars_in_title() {
     assert_eq!(session.groups[0].tabs[1].url.as_str(), "https://crates.io/");
 }
 
+// ─── Markdown dates and IDs ────────────────────────────────────────────────
+
+fn parse_md_in(content: &str, timezone: &str) -> tablitz_core::TabSession {
+    let f = write_tmp(content);
+    tablitz_recover::parse_onetab_export_with_timezone(f.path(), timezone.parse().unwrap()).unwrap()
+}
+
+fn md_group(created: &str, url: &str) -> String {
+    format!("---\n## 1 tabs\n> {}\n\n[Tab]({})\n\n", created, url)
+}
+
+fn utc(rfc3339: &str) -> chrono::DateTime<chrono::Utc> {
+    rfc3339.parse().unwrap()
+}
+
+#[test]
+fn test_markdown_created_times_are_kept() {
+    let content = [
+        md_group("Created 3/20/2025, 10:08:46 PM", "https://example.com/us"),
+        md_group("Created 2025/3/21 09:15:00", "https://example.com/ja"),
+        md_group("Created 2025-03-22 23:59:59", "https://example.com/iso"),
+    ]
+    .concat();
+    let session = parse_md_in(&content, "utc");
+    let created: Vec<_> = session.groups.iter().map(|g| g.created_at).collect();
+    assert_eq!(
+        created,
+        vec![utc("2025-03-20T22:08:46Z"), utc("2025-03-21T09:15:00Z"), utc("2025-03-22T23:59:59Z")]
+    );
+    assert!(session.groups.iter().all(|g| g.tabs[0].added_at == g.created_at));
+
+    // The same wall-clock time two hours east of UTC
+    let session = parse_md_in(&content, "+02:00");
+    assert_eq!(session.groups[0].created_at, utc("2025-03-20T20:08:46Z"));
+}
+
+#[test]
+fn test_markdown_day_first_dates_are_inferred_per_file() {
+    // 20.3 can only be day-first, so 4.3 in the same file is the 4th of March
+    let content = [
+        md_group("Created 20.3.2025, 22:08:46", "https://example.com/a"),
+        md_group("Created 4.3.2025, 08:00:00", "https://example.com/b"),
+    ]
+    .concat();
+    let session = parse_md_in(&content, "utc");
+    assert_eq!(session.groups[1].created_at, utc("2025-03-04T08:00:00Z"));
+
+    // Without that evidence, US month-first order is assumed
+    let session = parse_md_in(&md_group("Created 4/3/2025, 8:00:00 AM", "https://example.com/b"), "utc");
+    assert_eq!(session.groups[0].created_at, utc("2025-04-03T08:00:00Z"));
+}
+
+#[test]
+fn test_markdown_ids_are_content_hashes() {
+    let a = md_group("Created 3/20/2025, 10:08:46 PM", "https://example.com/a");
+    let b = md_group("Created 3/21/2025, 10:08:46 PM", "https://example.com/b");
+    let first = parse_md_in(&a, "utc");
+    let both = parse_md_in(&[b.clone(), a.clone()].concat(), "utc");
+    let other = parse_md_in(&b, "utc");
+
+    assert!(first.groups[0].id.starts_with("markdown-"));
+    assert_ne!(first.groups[0].id, other.groups[0].id, "different files must not collide");
+    // A group keeps its ID wherever it appears, so re-importing it is a no-op
+    assert_eq!(both.groups[1].id, first.groups[0].id);
+    assert_eq!(both.groups[1].tabs[0].id, first.groups[0].tabs[0].id);
+}
+
+#[test]
+fn test_markdown_export_round_trip_keeps_dates_and_labels() {
+    use tablitz_recover::{format_markdown_export, Timezone};
+
+    let mut reading = pipe_group("g1", &[("https://example.com/one", "One"), ("https://example.com/two", "Two")]);
+    reading.created_at = utc("2024-11-05T00:30:15Z");
+    reading.tags = vec!["papers".to_string(), "rust".to_string()];
+    let mut unlabeled = pipe_group("g2", &[("https://example.org/", "Example [draft]")]);
+    unlabeled.label = None;
+    unlabeled.created_at = utc("2025-03-20T12:00:00Z");
+    let groups = vec![reading, unlabeled];
+
+    for timezone in ["utc", "+05:30", "-08:00"] {
+        let tz: Timezone = timezone.parse().unwrap();
+        let text = format_markdown_export(&groups, tz);
+        let session = parse_md_in(&text, timezone);
+        assert_eq!(session.groups.len(), 2);
+        for (read, original) in session.groups.iter().zip(&groups) {
+            assert_eq!(read.created_at, original.created_at, "in {}", timezone);
+            assert_eq!(read.label, original.label);
+            assert_eq!(read.tags, original.tags);
+            let tabs = |g: &tablitz_core::TabGroup| {
+                g.tabs.iter().map(|t| (t.url.clone(), t.title.clone())).collect::<Vec<_>>()
+            };
+            assert_eq!(tabs(read), tabs(original));
+        }
+        assert_eq!(format_markdown_export(&session.groups, tz), text);
+    }
+}
+
+#[test]
+fn test_markdown_export_escapes_titles_and_labels_that_look_like_markup() {
+    use tablitz_recover::{format_markdown_export, Timezone};
+
+    let mut created = pipe_group("g1", &[
+        ("https://example.com/a", "See [the docs](https://docs.rs) first"),
+        ("https://example.com/b", r"C:\Users\[me]"),
+    ]);
+    created.label = Some("Created by hand".to_string());
+    created.created_at = utc("2025-03-20T12:00:00Z");
+    let mut tags = pipe_group("g2", &[("https://example.org/", "Plain")]);
+    tags.label = Some("Tags: not really".to_string());
+    tags.created_at = utc("2025-03-21T12:00:00Z");
+    let mut backslash = pipe_group("g3", &[("https://example.net/", "Plain")]);
+    backslash.label = Some(r"\escaped".to_string());
+    backslash.created_at = utc("2025-03-22T12:00:00Z");
+    let groups = vec![created, tags, backslash];
+
+    let text = format_markdown_export(&groups, Timezone::utc());
+    let session = parse_md_in(&text, "utc");
+    assert_eq!(session.groups.len(), 3);
+    for (read, original) in session.groups.iter().zip(&groups) {
+        assert_eq!(read.label, original.label);
+        assert_eq!(read.created_at, original.created_at);
+        assert!(read.tags.is_empty());
+        let tabs = |g: &tablitz_core::TabGroup| {
+            g.tabs.iter().map(|t| (t.url.clone(), t.title.clone())).collect::<Vec<_>>()
+        };
+        assert_eq!(tabs(read), tabs(original));
+    }
+}
+
+// ─── tablitz native files ──────────────────────────────────────────────────
+
+fn native_file(name: &str, content: &str) -> (tempfile::TempDir, std::path::PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(name);
+    std::fs::write(&path, content).unwrap();
+    (dir, path)
+}
+
+#[test]
+fn test_parse_tablitz_export_detects_shape() {
+    use tablitz_core::{SessionSource, TabSession};
+    use tablitz_recover::parse_tablitz_export;
+
+    let mut group = pipe_group("g1", &[("https://example.com/", "Example")]);
+    group.created_at = utc("2025-03-20T22:08:46.123Z");
+    group.tabs[0].added_at = group.created_at;
+    let groups = vec![group];
+    let session = TabSession {
+        version: 1,
+        source: SessionSource::Chrome { profile: "Work".to_string() },
+        groups: groups.clone(),
+        created_at: chrono::Utc::now(),
+        imported_at: chrono::Utc::now(),
+    };
+
+    // `recover --out`: a whole session, which keeps its source
+    let (_d, path) = native_file("session.json", &serde_json::to_string_pretty(&session).unwrap());
+    let parsed = parse_tablitz_export(&path).unwrap();
+    assert_eq!(parsed.source, session.source);
+    assert_eq!(parsed.groups, groups);
+
+    // `export --format json` and `--format toml`: just the groups, with a
+    // version; older exports were a bare version 1 list
+    #[derive(serde::Serialize)]
+    struct GroupList<'a> {
+        version: u32,
+        groups: &'a [tablitz_core::TabGroup],
+    }
+    let list = GroupList { version: TabSession::CURRENT_VERSION, groups: &groups };
+    for (name, content) in [
+        ("groups.json", serde_json::to_string_pretty(&list).unwrap()),
+        ("groups.toml", toml::to_string(&list).unwrap()),
+        ("unversioned.json", serde_json::to_string_pretty(&groups).unwrap()),
+    ] {
+        let (_d, path) = native_file(name, &content);
+        let parsed = parse_tablitz_export(&path).unwrap();
+        assert_eq!(parsed.groups, groups, "{}", name);
+        assert_eq!(parsed.source, SessionSource::TablitzNative { path: path.to_string_lossy().to_string() });
+    }
+}
+
+/// `groups-v1.json` is what `export --format json` wrote before exports
+/// carried a version: a bare list of groups with dates in milliseconds.
+#[test]
+fn test_parse_tablitz_export_reads_unversioned_group_lists() {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/groups-v1.json");
+    let session = tablitz_recover::parse_tablitz_export(&path).unwrap();
+    assert_eq!(session.version, tablitz_core::TabSession::CURRENT_VERSION);
+    assert_eq!(session.groups.len(), 1);
+    let group = &session.groups[0];
+    assert_eq!(group.id, "abc123");
+    assert_eq!(group.created_at, utc("2025-03-19T18:43:59Z"));
+    assert!(group.starred && group.tags.is_empty());
+    assert_eq!(group.tabs[1].added_at, utc("2025-03-20T22:08:46Z"));
+    assert_eq!(group.tabs[1].favicon_url.as_deref(), Some("https://doc.rust-lang.org/favicon.ico"));
+}
+
+#[test]
+fn test_parse_tablitz_export_rejects_newer_versions_and_other_files() {
+    use tablitz_recover::parse_tablitz_export;
+
+    let (_d, path) = native_file(
+        "future.json",
+        r#"{"version": 99, "source": "Unknown", "groups": [], "created_at": 0, "imported_at": 0}"#,
+    );
+    let err = format!("{:#}", parse_tablitz_export(&path).unwrap_err());
+    assert!(err.contains("format version 99"), "{}", err);
+    let (_d, path) = native_file("future-groups.json", r#"{"version": 99, "groups": []}"#);
+    let err = format!("{:#}", parse_tablitz_export(&path).unwrap_err());
+    assert!(err.contains("format version 99"), "{}", err);
+
+    for (name, content) in [
+        ("tabs.txt", "https://example.com | Example\n"),
+        ("other.json", r#"{"tabGroups": []}"#),
+        ("broken.json", r#"[{"id": "g1"}]"#),
+    ] {
+        let (_d, path) = native_file(name, content);
+        assert!(parse_tablitz_export(&path).is_err(), "{} should be refused", name);
+    }
+}
+
 // ─── Format auto-detection ─────────────────────────────────────────────────
 
 #[test]
@@ -350,3 +675,1111 @@ fn test_extract_from_leveldb_double_encoded_value() {
         "https://docs.rs/serde"
     );
 }
+
+// ─── Firefox IndexedDB ─────────────────────────────────────────────────────
+
+const ONETAB_STATE: &str = r#"{"tabGroups":[{"id":"grp-ff-001","createDate":1760074389851,"title":"Reading","tabsMeta":[{"id":"tab-ff-001","url":"https://example.com/rust","title":"The Rust Programming Language"},{"id":"tab-ff-002","url":"https://docs.rs/serde","title":"serde – Rust"}]}]}"#;
+
+/// Encodes a JSON value in SpiderMonkey's structured clone format.
+fn structured_clone(value: &serde_json::Value) -> Vec<u8> {
+    fn pair(out: &mut Vec<u8>, tag: u32, data: u32) {
+        out.extend((((tag as u64) << 32) | data as u64).to_le_bytes());
+    }
+    fn string(out: &mut Vec<u8>, s: &str) {
+        let mut chars: Vec<u8> = if s.chars().all(|c| (c as u32) < 0x100) {
+            pair(out, 0xFFFF_0004, s.chars().count() as u32 | 0x8000_0000);
+            s.chars().map(|c| c as u8).collect()
+        } else {
+            let units: Vec<u16> = s.encode_utf16().collect();
+            pair(out, 0xFFFF_0004, units.len() as u32);
+            units.iter().flat_map(|u| u.to_le_bytes()).collect()
+        };
+        chars.resize(chars.len().div_ceil(8) * 8, 0);
+        out.extend(chars);
+    }
+    fn write(out: &mut Vec<u8>, value: &serde_json::Value) {
+        use serde_json::Value;
+        match value {
+            Value::Null => pair(out, 0xFFFF_0000, 0),
+            Value::Bool(b) => pair(out, 0xFFFF_0002, *b as u32),
+            Value::Number(n) => out.extend(n.as_f64().unwrap().to_le_bytes()),
+            Value::String(s) => string(out, s),
+            Value::Array(items) => {
+                pair(out, 0xFFFF_0007, items.len() as u32);
+                for (i, item) in items.iter().enumerate() {
+                    pair(out, 0xFFFF_0003, i as u32);
+                    write(out, item);
+                }
+                pair(out, 0xFFFF_0013, 0);
+            }
+            Value::Object(map) => {
+                pair(out, 0xFFFF_0008, 0);
+                for (key, item) in map {
+                    string(out, key);
+                    write(out, item);
+                }
+                pair(out, 0xFFFF_0013, 0);
+            }
+        }
+    }
+    let mut out = Vec::new();
+    pair(&mut out, 0xFFF1_0000, 0);
+    write(&mut out, value);
+    out
+}
+
+/// Creates an IndexedDB database with Firefox's `object_data` layout.
+fn create_idb(path: &std::path::Path) -> rusqlite::Connection {
+    let conn = rusqlite::Connection::open(path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE object_data (
+            object_store_id INTEGER NOT NULL,
+            key BLOB NOT NULL,
+            index_data_values BLOB DEFAULT NULL,
+            file_ids TEXT,
+            data BLOB NOT NULL,
+            PRIMARY KEY (object_store_id, key)
+        ) WITHOUT ROWID;",
+    )
+    .unwrap();
+    conn
+}
+
+/// Stores a value inline, snappy-compressed, as Firefox does for small values.
+fn put_inline(conn: &rusqlite::Connection, key: &str, value: &serde_json::Value) {
+    let compressed = snap::raw::Encoder::new()
+        .compress_vec(&structured_clone(value))
+        .unwrap();
+    conn.execute(
+        "INSERT INTO object_data (object_store_id, key, data) VALUES (1, ?1, ?2)",
+        rusqlite::params![key.as_bytes(), compressed],
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_extract_from_firefox_idb_string_value() {
+    use tablitz_core::SessionSource;
+
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("3647222921wleabcEoxlt-eengsairo.sqlite");
+    let conn = create_idb(&db_path);
+    put_inline(&conn, "settings", &serde_json::json!({"theme": "dark"}));
+    put_inline(&conn, "state", &serde_json::Value::String(ONETAB_STATE.to_string()));
+    drop(conn);
+
+    let session = tablitz_recover::extract_from_firefox_idb(&db_path, SessionSource::Unknown)
+        .expect("extract_from_firefox_idb");
+
+    assert_eq!(session.groups.len(), 1);
+    let group = &session.groups[0];
+    assert_eq!(group.id, "grp-ff-001");
+    assert_eq!(group.label.as_deref(), Some("Reading"));
+    assert_eq!(group.created_at.timestamp_millis(), 1760074389851);
+    assert_eq!(group.tabs.len(), 2);
+    assert_eq!(group.tabs[1].title, "serde – Rust");
+    assert_eq!(group.tabs[1].url.as_str(), "https://docs.rs/serde");
+}
+
+#[test]
+fn test_extract_from_firefox_idb_object_value_in_directory() {
+    use tablitz_core::SessionSource;
+
+    let dir = tempfile::tempdir().unwrap();
+    let conn = create_idb(&dir.path().join("a.sqlite"));
+    let state: serde_json::Value = serde_json::from_str(ONETAB_STATE).unwrap();
+    put_inline(&conn, "state", &state);
+    drop(conn);
+    // A second database holding the same group must not duplicate it
+    let conn = create_idb(&dir.path().join("b.sqlite"));
+    put_inline(&conn, "state", &state);
+    drop(conn);
+
+    let session = tablitz_recover::extract_from_firefox_idb(dir.path(), SessionSource::Unknown)
+        .expect("extract_from_firefox_idb");
+
+    assert_eq!(session.groups.len(), 1);
+    assert_eq!(session.groups[0].tabs[0].title, "The Rust Programming Language");
+}
+
+#[test]
+fn test_extract_from_firefox_idb_external_file_value() {
+    use std::io::Write;
+    use tablitz_core::SessionSource;
+
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("onetab.sqlite");
+    let conn = create_idb(&db_path);
+    conn.execute(
+        "INSERT INTO object_data (object_store_id, key, file_ids, data) VALUES (1, ?1, '.1', 1)",
+        rusqlite::params![b"state".to_vec()],
+    )
+    .unwrap();
+    drop(conn);
+
+    let files_dir = dir.path().join("onetab.files");
+    std::fs::create_dir(&files_dir).unwrap();
+    let mut encoder = snap::write::FrameEncoder::new(Vec::new());
+    encoder
+        .write_all(&structured_clone(&serde_json::Value::String(ONETAB_STATE.to_string())))
+        .unwrap();
+    std::fs::write(files_dir.join("1"), encoder.into_inner().unwrap()).unwrap();
+
+    let session = tablitz_recover::extract_from_firefox_idb(&db_path, SessionSource::Unknown)
+        .expect("extract_from_firefox_idb");
+
+    assert_eq!(session.groups.len(), 1);
+    assert_eq!(session.groups[0].tabs.len(), 2);
+}
+
+#[test]
+fn test_extract_from_firefox_idb_rejects_non_idb_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("other.sqlite");
+    rusqlite::Connection::open(&db_path)
+        .unwrap()
+        .execute_batch("CREATE TABLE t (x INTEGER);")
+        .unwrap();
+
+    let result = tablitz_recover::extract_from_firefox_idb(&db_path, tablitz_core::SessionSource::Unknown);
+    assert!(result.is_err());
+}
+
+/// Lays out a Firefox root with two profiles, OneTab installed in "work".
+fn fake_firefox_root() -> tempfile::TempDir {
+    let root = tempfile::tempdir().unwrap();
+    std::fs::write(
+        root.path().join("profiles.ini"),
+        "[Profile1]\nName=default\nIsRelative=1\nPath=abcd1234.default\nDefault=1\n\n\
+         [Profile0]\nName=work\nIsRelative=1\nPath=wxyz5678.work\n\n\
+         [General]\nStartWithLastProfile=1\nVersion=2\n\n\
+         [Install4F96D1932A9F858E]\nDefault=wxyz5678.work\nLocked=1\n",
+    )
+    .unwrap();
+
+    let profile = root.path().join("wxyz5678.work");
+    std::fs::create_dir_all(&profile).unwrap();
+    std::fs::write(
+        profile.join("prefs.js"),
+        "// Mozilla User Preferences\n\
+         user_pref(\"browser.startup.page\", 3);\n\
+         user_pref(\"extensions.webextensions.uuids\", \"{\\\"extension@one-tab.com\\\":\\\"0d6f3c5e-1b2a-4c3d-9e8f-7a6b5c4d3e2f\\\",\\\"other@example.com\\\":\\\"11111111-2222-3333-4444-555555555555\\\"}\");\n",
+    )
+    .unwrap();
+    let idb = profile
+        .join("storage/default")
+        .join("moz-extension+++0d6f3c5e-1b2a-4c3d-9e8f-7a6b5c4d3e2f^userContextId=4294967295")
+        .join("idb");
+    std::fs::create_dir_all(&idb).unwrap();
+    std::fs::create_dir_all(
+        profile.join("storage/default/moz-extension+++11111111-2222-3333-4444-555555555555/idb"),
+    )
+    .unwrap();
+    let conn = create_idb(&idb.join("3647222921wleabcEoxlt-eengsairo.sqlite"));
+    put_inline(&conn, "state", &serde_json::Value::String(ONETAB_STATE.to_string()));
+    root
+}
+
+#[test]
+fn test_list_firefox_profiles_uses_install_default() {
+    let root = fake_firefox_root();
+    let profiles = tablitz_recover::list_firefox_profiles(root.path()).unwrap();
+
+    assert_eq!(profiles.len(), 2);
+    let work = profiles.iter().find(|p| p.name == "work").unwrap();
+    assert!(work.is_default, "the [Install…] default wins over Default=1");
+    assert_eq!(work.path, root.path().join("wxyz5678.work"));
+    assert!(!profiles.iter().find(|p| p.name == "default").unwrap().is_default);
+}
+
+#[test]
+fn test_resolve_firefox_profile_by_name_and_dir() {
+    let root = fake_firefox_root();
+
+    let by_name = tablitz_recover::resolve_firefox_profile(root.path(), "Work").unwrap();
+    assert_eq!(by_name.name, "work");
+    let by_dir = tablitz_recover::resolve_firefox_profile(root.path(), "abcd1234.default").unwrap();
+    assert_eq!(by_dir.name, "default");
+    // "default" names a real profile here, so it is not the default-profile alias
+    let named_default = tablitz_recover::resolve_firefox_profile(root.path(), "Default").unwrap();
+    assert_eq!(named_default.name, "default");
+
+    let err = tablitz_recover::resolve_firefox_profile(root.path(), "missing").unwrap_err();
+    assert!(err.to_string().contains("work"), "error lists available profiles: {}", err);
+}
+
+#[test]
+fn test_firefox_profile_recovery_end_to_end() {
+    use tablitz_recover::{builtin_browser, recover, resolve_firefox_idb_path, resolve_firefox_profile, RecoverOptions};
+
+    let firefox = builtin_browser("firefox").unwrap();
+    let root = fake_firefox_root();
+    let profile = resolve_firefox_profile(root.path(), "work").unwrap();
+    let idb = resolve_firefox_idb_path(&profile.path, &firefox.extension_ids).unwrap();
+    assert!(idb.to_string_lossy().contains("0d6f3c5e-1b2a-4c3d-9e8f-7a6b5c4d3e2f"));
+
+    let session = recover(RecoverOptions {
+        browser: firefox.clone(),
+        profile: "work".to_string(),
+        dry_run: false,
+        db_path: Some(idb),
+        salvage: false,
+        version: None,
+    })
+    .unwrap();
+
+    assert!(matches!(
+        &session.source,
+        tablitz_core::SessionSource::Firefox { profile } if profile == "work"
+    ));
+    assert_eq!(session.groups.len(), 1);
+    assert_eq!(session.groups[0].tabs.len(), 2);
+
+    // Without OneTab installed the profile has nothing to resolve
+    let other = resolve_firefox_profile(root.path(), "default").unwrap();
+    std::fs::create_dir_all(&other.path).unwrap();
+    std::fs::write(other.path.join("prefs.js"), "").unwrap();
+    assert!(resolve_firefox_idb_path(&other.path, &firefox.extension_ids).is_err());
+}
+
+// ─── LevelDB salvage ───────────────────────────────────────────────────────
+
+/// A double-encoded OneTab state value holding one group with one tab.
+fn onetab_leveldb_value(group_id: &str, url: &str) -> String {
+    let inner = format!(
+        r#"{{"tabGroups":[{{"id":"{}","createDate":1760074389851,"tabsMeta":[{{"id":"{}-t0","url":"{}","title":"Tab in {}"}}]}}]}}"#,
+        group_id, group_id, url, group_id
+    );
+    serde_json::to_string(&inner).unwrap()
+}
+
+/// Writes two versions of OneTab's state: the first ends up in a table
+/// file, the second (which drops group "grp-old") in the write-ahead log.
+fn leveldb_with_two_versions() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let mut db = rusty_leveldb::DB::open(dir.path(), rusty_leveldb::Options::default()).unwrap();
+    db.put(b"state", onetab_leveldb_value("grp-old", "https://example.com/old").as_bytes())
+        .unwrap();
+    db.flush().unwrap();
+    db.compact_range(b"a", b"z").unwrap();
+    db.put(b"state", onetab_leveldb_value("grp-new", "https://example.com/new").as_bytes())
+        .unwrap();
+    db.flush().unwrap();
+    drop(db);
+    dir
+}
+
+fn leveldb_file(dir: &std::path::Path, extension: &str) -> std::path::PathBuf {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .find(|p| p.extension().is_some_and(|e| e == extension))
+        .unwrap_or_else(|| panic!("no .{} file", extension))
+}
+
+#[test]
+fn test_salvage_recovers_overwritten_versions() {
+    let dir = leveldb_with_two_versions();
+
+    // Opening the database only sees the latest version
+    let live = tablitz_recover::extract_from_leveldb(dir.path(), tablitz_core::SessionSource::Unknown)
+        .unwrap();
+    assert_eq!(live.groups.len(), 1);
+    assert_eq!(live.groups[0].id, "grp-new");
+
+    let report = tablitz_recover::salvage_leveldb(dir.path()).unwrap();
+    assert_eq!(report.values.len(), 2);
+    assert!(report.values[0].sequence < report.values[1].sequence);
+    assert_eq!(report.values[0].groups[0].id, "grp-old");
+    assert_eq!(report.values[1].groups[0].id, "grp-new");
+    assert!(report.values.iter().all(|v| v.key == "state"));
+    for file in &report.files {
+        assert_eq!(file.skipped, 0, "{:?}", file);
+        assert!(file.error.is_none(), "{:?}", file);
+        assert_eq!(file.tab_group_values, 1, "{:?}", file);
+    }
+
+    let mut ids: Vec<_> = report.merged_groups().into_iter().map(|g| g.id).collect();
+    ids.sort();
+    assert_eq!(ids, vec!["grp-new", "grp-old"]);
+}
+
+#[test]
+fn test_salvage_without_current_or_manifest() {
+    use tablitz_recover::{builtin_browser, recover, RecoverOptions};
+
+    let dir = leveldb_with_two_versions();
+    for entry in std::fs::read_dir(dir.path()).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        if name == "CURRENT" || name.starts_with("MANIFEST") {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    let session = recover(RecoverOptions {
+        browser: builtin_browser("chrome").unwrap(),
+        profile: "Default".to_string(),
+        dry_run: false,
+        db_path: Some(dir.path().to_path_buf()),
+        salvage: true,
+        version: None,
+    })
+    .unwrap();
+
+    assert!(matches!(&session.source, tablitz_core::SessionSource::Chrome { .. }));
+    assert_eq!(session.groups.len(), 2);
+}
+
+#[test]
+fn test_salvage_skips_corrupt_log_record() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut db = rusty_leveldb::DB::open(dir.path(), rusty_leveldb::Options::default()).unwrap();
+    db.put(b"state", onetab_leveldb_value("grp-good", "https://example.com/a").as_bytes())
+        .unwrap();
+    db.put(b"state", onetab_leveldb_value("grp-bad", "https://example.com/b").as_bytes())
+        .unwrap();
+    drop(db);
+
+    // Flip a byte inside the second record's payload
+    let log = leveldb_file(dir.path(), "log");
+    let mut bytes = std::fs::read(&log).unwrap();
+    let at = bytes.windows(7).position(|w| w == b"grp-bad").unwrap();
+    bytes[at] ^= 0xff;
+    std::fs::write(&log, &bytes).unwrap();
+
+    let report = tablitz_recover::salvage_leveldb(dir.path()).unwrap();
+    let log_report = report.files.iter().find(|f| f.path == log).unwrap();
+    assert_eq!(log_report.skipped, 1);
+    assert_eq!(log_report.tab_group_values, 1);
+    let ids: Vec<_> = report.merged_groups().into_iter().map(|g| g.id).collect();
+    assert_eq!(ids, vec!["grp-good"]);
+}
+
+#[test]
+fn test_salvage_truncated_log_keeps_complete_records() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut db = rusty_leveldb::DB::open(dir.path(), rusty_leveldb::Options::default()).unwrap();
+    db.put(b"state", onetab_leveldb_value("grp-first", "https://example.com/a").as_bytes())
+        .unwrap();
+    db.put(b"state", onetab_leveldb_value("grp-second", "https://example.com/b").as_bytes())
+        .unwrap();
+    drop(db);
+
+    let log = leveldb_file(dir.path(), "log");
+    let bytes = std::fs::read(&log).unwrap();
+    std::fs::write(&log, &bytes[..bytes.len() - 10]).unwrap();
+
+    let report = tablitz_recover::salvage_leveldb(dir.path()).unwrap();
+    let ids: Vec<_> = report.merged_groups().into_iter().map(|g| g.id).collect();
+    assert_eq!(ids, vec!["grp-first"]);
+    assert_eq!(report.files.iter().map(|f| f.skipped).sum::<usize>(), 1);
+}
+
+#[test]
+fn test_salvage_table_without_footer() {
+    let dir = leveldb_with_two_versions();
+    let table = leveldb_file(dir.path(), "ldb");
+    let bytes = std::fs::read(&table).unwrap();
+    std::fs::write(&table, &bytes[..bytes.len() - 48]).unwrap();
+
+    let report = tablitz_recover::salvage_leveldb(dir.path()).unwrap();
+    let table_report = report.files.iter().find(|f| f.path == table).unwrap();
+    assert!(table_report.error.is_none(), "{:?}", table_report);
+    assert_eq!(table_report.tab_group_values, 1);
+    assert_eq!(report.merged_groups().len(), 2);
+}
+
+#[test]
+fn test_salvage_corrupt_table_block_is_skipped() {
+    let dir = leveldb_with_two_versions();
+    let table = leveldb_file(dir.path(), "ldb");
+    let mut bytes = std::fs::read(&table).unwrap();
+    bytes[10] ^= 0xff;
+    std::fs::write(&table, &bytes).unwrap();
+
+    let report = tablitz_recover::salvage_leveldb(dir.path()).unwrap();
+    let table_report = report.files.iter().find(|f| f.path == table).unwrap();
+    assert_eq!(table_report.skipped, 1);
+    assert_eq!(table_report.tab_group_values, 0);
+    // The log's version still comes through
+    let ids: Vec<_> = report.merged_groups().into_iter().map(|g| g.id).collect();
+    assert_eq!(ids, vec!["grp-new"]);
+}
+
+#[test]
+fn test_salvage_empty_directory_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    assert!(tablitz_recover::salvage_leveldb(dir.path()).is_err());
+}
+
+// ─── Historical versions ───────────────────────────────────────────────────
+
+fn leveldb_options(dir: &std::path::Path) -> tablitz_recover::RecoverOptions {
+    tablitz_recover::RecoverOptions {
+        db_path: Some(dir.to_path_buf()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_list_versions_oldest_first_with_counts() {
+    let dir = leveldb_with_two_versions();
+    let versions = tablitz_recover::list_versions(&leveldb_options(dir.path())).unwrap();
+
+    assert_eq!(versions.len(), 2);
+    assert!(versions[0].sequence < versions[1].sequence);
+    assert_eq!(versions[0].groups[0].id, "grp-old");
+    assert_eq!((versions[1].groups.len(), versions[1].tab_count()), (1, 1));
+}
+
+#[test]
+fn test_recover_picks_one_version() {
+    let dir = leveldb_with_two_versions();
+    let versions = tablitz_recover::list_versions(&leveldb_options(dir.path())).unwrap();
+
+    let session = tablitz_recover::recover(tablitz_recover::RecoverOptions {
+        version: Some(versions[0].sequence),
+        ..leveldb_options(dir.path())
+    })
+    .unwrap();
+    assert_eq!(session.groups.len(), 1);
+    assert_eq!(session.groups[0].id, "grp-old");
+    assert_eq!(session.groups[0].tabs[0].url.as_str(), "https://example.com/old");
+
+    let missing = tablitz_recover::recover(tablitz_recover::RecoverOptions {
+        version: Some(999_999),
+        ..leveldb_options(dir.path())
+    })
+    .unwrap_err();
+    assert!(
+        missing.to_string().contains(&versions[1].sequence.to_string()),
+        "error lists available versions: {}",
+        missing
+    );
+}
+
+#[test]
+fn test_versions_not_available_for_firefox() {
+    let dir = tempfile::tempdir().unwrap();
+    let opts = tablitz_recover::RecoverOptions {
+        browser: tablitz_recover::builtin_browser("firefox").unwrap(),
+        ..leveldb_options(dir.path())
+    };
+    assert!(tablitz_recover::list_versions(&opts).is_err());
+}
+
+// ─── Profile discovery ─────────────────────────────────────────────────────
+
+const LOCAL_STATE: &str = r#"{
+  "browser": {"enabled_labs_experiments": []},
+  "profile": {
+    "last_used": "Profile 2",
+    "info_cache": {
+      "Profile 10": {"name": "Spare"},
+      "Profile 2": {"name": "Work", "is_using_default_name": false},
+      "Default": {"name": "Person 1"}
+    }
+  }
+}"#;
+
+#[test]
+fn test_list_chromium_profiles_from_local_state() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("Local State"), LOCAL_STATE).unwrap();
+
+    let profiles = tablitz_recover::list_chromium_profiles(dir.path());
+    assert_eq!(
+        profiles,
+        vec![
+            ("Default".to_string(), Some("Person 1".to_string())),
+            ("Profile 2".to_string(), Some("Work".to_string())),
+            ("Profile 10".to_string(), Some("Spare".to_string())),
+        ]
+    );
+}
+
+#[test]
+fn test_list_chromium_profiles_without_local_state() {
+    let dir = tempfile::tempdir().unwrap();
+    for sub in ["Default", "Profile 1", "System Profile", "Crashpad"] {
+        std::fs::create_dir(dir.path().join(sub)).unwrap();
+    }
+
+    let profiles = tablitz_recover::list_chromium_profiles(dir.path());
+    let names: Vec<_> = profiles.iter().map(|(dir, _)| dir.as_str()).collect();
+    assert_eq!(names, vec!["Default", "Profile 1"]);
+    assert!(profiles.iter().all(|(_, display)| display.is_none()));
+}
+
+#[test]
+fn test_discover_chromium_stores_with_display_names() {
+    use tablitz_recover::{builtin_browser, discover_onetab_stores_in};
+
+    let vivaldi = builtin_browser("vivaldi").unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("Local State"), LOCAL_STATE).unwrap();
+    // OneTab is only installed in "Profile 2"
+    let leveldb = dir
+        .path()
+        .join("Profile 2/Local Extension Settings")
+        .join(&vivaldi.extension_ids[0]);
+    std::fs::create_dir_all(dir.path().join("Default")).unwrap();
+    let mut db = rusty_leveldb::DB::open(&leveldb, rusty_leveldb::Options::default()).unwrap();
+    db.put(b"state", onetab_leveldb_value("grp-work", "https://example.com/work").as_bytes())
+        .unwrap();
+    db.flush().unwrap();
+    drop(db);
+
+    let stores = discover_onetab_stores_in(&vivaldi, dir.path());
+    assert_eq!(stores.len(), 1);
+    assert_eq!(stores[0].profile, "Profile 2");
+    assert_eq!(stores[0].display_name.as_deref(), Some("Work"));
+    assert_eq!(stores[0].path, leveldb);
+
+    let session = tablitz_recover::recover(stores[0].recover_options()).unwrap();
+    assert!(matches!(
+        &session.source,
+        tablitz_core::SessionSource::Vivaldi { profile } if profile == "Profile 2"
+    ));
+    assert_eq!(session.groups[0].id, "grp-work");
+}
+
+#[test]
+fn test_discover_opera_profile_in_user_data_dir() {
+    use tablitz_recover::{builtin_browser, discover_onetab_stores_in};
+
+    let opera = builtin_browser("opera").unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let leveldb = dir
+        .path()
+        .join("Local Extension Settings")
+        .join(&opera.extension_ids[0]);
+    std::fs::create_dir_all(&leveldb).unwrap();
+
+    let stores = discover_onetab_stores_in(&opera, dir.path());
+    assert_eq!(stores.len(), 1);
+    assert_eq!(stores[0].profile, "Default");
+    assert_eq!(stores[0].path, leveldb);
+}
+
+#[test]
+fn test_discover_firefox_stores() {
+    let root = fake_firefox_root();
+    let firefox = tablitz_recover::builtin_browser("firefox").unwrap();
+    let stores = tablitz_recover::discover_onetab_stores_in(&firefox, root.path());
+
+    assert_eq!(stores.len(), 1, "only the work profile has OneTab");
+    assert_eq!(stores[0].profile, "work");
+    assert!(stores[0].path.ends_with("idb"));
+}
+
+// ─── Browser registry ──────────────────────────────────────────────────────
+
+#[test]
+fn test_config_browser_with_forked_extension_id() {
+    use tablitz_recover::{discover_onetab_stores_in, BrowserRegistry};
+
+    let dir = tempfile::tempdir().unwrap();
+    let data_dir = dir.path().join("thorium");
+    let config = dir.path().join("config.toml");
+    std::fs::write(
+        &config,
+        format!(
+            "[[browser]]\nid = \"thorium\"\nname = \"Thorium\"\n\
+             extension_ids = [\"onetabforkonetabforkonetabforkab\"]\n\
+             windows = [\"{0}\"]\nmacos = [\"{0}\"]\nlinux = [\"{0}\"]\n",
+            data_dir.display()
+        ),
+    )
+    .unwrap();
+    let mut registry = BrowserRegistry::builtin();
+    registry.add_config_file(&config).unwrap();
+    let thorium = registry.browser("thorium").unwrap();
+    assert_eq!(thorium.data_dirs(), vec![data_dir.clone()]);
+
+    let leveldb = data_dir.join("Profile 1/Local Extension Settings/onetabforkonetabforkonetabforkab");
+    let mut db = rusty_leveldb::DB::open(&leveldb, rusty_leveldb::Options::default()).unwrap();
+    db.put(b"state", onetab_leveldb_value("grp-fork", "https://example.com/fork").as_bytes())
+        .unwrap();
+    db.flush().unwrap();
+    drop(db);
+
+    let stores = discover_onetab_stores_in(&thorium, &data_dir);
+    assert_eq!(stores.len(), 1);
+    assert_eq!(stores[0].path, leveldb);
+    assert_eq!(
+        tablitz_recover::resolve_leveldb_path(&thorium, "Profile 1").unwrap(),
+        leveldb
+    );
+
+    let session = tablitz_recover::recover(stores[0].recover_options()).unwrap();
+    assert!(matches!(
+        &session.source,
+        tablitz_core::SessionSource::Browser { browser, profile }
+            if browser == "thorium" && profile == "Profile 1"
+    ));
+    assert_eq!(session.groups[0].id, "grp-fork");
+}
+
+#[test]
+fn test_firefox_fork_probes_configured_addon_ids() {
+    use tablitz_recover::{resolve_firefox_idb_path, resolve_firefox_profile, BrowserRegistry};
+
+    let root = fake_firefox_root();
+    let mut registry = BrowserRegistry::builtin();
+    registry
+        .add_toml("[[browser]]\nid = \"firefox\"\nextension_ids = [\"other@example.com\"]\n")
+        .unwrap();
+    let firefox = registry.browser("firefox").unwrap();
+    assert_eq!(firefox.extension_ids, ["other@example.com", "extension@one-tab.com"]);
+
+    // The first configured add-on installed in the profile wins
+    let profile = resolve_firefox_profile(root.path(), "work").unwrap();
+    let idb = resolve_firefox_idb_path(&profile.path, &firefox.extension_ids).unwrap();
+    assert!(idb.to_string_lossy().contains("11111111-2222-3333-4444-555555555555"));
+
+    let missing = resolve_firefox_idb_path(&profile.path, &["nope@example.com".to_string()]).unwrap_err();
+    assert!(missing.to_string().contains("nope@example.com"));
+}
+
+// ─── Writing back to OneTab ────────────────────────────────────────────────
+
+/// A session holding one group with one tab.
+fn session_with_group(group_id: &str, url: &str) -> tablitz_core::TabSession {
+    let created_at = chrono::DateTime::from_timestamp_millis(1_760_000_000_000).unwrap();
+    tablitz_core::TabSession {
+        version: 1,
+        source: tablitz_core::SessionSource::Unknown,
+        groups: vec![tablitz_core::TabGroup {
+            id: group_id.to_string(),
+            label: Some("Restored".to_string()),
+            created_at,
+            tabs: vec![tablitz_core::Tab {
+                id: format!("{}-t0", group_id),
+                url: url::Url::parse(url).unwrap(),
+                title: "Restored tab".to_string(),
+                favicon_url: None,
+                added_at: created_at,
+                tags: Vec::new(),
+                activity: Default::default(),
+            }],
+            pinned: false,
+            locked: true,
+            starred: false,
+            tags: Vec::new(),
+        }],
+        created_at,
+        imported_at: created_at,
+    }
+}
+
+/// A OneTab LevelDB store in `<tempdir>/<extension id>` holding `state` and
+/// an unrelated `settings` key.
+fn onetab_store() -> (tempfile::TempDir, std::path::PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("chphlpgkkbolifaimnlloiipkdnihall");
+    let mut db = rusty_leveldb::DB::open(&path, rusty_leveldb::Options::default()).unwrap();
+    db.put(b"settings", br#"{"theme":"dark"}"#).unwrap();
+    db.put(b"state", onetab_leveldb_value("grp-old", "https://example.com/old").as_bytes())
+        .unwrap();
+    db.flush().unwrap();
+    drop(db);
+    (dir, path)
+}
+
+#[test]
+fn test_write_to_leveldb_replaces_state_and_keeps_backup() {
+    let (_dir, path) = onetab_store();
+    let session = session_with_group("grp-new", "https://example.com/new");
+
+    let report = tablitz_recover::write_to_leveldb(&path, &session).unwrap();
+    assert_eq!(report.key, "state");
+    assert_eq!(report.groups_replaced, 1);
+    assert_eq!((report.groups_written, report.tabs_written), (1, 1));
+    assert!(report.backup.file_name().unwrap().to_string_lossy().starts_with(
+        "chphlpgkkbolifaimnlloiipkdnihall.tablitz-backup-"
+    ));
+    assert!(!path.with_file_name("chphlpgkkbolifaimnlloiipkdnihall.tablitz-new").exists());
+
+    let written = tablitz_recover::recover(leveldb_options(&path)).unwrap();
+    assert_eq!(written.groups.len(), 1);
+    assert_eq!(written.groups[0].id, "grp-new");
+    assert_eq!(written.groups[0].label.as_deref(), Some("Restored"));
+    assert!(written.groups[0].locked);
+    assert_eq!(written.groups[0].created_at, session.groups[0].created_at);
+
+    let mut db = rusty_leveldb::DB::open(&path, rusty_leveldb::Options::default()).unwrap();
+    assert_eq!(db.get(b"settings").unwrap(), br#"{"theme":"dark"}"#);
+    drop(db);
+
+    let original = tablitz_recover::recover(leveldb_options(&report.backup)).unwrap();
+    assert_eq!(original.groups[0].id, "grp-old");
+}
+
+#[test]
+fn test_write_to_leveldb_creates_state_in_empty_store() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("store");
+    drop(rusty_leveldb::DB::open(&path, rusty_leveldb::Options::default()).unwrap());
+
+    let report =
+        tablitz_recover::write_to_leveldb(&path, &session_with_group("grp-new", "https://example.com/")).unwrap();
+    assert_eq!(report.key, "state");
+    assert_eq!(report.groups_replaced, 0);
+    let written = tablitz_recover::recover(leveldb_options(&path)).unwrap();
+    assert_eq!(written.groups[0].id, "grp-new");
+}
+
+#[test]
+fn test_write_to_leveldb_refuses_locked_store() {
+    let (dir, path) = onetab_store();
+    assert!(!tablitz_recover::leveldb_is_locked(&path).unwrap());
+
+    let held = rusty_leveldb::DB::open(&path, rusty_leveldb::Options::default()).unwrap();
+    assert!(tablitz_recover::leveldb_is_locked(&path).unwrap());
+    let err = tablitz_recover::write_to_leveldb(&path, &session_with_group("grp-new", "https://example.com/"))
+        .unwrap_err();
+    assert!(err.to_string().contains("locked"), "{}", err);
+    drop(held);
+
+    // Nothing was moved or copied
+    let entries: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+    assert_eq!(entries.len(), 1);
+}
+
+#[test]
+fn test_write_to_leveldb_rejects_non_leveldb_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let session = session_with_group("grp-new", "https://example.com/");
+    assert!(tablitz_recover::write_to_leveldb(dir.path(), &session).is_err());
+}
+
+// ─── Bookmarks HTML ────────────────────────────────────────────────────────
+
+const CHROME_BOOKMARKS: &str = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<!-- This is an automatically generated file.
+     It will be read and overwritten.
+     DO NOT EDIT! -->
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><H3 ADD_DATE="1700000000" LAST_MODIFIED="1700000500" PERSONAL_TOOLBAR_FOLDER="true">Bookmarks bar</H3>
+    <DL><p>
+        <DT><A HREF="https://www.rust-lang.org/" ADD_DATE="1700000100" ICON="data:image/png;base64,AAAA">Rust &amp; friends</A>
+        <DT><H3 ADD_DATE="1700000200">Reading</H3>
+        <DL><p>
+            <DT><A HREF="https://arxiv.org/abs/1905.11946" ADD_DATE="1700000300" TAGS="ml,Papers To Read">EfficientNet</A>
+            <DD>A description that is not a title
+            <DT><A HREF="javascript:alert(1)">Bookmarklet</A>
+            <DT><A HREF="place:sort=8&maxResults=10">Recent</A>
+        </DL><p>
+    </DL><p>
+    <DT><A HREF="https://example.com/loose" ADD_DATE="1700000400">Loose bookmark</A>
+</DL><p>
+"#;
+
+fn write_bookmarks(content: &str) -> tablitz_core::TabSession {
+    let f = write_tmp(content);
+    tablitz_recover::parse_bookmarks_html(f.path()).unwrap()
+}
+
+#[test]
+fn test_parse_bookmarks_html_folders_dates_and_tags() {
+    let session = write_bookmarks(CHROME_BOOKMARKS);
+    assert!(matches!(session.source, tablitz_core::SessionSource::BookmarksHtml { .. }));
+
+    let labels: Vec<Option<&str>> = session.groups.iter().map(|g| g.label.as_deref()).collect();
+    assert_eq!(labels, vec![None, Some("Bookmarks bar"), Some("Bookmarks bar / Reading")]);
+
+    let root = &session.groups[0];
+    assert_eq!(root.tabs.len(), 1);
+    assert_eq!(root.tabs[0].title, "Loose bookmark");
+    assert_eq!(root.created_at.timestamp(), 1_700_000_400, "falls back to the oldest bookmark");
+
+    let bar = &session.groups[1];
+    assert_eq!(bar.created_at.timestamp(), 1_700_000_000);
+    assert_eq!(bar.tabs[0].title, "Rust & friends");
+    assert_eq!(bar.tabs[0].added_at.timestamp(), 1_700_000_100);
+    assert_eq!(bar.tabs[0].favicon_url.as_deref(), Some("data:image/png;base64,AAAA"));
+
+    let reading = &session.groups[2];
+    assert_eq!(reading.tabs.len(), 1, "javascript: and place: bookmarks are skipped");
+    assert_eq!(reading.tabs[0].title, "EfficientNet");
+    assert_eq!(reading.tabs[0].tags, vec!["ml", "papers-to-read"]);
+    assert_eq!(reading.created_at.timestamp(), 1_700_000_200);
+
+    // IDs are stable across repeated imports of the same file
+    assert_eq!(write_bookmarks(CHROME_BOOKMARKS).groups[2].tabs[0].id, reading.tabs[0].id);
+}
+
+#[test]
+fn test_parse_pocket_style_bookmarks() {
+    let session = write_bookmarks(concat!(
+        "<!DOCTYPE html><html><head><title>Pocket Export</title></head><body>\n",
+        "<h1>Unread</h1>\n<ul>\n",
+        "<li><a href=\"https://example.com/a\" time_added=\"1600000000\" tags=\"rust,async\">A</a></li>\n",
+        "<li><a href=\"https://example.com/b\" time_added=\"1600000100\" tags=\"\">https://example.com/b</a></li>\n",
+        "</ul>\n<h1>Read Archive</h1>\n<ul>\n",
+        "<li><a href=\"https://example.com/c\" time_added=\"1500000000\">C</a></li>\n",
+        "</ul></body></html>\n",
+    ));
+    assert_eq!(session.groups.len(), 2, "each list is a group");
+    assert_eq!(session.groups[0].tabs.len(), 2);
+    assert_eq!(session.groups[0].tabs[0].tags, vec!["rust", "async"]);
+    assert!(session.groups[0].tabs[1].tags.is_empty());
+    assert_eq!(session.groups[1].tabs[0].added_at.timestamp(), 1_500_000_000);
+}
+
+#[test]
+fn test_bookmarks_html_export_round_trip() {
+    use tablitz_recover::format_bookmarks_html;
+
+    let mut plain = pipe_group("plain", &[("https://example.com/x?a=1&b=2", "Quotes \" <and> & more")]);
+    plain.created_at = chrono::DateTime::from_timestamp(1_650_000_000, 0).unwrap();
+    plain.tabs[0].added_at = chrono::DateTime::from_timestamp(1_650_000_100, 0).unwrap();
+    plain.tabs[0].tags = vec!["rust".to_string()];
+    plain.tags = vec!["work".to_string(), "rust".to_string()];
+    let mut starred = pipe_group("starred", &[("https://example.com/star", "Starred tab")]);
+    starred.starred = true;
+    starred.label = None;
+    starred.created_at = chrono::DateTime::from_timestamp(1_600_000_000, 0).unwrap();
+
+    let html = format_bookmarks_html(&[plain.clone(), pipe_group("empty", &[]), starred]);
+    assert!(html.starts_with("<!DOCTYPE NETSCAPE-Bookmark-file-1>"));
+    assert!(html.contains(r#"TAGS="rust,work""#), "{}", html);
+    assert!(html.find("2020-09-13").unwrap() < html.find("label plain").unwrap(), "starred first");
+
+    let session = write_bookmarks(&html);
+    assert_eq!(session.groups.len(), 2);
+    assert_eq!(session.groups[0].label.as_deref(), Some("2020-09-13 12:26"));
+    let back = &session.groups[1];
+    assert_eq!(back.label.as_deref(), Some("label plain"));
+    assert_eq!(back.created_at, plain.created_at);
+    assert_eq!(back.tabs[0].url, plain.tabs[0].url);
+    assert_eq!(back.tabs[0].title, plain.tabs[0].title);
+    assert_eq!(back.tabs[0].added_at, plain.tabs[0].added_at);
+    assert_eq!(back.tabs[0].tags, vec!["rust", "work"]);
+}
+
+// ─── Browser session files ─────────────────────────────────────────────────
+//
+// The fixtures are small hand-built session files: two Chromium SNSS logs
+// (`Session_*` with open, closed and re-navigated tabs, and `Tabs_*` with
+// recently closed tabs) and a mozLz4-compressed Firefox `sessionstore`.
+
+fn fixture(name: &str) -> std::path::PathBuf {
+    std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+fn session_tabs(group: &tablitz_core::TabGroup) -> Vec<(&str, &str, i64)> {
+    group.tabs.iter().map(|t| (t.url.as_str(), t.title.as_str(), t.added_at.timestamp())).collect()
+}
+
+#[test]
+fn test_parse_chromium_session_windows() {
+    let source = tablitz_core::SessionSource::ChromiumSession { profile: "Default".to_string() };
+    let session = tablitz_recover::parse_chromium_session(&fixture("Session_13346473600000000"), source.clone()).unwrap();
+    assert_eq!(session.source, source);
+    assert_eq!(session.groups.len(), 2, "the closed window is dropped");
+
+    // The first tab went back to its first page; the second has a last-active time
+    assert_eq!(
+        session_tabs(&session.groups[0]),
+        vec![
+            ("https://www.rust-lang.org/", "Rust Programming Language", 1_700_000_000),
+            ("https://github.com/", "GitHub", 1_700_000_900),
+        ]
+    );
+    assert_eq!(session.groups[0].created_at.timestamp(), 1_700_000_000);
+    assert_eq!(
+        session_tabs(&session.groups[1]),
+        vec![("https://fr.wikipedia.org/wiki/Caf%C3%A9", "Café — Wikipédia", 1_700_000_500)]
+    );
+
+    // Re-reading the same windows gives the same IDs
+    let again = tablitz_recover::parse_chromium_session(&fixture("Session_13346473600000000"), source).unwrap();
+    assert_eq!(again.groups[0].id, session.groups[0].id);
+    assert_ne!(session.groups[0].id, session.groups[1].id);
+}
+
+#[test]
+fn test_parse_chromium_recently_closed_tabs() {
+    let source = tablitz_core::SessionSource::ChromiumSession { profile: "Default".to_string() };
+    let session = tablitz_recover::parse_chromium_session(&fixture("Tabs_13346473600000000"), source).unwrap();
+    assert_eq!(session.groups.len(), 1);
+    assert_eq!(session.groups[0].label.as_deref(), Some("Recently closed"));
+    assert_eq!(
+        session_tabs(&session.groups[0]),
+        vec![
+            ("https://news.ycombinator.com/item?id=1", "Show HN", 1_690_000_000),
+            ("https://docs.rs/", "Docs.rs", 1_690_000_200),
+        ],
+        "the restored tab is dropped"
+    );
+}
+
+#[test]
+fn test_parse_chromium_session_from_profile_dir() {
+    let profile = tempfile::tempdir().unwrap();
+    let sessions = profile.path().join("Sessions");
+    std::fs::create_dir(&sessions).unwrap();
+    std::fs::copy(fixture("Tabs_13346473600000000"), sessions.join("Tabs_13346473600000000")).unwrap();
+    std::fs::copy(fixture("Session_13346473600000000"), sessions.join("Session_13346473600000000")).unwrap();
+
+    let session = tablitz_recover::parse_chromium_session(
+        profile.path(),
+        tablitz_core::SessionSource::ChromiumSession { profile: "Default".to_string() },
+    )
+    .unwrap();
+    assert_eq!(session.groups.len(), 2, "reads the Session_ file, not Tabs_");
+
+    let empty = tempfile::tempdir().unwrap();
+    let err = tablitz_recover::parse_chromium_session(empty.path(), tablitz_core::SessionSource::Unknown).unwrap_err();
+    assert!(err.to_string().contains("No Chromium session files"), "{}", err);
+}
+
+#[test]
+fn test_parse_firefox_session() {
+    let source = tablitz_core::SessionSource::FirefoxSession { profile: "default-release".to_string() };
+    let session = tablitz_recover::parse_firefox_session(&fixture("sessionstore.jsonlz4"), source).unwrap();
+    assert_eq!(session.groups.len(), 2, "closed windows are not included");
+    assert_eq!(session.created_at.timestamp(), 1_700_005_000);
+
+    assert_eq!(
+        session_tabs(&session.groups[0]),
+        vec![
+            ("https://developer.mozilla.org/en-US/docs/Web/JavaScript", "JavaScript | MDN", 1_700_001_000),
+            ("https://example.com/first", "First", 1_700_002_000),
+            ("about:blank", "about:blank", 1_700_005_000),
+        ]
+    );
+    assert_eq!(session_tabs(&session.groups[1]), vec![("https://www.rust-lang.org/", "Rust", 1_700_004_000)]);
+
+    // A profile directory resolves to its session file
+    let profile = tempfile::tempdir().unwrap();
+    std::fs::create_dir(profile.path().join("sessionstore-backups")).unwrap();
+    std::fs::copy(
+        fixture("sessionstore.jsonlz4"),
+        profile.path().join("sessionstore-backups/recovery.jsonlz4"),
+    )
+    .unwrap();
+    let from_dir = tablitz_recover::parse_firefox_session(profile.path(), tablitz_core::SessionSource::Unknown).unwrap();
+    assert_eq!(from_dir.groups, session.groups);
+}
+
+// ─── History and bookmarks ─────────────────────────────────────────────────
+
+/// µs since 1601, as Chromium stores times.
+fn chromium_us(rfc3339: &str) -> i64 {
+    let at: chrono::DateTime<chrono::Utc> = rfc3339.parse().unwrap();
+    at.timestamp_micros() + 11_644_473_600_000_000
+}
+
+fn fake_chromium_profile(dir: &std::path::Path) {
+    std::fs::create_dir_all(dir).unwrap();
+    let conn = rusqlite::Connection::open(dir.join("History")).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE urls (id INTEGER PRIMARY KEY, url LONGVARCHAR, title LONGVARCHAR,
+            visit_count INTEGER DEFAULT 0 NOT NULL, typed_count INTEGER DEFAULT 0 NOT NULL,
+            last_visit_time INTEGER NOT NULL, hidden INTEGER DEFAULT 0 NOT NULL);",
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO urls (url, title, visit_count, last_visit_time) VALUES (?1, 'Rust', 9, ?2), (?3, 'Never', 0, 0)",
+        rusqlite::params!["https://www.rust-lang.org/", chromium_us("2024-03-01T10:00:00Z"), "https://example.com/typed"],
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("Bookmarks"),
+        r#"{"checksum": "0", "version": 1, "roots": {
+            "bookmark_bar": {"type": "folder", "name": "Bookmarks bar", "children": [
+                {"type": "url", "name": "Docs", "url": "https://docs.rs/"},
+                {"type": "folder", "name": "Nested", "children": [
+                    {"type": "url", "name": "Rust", "url": "https://www.rust-lang.org/"}
+                ]}
+            ]},
+            "other": {"type": "folder", "children": []},
+            "synced": {"type": "folder", "children": []}
+        }}"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_read_chromium_history_and_bookmarks() {
+    use tablitz_recover::{read_chromium_bookmarks, read_chromium_history};
+
+    let dir = tempfile::tempdir().unwrap();
+    fake_chromium_profile(dir.path());
+
+    let history = read_chromium_history(&dir.path().join("History")).unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].url, "https://www.rust-lang.org/");
+    assert_eq!(history[0].activity.visit_count, 9);
+    assert_eq!(history[0].activity.last_visited_at, Some("2024-03-01T10:00:00Z".parse().unwrap()));
+    assert_eq!(history[1].activity.last_visited_at, None, "zero is Chromium's null time");
+
+    let bookmarks = read_chromium_bookmarks(&dir.path().join("Bookmarks")).unwrap();
+    let urls: Vec<&str> = bookmarks.iter().map(|b| b.url.as_str()).collect();
+    assert_eq!(urls, vec!["https://docs.rs/", "https://www.rust-lang.org/"]);
+    assert!(bookmarks.iter().all(|b| b.activity.bookmarked && b.activity.visit_count == 0));
+
+    assert!(read_chromium_history(&dir.path().join("Bookmarks")).is_err());
+}
+
+#[test]
+fn test_read_firefox_places() {
+    let dir = tempfile::tempdir().unwrap();
+    let places = dir.path().join("places.sqlite");
+    let conn = rusqlite::Connection::open(&places).unwrap();
+    conn.execute_batch(
+        "PRAGMA journal_mode = WAL;
+         CREATE TABLE moz_places (id INTEGER PRIMARY KEY, url LONGVARCHAR, title LONGVARCHAR,
+            visit_count INTEGER DEFAULT 0, last_visit_date INTEGER);
+         CREATE TABLE moz_bookmarks (id INTEGER PRIMARY KEY, type INTEGER, fk INTEGER DEFAULT NULL,
+            parent INTEGER, title LONGVARCHAR);
+         INSERT INTO moz_places VALUES (1, 'https://www.mozilla.org/', 'Mozilla', 4, 1709287200000000);
+         INSERT INTO moz_places VALUES (2, 'https://example.com/bookmarked', 'Saved', 0, NULL);
+         INSERT INTO moz_places VALUES (3, 'https://example.com/prefetched', NULL, 0, NULL);
+         INSERT INTO moz_bookmarks VALUES (1, 2, NULL, 0, 'toolbar');
+         INSERT INTO moz_bookmarks VALUES (2, 1, 2, 1, 'Saved');",
+    )
+    .unwrap();
+
+    // Read while the connection (and its WAL) is still open, as with a running Firefox
+    let activity = tablitz_recover::read_firefox_places(&places).unwrap();
+    assert_eq!(activity.len(), 2, "places with no visits or bookmarks are skipped");
+    assert_eq!(activity[0].activity.visit_count, 4);
+    assert_eq!(activity[0].activity.last_visited_at, Some("2024-03-01T10:00:00Z".parse().unwrap()));
+    assert!(!activity[0].activity.bookmarked);
+    assert_eq!(activity[1].url, "https://example.com/bookmarked");
+    assert!(activity[1].activity.bookmarked);
+    drop(conn);
+}
+
+#[test]
+fn test_read_profile_activity_from_registry_browser() {
+    use tablitz_recover::{read_profile_activity, resolve_profile_dir, BrowserRegistry};
+
+    let dir = tempfile::tempdir().unwrap();
+    let data_dir = dir.path().join("thorium");
+    fake_chromium_profile(&data_dir.join("Profile 1"));
+    let mut registry = BrowserRegistry::builtin();
+    registry
+        .add_toml(&format!(
+            "[[browser]]\nid = \"thorium\"\nname = \"Thorium\"\n\
+             extension_ids = [\"chphlpgkkbolifaimnlloiipkdnihall\"]\n\
+             windows = [\"{0}\"]\nmacos = [\"{0}\"]\nlinux = [\"{0}\"]\n",
+            data_dir.display()
+        ))
+        .unwrap();
+    let thorium = registry.browser("thorium").unwrap();
+
+    let profile_dir = resolve_profile_dir(&thorium, "Profile 1").unwrap();
+    assert_eq!(profile_dir, data_dir.join("Profile 1"));
+    assert!(resolve_profile_dir(&thorium, "Profile 2").is_err());
+    assert_eq!(read_profile_activity(&thorium, &profile_dir).unwrap().len(), 4);
+    assert!(read_profile_activity(&thorium, dir.path()).is_err());
+}
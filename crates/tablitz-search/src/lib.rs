@@ -2,17 +2,17 @@
 //!
 //! This crate provides:
 //! - Fuzzy matching on titles and URLs using nucleo
-//! - Title normalization with Unicode NFC
-//! - URL deduplication with multiple strategies
+//! - Title normalization with Unicode NFC and configurable cleanup rules
+//! - URL deduplication with multiple strategies, and near-duplicate clusters
+//! - A structured query language compiled to SQL or evaluated in memory
 //! - Full-text search index (optional feature)
 //! - Semantic search with embeddings (optional feature)
 //! - Auto-categorization for tab groups (optional feature)
 
-use tablitz_core::{Tab, TabGroup, TabSession};
-use std::collections::HashSet;
+use tablitz_core::{CanonicalRules, Tab, TabGroup, TabSession};
+use std::collections::HashMap;
 use nucleo::{Matcher, Config};
 use nucleo::pattern::{Pattern, CaseMatching, Normalization};
-use url::Url;
 
 /// A search result with rank information.
 #[derive(Debug, Clone)]
@@ -152,72 +152,259 @@ impl FuzzySearcher {
     }
 }
 
-/// Normalizes tab titles for improved matching.
-pub struct TitleNormalizer;
-
-impl TitleNormalizer {
-    /// Common site suffixes to strip from titles.
-    const SUFFIXES: &[&str] = &[
-        " - Google Search",
-        " | Twitter",
-        " on X",
-        " - YouTube",
-        " on YouTube",
-        " - Wikipedia",
-        " - Reddit",
-        " | LinkedIn",
-        " - Stack Overflow",
-        " | GitHub",
-        " | daily.dev",
-        " | DEV Community",
-        " | Hacker News",
-        " | Medium",
-        " – Frontend Masters Blog",
-        " | InfoWorld",
-        " | Product Hunt",
+pub use titles::{TitleNormalizer, TitlePipeline, TitleRule, TitleRules};
+
+/// Title normalization: a pipeline of configurable cleanup rules, and
+/// [`TitleNormalizer`] for the default ones.
+mod titles {
+    use super::*;
+    use anyhow::Context;
+    use regex::Regex;
+    use serde::{Deserialize, Serialize};
+    use std::sync::OnceLock;
+    use url::Url;
+
+    /// Site suffixes stripped when [`TitleRules::builtin_rules`] is set, as
+    /// `(domain, regex)`. Phrasing that also occurs in ordinary titles, like
+    /// " on X", only applies on its own site.
+    const BUILTIN_SUFFIXES: &[(Option<&str>, &str)] = &[
+        (None, r" - Google Search"),
+        (None, r" \| Twitter"),
+        (Some("x.com"), r" on X(?:: .*)?"),
+        (Some("twitter.com"), r" on X(?:: .*)?"),
+        (None, r" - YouTube"),
+        (Some("youtube.com"), r" on YouTube"),
+        (None, r" - Wikipedia"),
+        (None, r" - Reddit"),
+        (None, r" \| LinkedIn"),
+        (None, r" - Stack Overflow"),
+        (None, r" \| GitHub"),
+        (None, r" \| daily\.dev"),
+        (None, r" \| DEV Community"),
+        (None, r" \| Hacker News"),
+        (None, r" \| Medium"),
+        (None, r" – Frontend Masters Blog"),
+        (None, r" \| InfoWorld"),
+        (None, r" \| Product Hunt"),
     ];
 
-    /// Normalize a single title.
+    /// How titles are cleaned up, loaded from the `[titles]` table of
+    /// tablitz's config file; fields left out keep their defaults.
     ///
-    /// Applies Unicode NFC normalization, trims whitespace,
-    /// collapses multiple spaces, and strips common site suffixes.
-    pub fn normalize(title: &str) -> String {
-        use unicode_normalization::UnicodeNormalization;
+    /// Titles are always NFC-normalized and have their whitespace collapsed.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    pub struct TitleRules {
+        /// Drop notification counters like "(3) " or "(20+) " from the start.
+        pub strip_counters: bool,
+        /// Drop emoji.
+        pub strip_emoji: bool,
+        /// Lowercase the result.
+        pub fold_case: bool,
+        /// Strip common site suffixes (" - YouTube", " | GitHub", ...) too.
+        pub builtin_rules: bool,
+        /// Prefixes and suffixes to strip, tried before the built-in ones.
+        pub rule: Vec<TitleRule>,
+    }
+
+    impl Default for TitleRules {
+        fn default() -> Self {
+            Self {
+                strip_counters: true,
+                strip_emoji: false,
+                fold_case: false,
+                builtin_rules: true,
+                rule: Vec::new(),
+            }
+        }
+    }
+
+    /// A prefix and/or suffix stripped from titles, given as regexes.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct TitleRule {
+        /// Only for tabs on this domain and its subdomains; all tabs if unset.
+        #[serde(default)]
+        pub domain: Option<String>,
+        #[serde(default)]
+        pub prefix: Option<String>,
+        #[serde(default)]
+        pub suffix: Option<String>,
+    }
+
+    /// A compiled [`TitleRules`].
+    #[derive(Debug, Clone)]
+    pub struct TitlePipeline {
+        strip_counters: bool,
+        strip_emoji: bool,
+        fold_case: bool,
+        /// `(domain, regex anchored at the start or end of the title)`
+        strips: Vec<(Option<String>, Regex)>,
+    }
 
-        // 1. Unicode NFC normalization
-        let normalized = title.nfc().collect::<String>();
+    fn counter() -> &'static Regex {
+        static COUNTER: OnceLock<Regex> = OnceLock::new();
+        COUNTER.get_or_init(|| Regex::new(r"^\(\d+\+?\)\s*").expect("valid regex"))
+    }
 
-        // 2. Trim whitespace
-        let trimmed = normalized.trim();
+    fn emoji() -> &'static Regex {
+        static EMOJI: OnceLock<Regex> = OnceLock::new();
+        // Pictographs and the modifiers and joiners that combine them, but
+        // not the ©, ® and ™ signs
+        EMOJI.get_or_init(|| {
+            Regex::new(r"[[\p{Extended_Pictographic}\p{Emoji_Modifier}\u{FE0F}\u{200D}]&&[^©®™]]")
+                .expect("valid regex")
+        })
+    }
 
-        // 3. Collapse multiple spaces to single
-        let collapsed = trimmed.split_whitespace().collect::<Vec<_>>().join(" ");
+    /// Whether `host` is `domain` or one of its subdomains, ignoring `www.`.
+    fn on_domain(host: &str, domain: &str) -> bool {
+        let host = host.strip_prefix("www.").unwrap_or(host);
+        let domain = domain.strip_prefix("www.").unwrap_or(domain);
+        host.eq_ignore_ascii_case(domain)
+            || host.len() > domain.len()
+                && host[host.len() - domain.len()..].eq_ignore_ascii_case(domain)
+                && host.as_bytes()[host.len() - domain.len() - 1] == b'.'
+    }
 
-        // 4. Strip common site suffixes (from last occurrence)
-        let mut result = collapsed;
-        for suffix in Self::SUFFIXES {
-            if let Some(pos) = result.rfind(suffix) {
-                result = result[..pos].to_string();
+    impl TitlePipeline {
+        /// Compiles `rules`, failing on a regex that doesn't parse or a rule
+        /// with neither a prefix nor a suffix.
+        pub fn new(rules: &TitleRules) -> anyhow::Result<Self> {
+            let mut strips = Vec::new();
+            for rule in &rules.rule {
+                anyhow::ensure!(
+                    rule.prefix.is_some() || rule.suffix.is_some(),
+                    "title rule{} has neither a prefix nor a suffix",
+                    rule.domain.as_ref().map(|d| format!(" for {}", d)).unwrap_or_default()
+                );
+                if let Some(prefix) = &rule.prefix {
+                    let regex = Regex::new(&format!("^(?:{})", prefix))
+                        .with_context(|| format!("invalid title prefix '{}'", prefix))?;
+                    strips.push((rule.domain.clone(), regex));
+                }
+                if let Some(suffix) = &rule.suffix {
+                    let regex = Regex::new(&format!("(?:{})$", suffix))
+                        .with_context(|| format!("invalid title suffix '{}'", suffix))?;
+                    strips.push((rule.domain.clone(), regex));
+                }
             }
+            if rules.builtin_rules {
+                for (domain, suffix) in BUILTIN_SUFFIXES {
+                    let regex = Regex::new(&format!("(?:{})$", suffix)).expect("valid built-in suffix");
+                    strips.push((domain.map(str::to_string), regex));
+                }
+            }
+            Ok(Self {
+                strip_counters: rules.strip_counters,
+                strip_emoji: rules.strip_emoji,
+                fold_case: rules.fold_case,
+                strips,
+            })
         }
 
-        result.trim().to_string()
-    }
+        /// Normalizes `title`, applying domain-scoped rules only if the
+        /// tab's `url` is given and on their domain.
+        ///
+        /// Prefixes and suffixes are stripped until none match, so the
+        /// result is stable under normalizing again.
+        pub fn normalize(&self, title: &str, url: Option<&Url>) -> String {
+            use unicode_normalization::UnicodeNormalization;
+
+            let mut result: String = title.nfc().collect();
+            if self.strip_counters {
+                result = counter().replace(result.trim_start(), "").into_owned();
+            }
+            if self.strip_emoji {
+                result = emoji().replace_all(&result, "").into_owned();
+            }
+            result = result.split_whitespace().collect::<Vec<_>>().join(" ");
 
-    /// Normalize all titles in a session.
-    pub fn normalize_session(session: &TabSession) -> TabSession {
-        let mut normalized = session.clone();
-        for group in &mut normalized.groups {
-            for tab in &mut group.tabs {
-                tab.title = Self::normalize(&tab.title);
+            let host = url.and_then(Url::host_str);
+            let strips: Vec<&Regex> = self
+                .strips
+                .iter()
+                .filter(|(domain, _)| match domain {
+                    Some(domain) => host.is_some_and(|host| on_domain(host, domain)),
+                    None => true,
+                })
+                .map(|(_, regex)| regex)
+                .collect();
+            // Stop short of stripping a title down to nothing
+            while let Some(stripped) = strips
+                .iter()
+                .filter_map(|regex| regex.find(&result))
+                .filter(|m| !m.is_empty())
+                .map(|m| format!("{}{}", &result[..m.start()], &result[m.end()..]).trim().to_string())
+                .find(|stripped| !stripped.is_empty())
+            {
+                result = stripped;
             }
+
+            if self.fold_case {
+                result = result.to_lowercase();
+            }
+            result
+        }
+
+        /// Normalize a tab's title, with the rules for its domain.
+        pub fn normalize_tab(&self, tab: &Tab) -> String {
+            self.normalize(&tab.title, Some(&tab.url))
+        }
+
+        /// Normalize all titles in a session.
+        pub fn normalize_session(&self, session: &TabSession) -> TabSession {
+            let mut normalized = session.clone();
+            for group in &mut normalized.groups {
+                for tab in &mut group.tabs {
+                    tab.title = self.normalize_tab(tab);
+                }
+            }
+            normalized
+        }
+    }
+
+    impl Default for TitlePipeline {
+        /// The default [`TitleRules`], compiled.
+        fn default() -> Self {
+            default_pipeline().clone()
+        }
+    }
+
+    fn default_pipeline() -> &'static TitlePipeline {
+        static PIPELINE: OnceLock<TitlePipeline> = OnceLock::new();
+        PIPELINE.get_or_init(|| TitlePipeline::new(&TitleRules::default()).expect("default title rules compile"))
+    }
+
+    /// Normalizes tab titles for improved matching, with the default
+    /// [`TitleRules`]. Build a [`TitlePipeline`] to apply configured ones.
+    pub struct TitleNormalizer;
+
+    impl TitleNormalizer {
+        /// Normalize a single title, without domain-scoped rules.
+        pub fn normalize(title: &str) -> String {
+            default_pipeline().normalize(title, None)
+        }
+
+        /// Normalize a tab's title, with the rules for its domain.
+        pub fn normalize_tab(tab: &Tab) -> String {
+            default_pipeline().normalize_tab(tab)
+        }
+
+        /// Normalize all titles in a session.
+        pub fn normalize_session(session: &TabSession) -> TabSession {
+            default_pipeline().normalize_session(session)
         }
-        normalized
     }
 }
 
-/// Deduplicates tabs based on URL similarity.
-pub struct DedupEngine;
+/// Deduplicates tabs based on URL similarity, comparing URLs under its
+/// [`CanonicalRules`] and titles through its [`TitlePipeline`].
+#[derive(Debug, Clone, Default)]
+pub struct DedupEngine {
+    canonical: CanonicalRules,
+    titles: TitlePipeline,
+}
 
 /// Strategy for determining duplicate URLs.
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -226,10 +413,14 @@ pub enum DedupStrategy {
     ExactUrl,
     /// Normalized URL match (case-insensitive, query params normalized).
     NormalizedUrl,
-    /// Fuzzy match on URLs.
+    /// Near-identical URLs on the same host and first path segment, with a
+    /// path-and-query similarity (0.0–1.0) of at least `threshold`.
     FuzzyUrl { threshold: f32 },
     /// Match on both URL and title.
     UrlAndTitle,
+    /// Same page under another URL, or a title at least `threshold`
+    /// similar; see [`DedupEngine::near_duplicates`].
+    NearDuplicate { threshold: f32 },
 }
 
 /// Result of a deduplication operation.
@@ -241,34 +432,50 @@ pub struct DedupResult {
     pub deduplicated_count: usize,
     /// Tabs that were removed as duplicates.
     pub removed: Vec<Tab>,
+    /// For each removed tab's id, the id of the kept tab it duplicates.
+    pub replaced_by: HashMap<String, String>,
     /// The deduplicated session.
     pub session: TabSession,
 }
 
 impl DedupEngine {
+    /// The `FuzzyUrl` threshold the CLI uses unless told otherwise.
+    pub const DEFAULT_FUZZY_THRESHOLD: f32 = 0.9;
+
+    /// An engine that compares URLs under `canonical` and titles normalized
+    /// by `titles`.
+    pub fn new(canonical: CanonicalRules, titles: TitlePipeline) -> Self {
+        Self { canonical, titles }
+    }
+
     /// Deduplicate tabs in a session using the specified strategy.
-    pub fn dedup(session: &TabSession, strategy: DedupStrategy) -> DedupResult {
+    pub fn dedup(&self, session: &TabSession, strategy: DedupStrategy) -> DedupResult {
         match strategy {
             DedupStrategy::ExactUrl => Self::dedup_exact_url(session),
-            DedupStrategy::NormalizedUrl => Self::dedup_normalized_url(session),
-            DedupStrategy::FuzzyUrl { threshold } => Self::dedup_fuzzy_url(session, threshold),
-            DedupStrategy::UrlAndTitle => Self::dedup_url_and_title(session),
+            DedupStrategy::NormalizedUrl => self.dedup_normalized_url(session),
+            DedupStrategy::FuzzyUrl { threshold } => self.dedup_fuzzy_url(session, threshold),
+            DedupStrategy::UrlAndTitle => self.dedup_url_and_title(session),
+            DedupStrategy::NearDuplicate { threshold } => {
+                Self::merge_clusters(session, &self.near_duplicates(session, threshold))
+            }
         }
     }
 
     fn dedup_exact_url(session: &TabSession) -> DedupResult {
-        let mut seen_urls = HashSet::new();
+        let mut seen_urls: HashMap<String, String> = HashMap::new();
         let mut removed = Vec::new();
+        let mut replaced_by = HashMap::new();
         let mut deduped_session = session.clone();
 
         for group in &mut deduped_session.groups {
             let mut kept_tabs = Vec::new();
             for tab in &group.tabs {
                 let url_str = tab.url.as_str().to_string();
-                if seen_urls.contains(&url_str) {
+                if let Some(kept) = seen_urls.get(&url_str) {
+                    replaced_by.insert(tab.id.clone(), kept.clone());
                     removed.push(tab.clone());
                 } else {
-                    seen_urls.insert(url_str);
+                    seen_urls.insert(url_str, tab.id.clone());
                     kept_tabs.push(tab.clone());
                 }
             }
@@ -282,23 +489,26 @@ impl DedupEngine {
             original_count,
             deduplicated_count,
             removed,
+            replaced_by,
             session: deduped_session,
         }
     }
 
-    fn dedup_normalized_url(session: &TabSession) -> DedupResult {
-        let mut seen_urls = HashSet::new();
+    fn dedup_normalized_url(&self, session: &TabSession) -> DedupResult {
+        let mut seen_urls: HashMap<String, String> = HashMap::new();
         let mut removed = Vec::new();
+        let mut replaced_by = HashMap::new();
         let mut deduped_session = session.clone();
 
         for group in &mut deduped_session.groups {
             let mut kept_tabs = Vec::new();
             for tab in &group.tabs {
-                let normalized = Self::normalize_url(tab.url.as_str());
-                if seen_urls.contains(&normalized) {
+                let normalized = self.normalize_url(tab.url.as_str());
+                if let Some(kept) = seen_urls.get(&normalized) {
+                    replaced_by.insert(tab.id.clone(), kept.clone());
                     removed.push(tab.clone());
                 } else {
-                    seen_urls.insert(normalized);
+                    seen_urls.insert(normalized, tab.id.clone());
                     kept_tabs.push(tab.clone());
                 }
             }
@@ -312,55 +522,46 @@ impl DedupEngine {
             original_count,
             deduplicated_count,
             removed,
+            replaced_by,
             session: deduped_session,
         }
     }
 
-    fn dedup_fuzzy_url(session: &TabSession, threshold: f32) -> DedupResult {
-        let mut kept_tabs: Vec<(String, Tab)> = Vec::new(); // (normalized_url, tab)
+    /// Keeps the first tab of every set of near-identical URLs, in its group.
+    ///
+    /// Tabs are only compared within a block of the same host (ignoring
+    /// `www.`) and first path segment; see [`UrlBlock`] for how candidates
+    /// within a block are narrowed down.
+    fn dedup_fuzzy_url(&self, session: &TabSession, threshold: f32) -> DedupResult {
+        let mut blocks: HashMap<String, UrlBlock> = HashMap::new();
+        let mut seen_urls: HashMap<String, String> = HashMap::new();
         let mut removed = Vec::new();
-        let mut matcher = Matcher::new(Config::DEFAULT);
+        let mut replaced_by = HashMap::new();
+        let mut deduped_session = session.clone();
 
-        for group in &session.groups {
+        for group in &mut deduped_session.groups {
+            let mut kept_tabs = Vec::new();
             for tab in &group.tabs {
-                let normalized = Self::normalize_url(tab.url.as_str());
-                let mut found_duplicate = false;
-
-                for (existing_url, _) in &kept_tabs {
-                    if Self::fuzzy_match_urls(&normalized, existing_url, threshold, &mut matcher) {
-                        found_duplicate = true;
-                        break;
-                    }
-                }
-
-                if found_duplicate {
+                let normalized = self.normalize_url(tab.url.as_str());
+                let (block, rest) = Self::url_block(&normalized);
+                let rest: Vec<char> = rest.chars().collect();
+                let block = blocks.entry(block).or_default();
+                let duplicate_of = seen_urls
+                    .get(&normalized)
+                    .or_else(|| block.find(&rest, threshold))
+                    .cloned();
+
+                if let Some(kept) = duplicate_of {
+                    replaced_by.insert(tab.id.clone(), kept);
                     removed.push(tab.clone());
                 } else {
-                    kept_tabs.push((normalized, tab.clone()));
-                }
-            }
-        }
-
-        // Rebuild session with kept tabs (grouping gets lost, put all in one group)
-        let deduped_session = TabSession {
-            version: session.version,
-            source: session.source.clone(),
-            groups: if kept_tabs.is_empty() {
-                vec![]
-            } else {
-                vec![TabGroup {
-                    id: "deduped".to_string(),
-                    label: None,
-                    created_at: session.created_at,
-                    tabs: kept_tabs.into_iter().map(|(_, tab)| tab).collect(),
-                    pinned: false,
-                    locked: false,
-                    starred: false,
-                }]
-            },
-            created_at: session.created_at,
-            imported_at: session.imported_at,
-        };
+                    seen_urls.insert(normalized, tab.id.clone());
+                    block.push(rest, tab.id.clone());
+                    kept_tabs.push(tab.clone());
+                }
+            }
+            group.tabs = kept_tabs;
+        }
 
         let original_count = session.total_tab_count();
         let deduplicated_count = deduped_session.total_tab_count();
@@ -369,26 +570,29 @@ impl DedupEngine {
             original_count,
             deduplicated_count,
             removed,
+            replaced_by,
             session: deduped_session,
         }
     }
 
-    fn dedup_url_and_title(session: &TabSession) -> DedupResult {
-        let mut seen: HashSet<(String, String)> = HashSet::new(); // (normalized_url, normalized_title)
+    fn dedup_url_and_title(&self, session: &TabSession) -> DedupResult {
+        let mut seen: HashMap<(String, String), String> = HashMap::new(); // (normalized_url, normalized_title)
         let mut removed = Vec::new();
+        let mut replaced_by = HashMap::new();
         let mut deduped_session = session.clone();
 
         for group in &mut deduped_session.groups {
             let mut kept_tabs = Vec::new();
             for tab in &group.tabs {
-                let normalized_url = Self::normalize_url(tab.url.as_str());
-                let normalized_title = TitleNormalizer::normalize(&tab.title);
+                let normalized_url = self.normalize_url(tab.url.as_str());
+                let normalized_title = self.titles.normalize_tab(tab);
                 let key = (normalized_url, normalized_title);
 
-                if seen.contains(&key) {
+                if let Some(kept) = seen.get(&key) {
+                    replaced_by.insert(tab.id.clone(), kept.clone());
                     removed.push(tab.clone());
                 } else {
-                    seen.insert(key);
+                    seen.insert(key, tab.id.clone());
                     kept_tabs.push(tab.clone());
                 }
             }
@@ -402,74 +606,995 @@ impl DedupEngine {
             original_count,
             deduplicated_count,
             removed,
+            replaced_by,
             session: deduped_session,
         }
     }
 
-    /// Normalize a URL for comparison.
+    /// Normalize a URL for comparison; see [`CanonicalRules::canonicalize`].
+    pub fn normalize_url(&self, url: &str) -> String {
+        self.canonical.canonicalize(url)
+    }
+
+    /// Splits a canonical URL into its fuzzy-dedup block (host without
+    /// `www.` and first path segment) and the rest (path and query).
+    fn url_block(canonical: &str) -> (String, String) {
+        let Ok(parsed) = url::Url::parse(canonical) else {
+            return (canonical.to_string(), String::new());
+        };
+        let host = parsed.host_str().unwrap_or_default();
+        let host = host.strip_prefix("www.").unwrap_or(host);
+        let first = parsed.path_segments().and_then(|mut s| s.next()).unwrap_or_default();
+        let rest = match parsed.query() {
+            Some(query) => format!("{}?{}", parsed.path(), query),
+            None => parsed.path().to_string(),
+        };
+        (format!("{}/{}", host, first), rest)
+    }
+
+    /// Whether two strings are at least `threshold` similar, where the
+    /// similarity is one minus their edit distance over the longer length.
     ///
-    /// Lowercases scheme/host, removes fragment, normalizes query params,
-    /// removes trailing slash (unless path is just "/").
-    pub fn normalize_url(url: &str) -> String {
-        if let Ok(mut parsed) = Url::parse(url) {
-            // Lowercase scheme and host
-            let scheme: &str = parsed.scheme();
-            let _ = parsed.set_scheme(&scheme.to_lowercase());
-            if let Some(host_str) = parsed.host_str() {
-                let host: String = host_str.to_lowercase();
-                let _ = parsed.set_host(Some(&host));
-            }
-
-            // Remove fragment
-            parsed.set_fragment(None);
-
-            // Remove utm_* query params and sort remaining
-            let query_params: Vec<(String, String)> = parsed
-                .query_pairs()
-                .map(|(k, v)| (k.to_string(), v.to_string()))
-                .filter(|(k, _)| !k.starts_with("utm_"))
-                .collect();
+    /// Only the diagonal band of the edit table that can stay within the
+    /// allowed distance is computed, and the comparison stops once no cell can.
+    fn similar_within(a: &[char], b: &[char], threshold: f32) -> bool {
+        let longest = a.len().max(b.len());
+        let max_edits = ((1.0 - threshold) * longest as f32 + 1e-4).floor().max(0.0) as usize;
+        if a.len().abs_diff(b.len()) > max_edits {
+            return false;
+        }
+        // Distances above `max_edits` are all stored as `beyond`
+        let beyond = max_edits + 1;
+        let mut prev: Vec<usize> = (0..=b.len()).map(|j| j.min(beyond)).collect();
+        let mut cur = vec![beyond; b.len() + 1];
+        for i in 1..=a.len() {
+            let lo = i.saturating_sub(max_edits).max(1);
+            let hi = (i + max_edits).min(b.len());
+            cur[lo - 1] = if lo == 1 { i.min(beyond) } else { beyond };
+            let mut best = cur[lo - 1];
+            for j in lo..=hi {
+                let substitution = prev[j - 1] + usize::from(a[i - 1] != b[j - 1]);
+                cur[j] = substitution.min(prev[j] + 1).min(cur[j - 1] + 1).min(beyond);
+                best = best.min(cur[j]);
+            }
+            if hi < b.len() {
+                cur[hi + 1] = beyond;
+            }
+            if best > max_edits {
+                return false;
+            }
+            std::mem::swap(&mut prev, &mut cur);
+        }
+        prev[b.len()] <= max_edits
+    }
+}
+
+/// The kept tabs of one fuzzy-dedup block, with a trigram index over the
+/// rest of their URLs.
+///
+/// Two strings within edit distance `k` share at least `len - 2 - 3k` of
+/// their trigrams, counted with repeats. So a match must share one of any
+/// `len - 2 - needed + 1` trigrams of the string looked up: only the entries
+/// holding its rarest ones are compared in full.
+#[derive(Default)]
+struct UrlBlock {
+    /// (rest of the canonical URL, kept tab id)
+    entries: Vec<(Vec<char>, String)>,
+    /// trigram -> indexes of the entries containing it
+    grams: HashMap<[char; 3], Vec<usize>>,
+}
 
-            if query_params.is_empty() {
-                parsed.set_query(None);
-            } else {
-                let mut sorted: Vec<(String, String)> = query_params;
-                sorted.sort_by(|a, b| a.0.cmp(&b.0));
-                let query_string = sorted
+impl UrlBlock {
+    fn trigrams(rest: &[char]) -> HashMap<[char; 3], usize> {
+        let mut counts = HashMap::new();
+        for w in rest.windows(3) {
+            *counts.entry([w[0], w[1], w[2]]).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Returns the id of the first entry at least `threshold` similar to `rest`.
+    fn find(&self, rest: &[char], threshold: f32) -> Option<&String> {
+        let matches = |kept: &[char]| {
+            let longest = rest.len().max(kept.len()).max(1);
+            // The edit distance is at least the difference in length
+            1.0 - rest.len().abs_diff(kept.len()) as f32 / longest as f32 >= threshold
+                && DedupEngine::similar_within(rest, kept, threshold)
+        };
+
+        // Trigrams a match must share; it only grows with the longer
+        // string's length when the threshold is above 2/3.
+        let max_edits = ((1.0 - threshold) * rest.len() as f32).floor() as isize;
+        let needed = rest.len() as isize - 2 - 3 * max_edits;
+        if threshold <= 2.0 / 3.0 || needed <= 0 {
+            return self.entries.iter().find(|(kept, _)| matches(kept)).map(|(_, id)| id);
+        }
+
+        let mut grams: Vec<([char; 3], usize)> = Self::trigrams(rest).into_iter().collect();
+        grams.sort_by_key(|(gram, _)| self.grams.get(gram).map_or(0, Vec::len));
+        let mut probe = rest.len() as isize - 2 - needed + 1;
+        let mut candidates = Vec::new();
+        for (gram, count) in grams {
+            if probe <= 0 {
+                break;
+            }
+            probe -= count as isize;
+            candidates.extend(self.grams.get(&gram).into_iter().flatten().copied());
+        }
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
+            .into_iter()
+            .map(|index| &self.entries[index])
+            .find(|(kept, _)| matches(kept))
+            .map(|(_, id)| id)
+    }
+
+    fn push(&mut self, rest: Vec<char>, id: String) {
+        let index = self.entries.len();
+        for gram in Self::trigrams(&rest).into_keys() {
+            self.grams.entry(gram).or_default().push(index);
+        }
+        self.entries.push((rest, id));
+    }
+}
+
+pub use near_duplicate::{rewrite_url, DuplicateCluster, MatchReason, NearDuplicate};
+
+/// Near-duplicates whose URLs differ: the same page behind another host,
+/// an AMP or mobile copy, a short link or another view of the same paper.
+///
+/// URLs are first reduced by [`rewrite_url`]; tabs whose rewritten URLs are
+/// equal are duplicates. Otherwise titles are compared by MinHash over their
+/// character trigrams, with locality-sensitive hashing so only tabs sharing a
+/// band of the signature are compared.
+mod near_duplicate {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// MinHash signature length, split into [`BANDS`] bands for LSH.
+    const HASHES: usize = 64;
+    const BANDS: usize = 16;
+    const ROWS: usize = HASHES / BANDS;
+    /// Titles shorter than this (normalized) are too generic to compare.
+    const MIN_TITLE_CHARS: usize = 12;
+
+    /// Hosts that serve the same pages as another.
+    const HOST_ALIASES: &[(&str, &str)] = &[
+        ("youtu.be", "youtube.com"),
+        ("old.reddit.com", "reddit.com"),
+        ("new.reddit.com", "reddit.com"),
+        ("np.reddit.com", "reddit.com"),
+        ("twitter.com", "x.com"),
+        ("export.arxiv.org", "arxiv.org"),
+    ];
+
+    /// Why a tab was put in a cluster.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum MatchReason {
+        /// Its URL is the same as the kept tab's after [`rewrite_url`].
+        SameUrl,
+        /// Its title is similar to the kept tab's.
+        SimilarTitle,
+    }
+
+    /// A tab found to duplicate a cluster's kept tab.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct NearDuplicate {
+        pub tab_id: String,
+        /// Similarity to the kept tab, from 0.0 to 1.0 (1.0 for [`MatchReason::SameUrl`]).
+        pub score: f32,
+        pub reason: MatchReason,
+    }
+
+    /// A tab and the later tabs that duplicate it.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct DuplicateCluster {
+        /// The first of the tabs in session order, which is kept
+        pub keep: String,
+        pub duplicates: Vec<NearDuplicate>,
+    }
+
+    /// Reduces a URL to a key that other copies of the same page share.
+    ///
+    /// Starts from the URL canonicalized by `rules`, then drops the scheme,
+    /// `www.`, `m.` and `mobile.` hosts, AMP variants (`/amp` paths, `amp=1`
+    /// and the AMP cache), and maps [`HOST_ALIASES`]. Well-known sites are
+    /// reduced to their IDs: `youtube:<video>` for watch pages, short links,
+    /// shorts and embeds, and `arxiv:<paper>` for abstracts and PDFs of any version.
+    pub fn rewrite_url(url: &str, rules: &CanonicalRules) -> String {
+        let canonical = rules.canonicalize(url);
+        let Ok(parsed) = url::Url::parse(&canonical) else {
+            return canonical;
+        };
+        let Some(mut host) = parsed.host_str().map(str::to_string) else {
+            return canonical;
+        };
+        let mut path = parsed.path().to_string();
+        let mut query: Vec<(String, String)> = parsed
+            .query_pairs()
+            .filter(|(k, v)| !(k == "amp" || (k == "outputType" && v == "amp")))
+            .map(|(k, v)| (k.into_owned(), v.into_owned()))
+            .collect();
+
+        // AMP cache: https://example-com.cdn.ampproject.org/c/s/example.com/page
+        if host.ends_with(".cdn.ampproject.org") {
+            let rest = path.trim_start_matches("/c").trim_start_matches("/s");
+            if let Some((cached_host, cached_path)) = rest.trim_start_matches('/').split_once('/') {
+                host = cached_host.to_string();
+                path = format!("/{}", cached_path);
+            }
+        }
+        for prefix in ["www.", "m.", "mobile.", "amp."] {
+            if let Some(stripped) = host.strip_prefix(prefix) {
+                host = stripped.to_string();
+            }
+        }
+        // en.m.wikipedia.org and similar
+        host = host.replace(".m.", ".");
+        if let Some((_, to)) = HOST_ALIASES.iter().find(|(from, _)| *from == host) {
+            host = to.to_string();
+        }
+        if let Some(stripped) = path.strip_suffix("/amp") {
+            path = stripped.to_string();
+        } else if path.starts_with("/amp/") {
+            path = path["/amp".len()..].to_string();
+        }
+        path = path.trim_end_matches('/').to_string();
+
+        match host.as_str() {
+            "youtube.com" => {
+                let video = query.iter().find(|(k, _)| k == "v").map(|(_, v)| v.clone()).or_else(|| {
+                    let mut segments = path.trim_start_matches('/').split('/');
+                    match (segments.next(), segments.next()) {
+                        (Some("shorts" | "embed" | "live"), Some(id)) => Some(id.to_string()),
+                        (Some(id), None) if parsed.host_str() == Some("youtu.be") && !id.is_empty() => {
+                            Some(id.to_string())
+                        }
+                        _ => None,
+                    }
+                });
+                if let Some(video) = video {
+                    return format!("youtube:{}", video);
+                }
+            }
+            "arxiv.org" => {
+                let mut segments = path.trim_start_matches('/').splitn(2, '/');
+                if let (Some("abs" | "pdf" | "html"), Some(id)) = (segments.next(), segments.next()) {
+                    let id = id.trim_end_matches(".pdf");
+                    // Drop a version suffix like `v2`
+                    let id = match id.rsplit_once('v') {
+                        Some((paper, version))
+                            if !paper.is_empty() && !version.is_empty() && version.chars().all(|c| c.is_ascii_digit()) =>
+                        {
+                            paper
+                        }
+                        _ => id,
+                    };
+                    return format!("arxiv:{}", id);
+                }
+            }
+            _ => {}
+        }
+        query.sort();
+        if query.is_empty() {
+            format!("{}{}", host, path)
+        } else {
+            let query: Vec<String> = query.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+            format!("{}{}?{}", host, path, query.join("&"))
+        }
+    }
+
+    /// The normalized, lowercased title, if it's long enough to compare.
+    fn comparable_title(tab: &Tab, titles: &TitlePipeline) -> Option<Vec<char>> {
+        let title: Vec<char> = titles.normalize_tab(tab).to_lowercase().chars().collect();
+        (title.len() >= MIN_TITLE_CHARS).then_some(title)
+    }
+
+    /// Odd multipliers for the MinHash family `h_i(x) = x * SEEDS[i]`, from
+    /// a SplitMix64 sequence.
+    const SEEDS: [u64; HASHES] = {
+        let mut seeds = [0u64; HASHES];
+        let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut i = 0;
+        while i < HASHES {
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            seeds[i] = (z ^ (z >> 31)) | 1;
+            i += 1;
+        }
+        seeds
+    };
+
+    fn minhash(title: &[char]) -> [u64; HASHES] {
+        let shingles: HashSet<u64> = title
+            .windows(3)
+            .map(|w| {
+                // Mix the packed trigram once so the multiplications see all its bits
+                let mut h = w.iter().fold(0u64, |h, c| (h << 21) | *c as u64);
+                h ^= h >> 33;
+                h = h.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+                h ^ (h >> 33)
+            })
+            .collect();
+        let mut signature = [u64::MAX; HASHES];
+        for shingle in shingles {
+            for (slot, seed) in signature.iter_mut().zip(SEEDS) {
+                *slot = (*slot).min(shingle.wrapping_mul(seed).rotate_left(32));
+            }
+        }
+        signature
+    }
+
+    /// Estimated Jaccard similarity of two titles' trigram sets.
+    fn similarity(a: &[u64; HASHES], b: &[u64; HASHES]) -> f32 {
+        a.iter().zip(b).filter(|(x, y)| x == y).count() as f32 / HASHES as f32
+    }
+
+    fn band_key(signature: &[u64; HASHES], band: usize) -> (usize, u64) {
+        let rows = &signature[band * ROWS..(band + 1) * ROWS];
+        let mut h = band as u64;
+        for row in rows {
+            h = (h ^ row).wrapping_mul(1099511628211);
+        }
+        (band, h)
+    }
+
+    impl DedupEngine {
+        /// The `NearDuplicate` threshold the CLI uses unless told otherwise.
+        pub const DEFAULT_NEAR_DUPLICATE_THRESHOLD: f32 = 0.8;
+
+        /// Finds clusters of near-duplicate tabs, in session order.
+        ///
+        /// Tabs are first grouped by rewritten URL. Each group is then merged
+        /// into the earliest kept one with a title at least `threshold`
+        /// similar to one of its own; the first tab of a cluster is kept.
+        pub fn near_duplicates(&self, session: &TabSession, threshold: f32) -> Vec<DuplicateCluster> {
+            // Tabs by rewritten URL, in order of first appearance
+            let mut by_url: HashMap<String, usize> = HashMap::new();
+            let mut url_groups: Vec<Vec<&Tab>> = Vec::new();
+            for tab in session.groups.iter().flat_map(|g| &g.tabs) {
+                let next = url_groups.len();
+                let index = *by_url.entry(rewrite_url(tab.url.as_str(), &self.canonical)).or_insert(next);
+                if index == next {
+                    url_groups.push(Vec::new());
+                }
+                url_groups[index].push(tab);
+            }
+
+            let mut clusters: Vec<DuplicateCluster> = Vec::new();
+            let mut signatures: Vec<Vec<[u64; HASHES]>> = Vec::new();
+            let mut buckets: HashMap<(usize, u64), Vec<usize>> = HashMap::new();
+            for tabs in url_groups {
+                let own: Vec<[u64; HASHES]> =
+                    tabs.iter().filter_map(|t| comparable_title(t, &self.titles)).map(|t| minhash(&t)).collect();
+                let mut candidates: Vec<usize> = own
                     .iter()
-                    .map(|(k, v)| format!("{}={}", k, v))
-                    .collect::<Vec<_>>()
-                    .join("&");
-                parsed.set_query(Some(&query_string));
+                    .flat_map(|sig| (0..BANDS).filter_map(|band| buckets.get(&band_key(sig, band))))
+                    .flatten()
+                    .copied()
+                    .collect();
+                candidates.sort_unstable();
+                candidates.dedup();
+                let best = candidates
+                    .into_iter()
+                    .filter_map(|c| {
+                        let score = own
+                            .iter()
+                            .flat_map(|a| signatures[c].iter().map(|b| similarity(a, b)))
+                            .reduce(f32::max)?;
+                        (score >= threshold).then_some((c, score))
+                    })
+                    .fold(None, |best: Option<(usize, f32)>, (c, score)| match best {
+                        Some((_, top)) if top >= score => best,
+                        _ => Some((c, score)),
+                    });
+
+                let cluster = match best {
+                    Some((cluster, score)) => {
+                        clusters[cluster].duplicates.extend(tabs.iter().map(|t| NearDuplicate {
+                            tab_id: t.id.clone(),
+                            score,
+                            reason: MatchReason::SimilarTitle,
+                        }));
+                        cluster
+                    }
+                    None => {
+                        clusters.push(DuplicateCluster {
+                            keep: tabs[0].id.clone(),
+                            duplicates: tabs[1..]
+                                .iter()
+                                .map(|t| NearDuplicate { tab_id: t.id.clone(), score: 1.0, reason: MatchReason::SameUrl })
+                                .collect(),
+                        });
+                        signatures.push(Vec::new());
+                        clusters.len() - 1
+                    }
+                };
+                for signature in own {
+                    for band in 0..BANDS {
+                        buckets.entry(band_key(&signature, band)).or_default().push(cluster);
+                    }
+                    signatures[cluster].push(signature);
+                }
             }
 
-            // Remove trailing slash from path (unless path is just "/")
-            let path: String = parsed.path().to_string();
-            if path.ends_with('/') && path.len() > 1 {
-                let new_path = &path[..path.len() - 1];
-                let _ = parsed.set_path(new_path);
+            clusters.retain(|c| !c.duplicates.is_empty());
+            clusters
+        }
+
+        /// Removes the duplicates of `clusters` from their groups, keeping
+        /// each cluster's kept tab where it is.
+        pub fn merge_clusters(session: &TabSession, clusters: &[DuplicateCluster]) -> DedupResult {
+            let replaced_by: HashMap<String, String> = clusters
+                .iter()
+                .flat_map(|c| c.duplicates.iter().map(move |d| (d.tab_id.clone(), c.keep.clone())))
+                .collect();
+            let mut removed = Vec::new();
+            let mut deduped_session = session.clone();
+            for group in &mut deduped_session.groups {
+                let (gone, kept): (Vec<Tab>, Vec<Tab>) =
+                    group.tabs.drain(..).partition(|t| replaced_by.contains_key(&t.id));
+                removed.extend(gone);
+                group.tabs = kept;
             }
 
-            parsed.to_string()
-        } else {
-            url.to_lowercase()
+            DedupResult {
+                original_count: session.total_tab_count(),
+                deduplicated_count: deduped_session.total_tab_count(),
+                removed,
+                replaced_by,
+                session: deduped_session,
+            }
         }
     }
+}
 
-    /// Check if two URLs match fuzzily above threshold.
-    fn fuzzy_match_urls(a: &str, b: &str, threshold: f32, matcher: &mut Matcher) -> bool {
-        let pattern = Pattern::parse(a, CaseMatching::Smart, Normalization::Smart);
-        let mut buf = Vec::new();
+pub use query::{Flag, Predicate, Query, QueryError, SqlFilter, SqlValue};
 
-        let haystack = nucleo::Utf32Str::new(b, &mut buf);
-        let score = pattern.score(haystack, matcher);
+/// A small filtering language shared by `search`, `list`, `export` and the
+/// MCP tools, e.g. `domain:github.com starred:true after:2024-01-01 tag:rust "async runtime"`.
+///
+/// Terms separated by whitespace must all match; `OR`, `NOT` / `-term` and
+/// parentheses combine them further. A [`Query`] is evaluated per tab (with its
+/// group) either in memory via [`Query::matches`] or in SQLite via [`Query::to_sql`].
+mod query {
+    use super::*;
+    use chrono::{DateTime, NaiveDate, Utc};
+    use thiserror::Error;
 
-        match score {
-            Some(s) => {
-                let normalized = s as f32 / u32::MAX as f32;
-                normalized >= threshold
+    /// Fields understood by the query language.
+    const FIELDS: &[&str] = &[
+        "domain", "url", "title", "label", "tag", "starred", "pinned", "locked", "after", "before",
+        "bookmarked", "visits", "visited-after", "visited-before",
+    ];
+
+    /// A parsed query.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Query {
+        /// Every sub-query must match; an empty list matches everything.
+        And(Vec<Query>),
+        /// At least one sub-query must match.
+        Or(Vec<Query>),
+        /// The sub-query must not match.
+        Not(Box<Query>),
+        /// A single condition.
+        Predicate(Predicate),
+    }
+
+    /// A single condition on a tab or its group.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Predicate {
+        /// Bare word or quoted phrase: substring of the title, URL or group label.
+        Text(String),
+        /// `domain:` — the URL host is this domain or one of its subdomains.
+        Domain(String),
+        /// `url:` — substring of the URL.
+        Url(String),
+        /// `title:` — substring of the title.
+        Title(String),
+        /// `label:` — substring of the group label.
+        Label(String),
+        /// `tag:` — the tab or its group carries this tag.
+        Tag(String),
+        /// `starred:` / `pinned:` / `locked:` — a group flag has this value.
+        Flag(Flag, bool),
+        /// `after:` — the tab was added at or after this instant.
+        After(DateTime<Utc>),
+        /// `before:` — the tab was added strictly before this instant.
+        Before(DateTime<Utc>),
+        /// `bookmarked:` — whether the URL is bookmarked in an enriched browser.
+        Bookmarked(bool),
+        /// `visits:` — the URL was visited at least this many times.
+        Visits(u64),
+        /// `visited-after:` — the URL was last visited at or after this instant.
+        VisitedAfter(DateTime<Utc>),
+        /// `visited-before:` — the URL was last visited strictly before this instant.
+        VisitedBefore(DateTime<Utc>),
+    }
+
+    /// Boolean group flags that can be queried.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Flag {
+        Starred,
+        Pinned,
+        Locked,
+    }
+
+    impl Flag {
+        fn column(self) -> &'static str {
+            match self {
+                Flag::Starred => "g.starred",
+                Flag::Pinned => "g.pinned",
+                Flag::Locked => "g.locked",
+            }
+        }
+
+        fn get(self, group: &TabGroup) -> bool {
+            match self {
+                Flag::Starred => group.starred,
+                Flag::Pinned => group.pinned,
+                Flag::Locked => group.locked,
+            }
+        }
+    }
+
+    /// Why a query string could not be parsed.
+    #[derive(Error, Debug, Clone, PartialEq)]
+    pub enum QueryError {
+        #[error("unknown field '{0}' (expected one of: {})", FIELDS.join(", "))]
+        UnknownField(String),
+        #[error("'{field}:' needs {expected}, got '{value}'")]
+        InvalidValue { field: String, value: String, expected: &'static str },
+        #[error("unterminated quote")]
+        UnterminatedQuote,
+        #[error("unbalanced parentheses")]
+        UnbalancedParens,
+        #[error("'{0}' is missing an operand")]
+        MissingOperand(&'static str),
+    }
+
+    /// A value bound to a placeholder of a [`SqlFilter`].
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum SqlValue {
+        Text(String),
+        Integer(i64),
+    }
+
+    /// A query compiled to a SQL boolean expression.
+    ///
+    /// The expression refers to the current tab as `t` (`tabs`) and its group
+    /// as `g` (`tab_groups`), and uses numbered placeholders starting at the
+    /// index passed to [`Query::to_sql`].
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct SqlFilter {
+        pub clause: String,
+        pub params: Vec<SqlValue>,
+    }
+
+    impl Default for Query {
+        fn default() -> Self {
+            Query::And(Vec::new())
+        }
+    }
+
+    impl std::str::FromStr for Query {
+        type Err = QueryError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            Query::parse(s)
+        }
+    }
+
+    impl From<Predicate> for Query {
+        fn from(predicate: Predicate) -> Self {
+            Query::Predicate(predicate)
+        }
+    }
+
+    impl Query {
+        /// Parses a query string. An empty string matches everything.
+        pub fn parse(input: &str) -> Result<Query, QueryError> {
+            let tokens = tokenize(input)?;
+            let mut parser = Parser { tokens, pos: 0 };
+            let query = parser.parse_or()?;
+            if parser.pos < parser.tokens.len() {
+                return Err(QueryError::UnbalancedParens);
+            }
+            Ok(query)
+        }
+
+        /// Returns true if this query matches everything.
+        pub fn is_empty(&self) -> bool {
+            matches!(self, Query::And(items) if items.iter().all(Query::is_empty))
+        }
+
+        /// Combines two queries so both must match.
+        pub fn and(self, other: Query) -> Query {
+            match (self, other) {
+                (q, other) if other.is_empty() => q,
+                (q, other) if q.is_empty() => other,
+                (Query::And(mut items), Query::And(more)) => {
+                    items.extend(more);
+                    Query::And(items)
+                }
+                (Query::And(mut items), other) => {
+                    items.push(other);
+                    Query::And(items)
+                }
+                (q, other) => Query::And(vec![q, other]),
+            }
+        }
+
+        /// Splits off the top-level free-text terms, for handing to a ranked
+        /// searcher, and returns them together with the remaining filters.
+        ///
+        /// Text nested under `OR` or `NOT` stays part of the filters.
+        pub fn split_text(&self) -> (Vec<String>, Query) {
+            let items = match self {
+                Query::And(items) => items.as_slice(),
+                other => std::slice::from_ref(other),
+            };
+            let mut text = Vec::new();
+            let mut rest = Vec::new();
+            for item in items {
+                match item {
+                    Query::Predicate(Predicate::Text(s)) => text.push(s.clone()),
+                    other => rest.push(other.clone()),
+                }
+            }
+            let rest = if rest.len() == 1 { rest.remove(0) } else { Query::And(rest) };
+            (text, rest)
+        }
+
+        /// Returns true if `tab`, which belongs to `group`, matches.
+        pub fn matches(&self, group: &TabGroup, tab: &Tab) -> bool {
+            match self {
+                Query::And(items) => items.iter().all(|q| q.matches(group, tab)),
+                Query::Or(items) => items.iter().any(|q| q.matches(group, tab)),
+                Query::Not(q) => !q.matches(group, tab),
+                Query::Predicate(p) => p.matches(group, tab),
+            }
+        }
+
+        /// Returns `group` restricted to its matching tabs, or `None` if none match.
+        pub fn filter_group(&self, group: &TabGroup) -> Option<TabGroup> {
+            if self.is_empty() {
+                return Some(group.clone());
+            }
+            let tabs: Vec<Tab> = group.tabs.iter().filter(|t| self.matches(group, t)).cloned().collect();
+            if tabs.is_empty() {
+                return None;
+            }
+            Some(TabGroup { tabs, ..group.clone() })
+        }
+
+        /// Returns a copy of `session` with only matching tabs, dropping groups left empty.
+        pub fn filter_session(&self, session: &TabSession) -> TabSession {
+            TabSession {
+                groups: session.groups.iter().filter_map(|g| self.filter_group(g)).collect(),
+                ..session.clone()
+            }
+        }
+
+        /// Compiles this query to SQL whose first placeholder is `?{first_param}`.
+        pub fn to_sql(&self, first_param: usize) -> SqlFilter {
+            let mut filter = SqlFilter { clause: String::new(), params: Vec::new() };
+            filter.clause = self.write_sql(first_param, &mut filter.params);
+            filter
+        }
+
+        fn write_sql(&self, first_param: usize, params: &mut Vec<SqlValue>) -> String {
+            let join = |items: &[Query], op: &str, empty: &str, params: &mut Vec<SqlValue>| {
+                if items.is_empty() {
+                    return empty.to_string();
+                }
+                let parts: Vec<String> = items.iter().map(|q| q.write_sql(first_param, params)).collect();
+                format!("({})", parts.join(op))
+            };
+            match self {
+                Query::And(items) => join(items, " AND ", "1", params),
+                Query::Or(items) => join(items, " OR ", "0", params),
+                Query::Not(q) => format!("NOT {}", q.write_sql(first_param, params)),
+                Query::Predicate(p) => p.write_sql(first_param, params),
+            }
+        }
+    }
+
+    impl Predicate {
+        fn matches(&self, group: &TabGroup, tab: &Tab) -> bool {
+            let contains = |haystack: &str, needle: &str| haystack.to_ascii_lowercase().contains(needle);
+            let label = group.label.as_deref().unwrap_or("");
+            match self {
+                Predicate::Text(s) => {
+                    contains(&tab.title, s) || contains(tab.url.as_str(), s) || contains(label, s)
+                }
+                Predicate::Url(s) => contains(tab.url.as_str(), s),
+                Predicate::Title(s) => contains(&tab.title, s),
+                Predicate::Label(s) => contains(label, s),
+                Predicate::Domain(d) => tab.url.host_str().is_some_and(|host| {
+                    host == d || host.strip_suffix(d.as_str()).is_some_and(|sub| sub.ends_with('.'))
+                }),
+                Predicate::Tag(tag) => tab.has_tag(tag) || group.has_tag(tag),
+                Predicate::Flag(flag, value) => flag.get(group) == *value,
+                Predicate::After(at) => tab.added_at >= *at,
+                Predicate::Before(at) => tab.added_at < *at,
+                Predicate::Bookmarked(value) => tab.activity.bookmarked == *value,
+                Predicate::Visits(n) => tab.activity.visit_count >= *n,
+                Predicate::VisitedAfter(at) => tab.activity.last_visited_at.is_some_and(|v| v >= *at),
+                Predicate::VisitedBefore(at) => tab.activity.last_visited_at.is_some_and(|v| v < *at),
+            }
+        }
+
+        fn write_sql(&self, first_param: usize, params: &mut Vec<SqlValue>) -> String {
+            let mut bind = |value: SqlValue| {
+                params.push(value);
+                format!("?{}", first_param + params.len() - 1)
+            };
+            let contains = |column: &str, p: &str| format!("instr(lower({}), {}) > 0", column, p);
+            match self {
+                Predicate::Text(s) => {
+                    let p = bind(SqlValue::Text(s.clone()));
+                    format!(
+                        "({} OR {} OR {})",
+                        contains("t.title", &p),
+                        contains("t.url", &p),
+                        contains("coalesce(g.label, '')", &p)
+                    )
+                }
+                Predicate::Url(s) => contains("t.url", &bind(SqlValue::Text(s.clone()))),
+                Predicate::Title(s) => contains("t.title", &bind(SqlValue::Text(s.clone()))),
+                Predicate::Label(s) => contains("coalesce(g.label, '')", &bind(SqlValue::Text(s.clone()))),
+                Predicate::Domain(d) => {
+                    let p = bind(SqlValue::Text(d.clone()));
+                    let host = host_sql();
+                    format!("({host} = {p} OR substr({host}, -length({p}) - 1) = '.' || {p})")
+                }
+                Predicate::Tag(tag) => {
+                    let p = bind(SqlValue::Text(tag.clone()));
+                    format!(
+                        "(EXISTS (SELECT 1 FROM tab_tags WHERE tab_id = t.id AND tag = {p}) \
+                         OR EXISTS (SELECT 1 FROM group_tags WHERE group_id = g.id AND tag = {p}))"
+                    )
+                }
+                Predicate::Flag(flag, value) => {
+                    format!("{} {} 0", flag.column(), if *value { "!=" } else { "=" })
+                }
+                Predicate::After(at) => format!("t.added_at >= {}", bind(SqlValue::Integer(at.timestamp_millis()))),
+                Predicate::Before(at) => format!("t.added_at < {}", bind(SqlValue::Integer(at.timestamp_millis()))),
+                Predicate::Bookmarked(value) => format!("t.bookmarked {} 0", if *value { "!=" } else { "=" }),
+                Predicate::Visits(n) => format!("t.visit_count >= {}", bind(SqlValue::Integer(*n as i64))),
+                Predicate::VisitedAfter(at) => {
+                    format!("t.last_visited_at >= {}", bind(SqlValue::Integer(at.timestamp_millis())))
+                }
+                Predicate::VisitedBefore(at) => {
+                    format!("t.last_visited_at < {}", bind(SqlValue::Integer(at.timestamp_millis())))
+                }
+            }
+        }
+
+        fn parse_field(field: &str, value: String) -> Result<Predicate, QueryError> {
+            let invalid = |expected| QueryError::InvalidValue {
+                field: field.to_string(),
+                value: value.clone(),
+                expected,
+            };
+            let boolean = || match value.to_ascii_lowercase().as_str() {
+                "true" | "yes" | "1" => Ok(true),
+                "false" | "no" | "0" => Ok(false),
+                _ => Err(invalid("true or false")),
+            };
+            let flag = |flag| boolean().map(|value| Predicate::Flag(flag, value));
+            let name = field.to_ascii_lowercase();
+            if !FIELDS.contains(&name.as_str()) {
+                return Err(QueryError::UnknownField(field.to_string()));
+            }
+            if value.is_empty() {
+                return Err(invalid("a value"));
+            }
+            let lower = value.to_ascii_lowercase();
+            match name.as_str() {
+                "domain" => Ok(Predicate::Domain(lower.trim_start_matches('.').to_string())),
+                "url" => Ok(Predicate::Url(lower)),
+                "title" => Ok(Predicate::Title(lower)),
+                "label" => Ok(Predicate::Label(lower)),
+                "tag" => tablitz_core::normalize_tag(&value)
+                    .map(Predicate::Tag)
+                    .ok_or_else(|| invalid("a tag name")),
+                "starred" => flag(Flag::Starred),
+                "pinned" => flag(Flag::Pinned),
+                "locked" => flag(Flag::Locked),
+                "after" => parse_date(&value).map(Predicate::After).ok_or_else(|| invalid("a date like 2024-01-31")),
+                "before" => parse_date(&value).map(Predicate::Before).ok_or_else(|| invalid("a date like 2024-01-31")),
+                "bookmarked" => boolean().map(Predicate::Bookmarked),
+                "visits" => value.parse().map(Predicate::Visits).map_err(|_| invalid("a number of visits")),
+                "visited-after" => parse_date(&value)
+                    .map(Predicate::VisitedAfter)
+                    .ok_or_else(|| invalid("a date like 2024-01-31")),
+                "visited-before" => parse_date(&value)
+                    .map(Predicate::VisitedBefore)
+                    .ok_or_else(|| invalid("a date like 2024-01-31")),
+                _ => unreachable!("checked against FIELDS"),
+            }
+        }
+    }
+
+    /// SQL expression extracting the lowercase host from `t.url`, matching
+    /// [`Url::host_str`] for the URLs the store holds.
+    fn host_sql() -> String {
+        let after_scheme = "substr(t.url, instr(t.url, '://') + 3)";
+        let authority = format!("substr({0}, 1, instr({0} || '/', '/') - 1)", after_scheme);
+        let host_port = format!("substr({0}, instr({0}, '@') + 1)", authority);
+        format!(
+            "(CASE WHEN instr(t.url, '://') = 0 THEN '' \
+             WHEN instr({0}, ':') > 0 THEN substr({0}, 1, instr({0}, ':') - 1) \
+             ELSE {0} END)",
+            host_port
+        )
+    }
+
+    /// Accepts `YYYY-MM-DD` (midnight UTC) or an RFC 3339 timestamp.
+    fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+        if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            return Some(date.and_hms_opt(0, 0, 0)?.and_utc());
+        }
+        DateTime::parse_from_rfc3339(value).ok().map(|dt| dt.with_timezone(&Utc))
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Token {
+        LParen,
+        RParen,
+        Or,
+        And,
+        Not,
+        /// A bare word or quoted phrase.
+        Text(String),
+        /// `field:value`, where the value may have been quoted.
+        Field(String, String),
+    }
+
+    fn tokenize(input: &str) -> Result<Vec<Token>, QueryError> {
+        let mut tokens = Vec::new();
+        let mut chars = input.chars().peekable();
+        let read_quoted = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+            let mut s = String::new();
+            for c in chars.by_ref() {
+                if c == '"' {
+                    return Ok(s);
+                }
+                s.push(c);
+            }
+            Err(QueryError::UnterminatedQuote)
+        };
+
+        while let Some(&c) = chars.peek() {
+            match c {
+                c if c.is_whitespace() => {
+                    chars.next();
+                }
+                '(' => {
+                    chars.next();
+                    tokens.push(Token::LParen);
+                }
+                ')' => {
+                    chars.next();
+                    tokens.push(Token::RParen);
+                }
+                '"' => {
+                    chars.next();
+                    tokens.push(Token::Text(read_quoted(&mut chars)?));
+                }
+                '-' => {
+                    chars.next();
+                    match chars.peek() {
+                        Some(&next) if !next.is_whitespace() && next != ')' => tokens.push(Token::Not),
+                        _ => tokens.push(Token::Text("-".to_string())),
+                    }
+                }
+                _ => {
+                    let mut word = String::new();
+                    let mut field = None;
+                    while let Some(&c) = chars.peek() {
+                        if c.is_whitespace() || c == '(' || c == ')' {
+                            break;
+                        }
+                        chars.next();
+                        if c == ':' && field.is_none() && !word.is_empty() {
+                            // `http://…` and similar stay plain text
+                            if chars.peek() == Some(&'/') {
+                                word.push(c);
+                                continue;
+                            }
+                            field = Some(std::mem::take(&mut word));
+                            if chars.peek() == Some(&'"') {
+                                chars.next();
+                                word = read_quoted(&mut chars)?;
+                                break;
+                            }
+                            continue;
+                        }
+                        word.push(c);
+                    }
+                    tokens.push(match (field, word.as_str()) {
+                        (Some(field), _) => Token::Field(field, word),
+                        (None, "OR") => Token::Or,
+                        (None, "AND") => Token::And,
+                        (None, "NOT") => Token::Not,
+                        (None, _) => Token::Text(word),
+                    });
+                }
+            }
+        }
+        Ok(tokens)
+    }
+
+    struct Parser {
+        tokens: Vec<Token>,
+        pos: usize,
+    }
+
+    impl Parser {
+        fn peek(&self) -> Option<&Token> {
+            self.tokens.get(self.pos)
+        }
+
+        fn parse_or(&mut self) -> Result<Query, QueryError> {
+            let mut items = vec![self.parse_and()?];
+            while self.peek() == Some(&Token::Or) {
+                self.pos += 1;
+                let next = self.parse_and()?;
+                if next.is_empty() {
+                    return Err(QueryError::MissingOperand("OR"));
+                }
+                items.push(next);
+            }
+            if items.len() == 1 {
+                return Ok(items.remove(0));
+            }
+            if items[0].is_empty() {
+                return Err(QueryError::MissingOperand("OR"));
+            }
+            Ok(Query::Or(items))
+        }
+
+        fn parse_and(&mut self) -> Result<Query, QueryError> {
+            let mut items = Vec::new();
+            loop {
+                match self.peek() {
+                    None | Some(Token::Or) | Some(Token::RParen) => break,
+                    Some(Token::And) => self.pos += 1,
+                    _ => items.push(self.parse_unary()?),
+                }
+            }
+            Ok(if items.len() == 1 { items.remove(0) } else { Query::And(items) })
+        }
+
+        fn parse_unary(&mut self) -> Result<Query, QueryError> {
+            let token = self.tokens[self.pos].clone();
+            self.pos += 1;
+            match token {
+                Token::Not => match self.peek() {
+                    None | Some(Token::Or) | Some(Token::And) | Some(Token::RParen) => {
+                        Err(QueryError::MissingOperand("NOT"))
+                    }
+                    _ => Ok(Query::Not(Box::new(self.parse_unary()?))),
+                },
+                Token::LParen => {
+                    let inner = self.parse_or()?;
+                    if self.peek() != Some(&Token::RParen) {
+                        return Err(QueryError::UnbalancedParens);
+                    }
+                    self.pos += 1;
+                    Ok(inner)
+                }
+                Token::RParen => Err(QueryError::UnbalancedParens),
+                Token::Text(s) => Ok(Predicate::Text(s.to_ascii_lowercase()).into()),
+                Token::Field(field, value) => Ok(Predicate::parse_field(&field, value)?.into()),
+                Token::Or | Token::And => unreachable!("handled by parse_or / parse_and"),
             }
-            None => false,
         }
     }
 }
@@ -482,14 +1607,33 @@ mod fulltext {
     use super::*;
     use anyhow::Context;
     use chrono::{DateTime, Utc};
+    use std::path::{Path, PathBuf};
+    use url::Url;
     use tantivy::{
-        schema::{Schema, Field, STORED, TEXT, Value},
-        Index, IndexWriter, TantivyDocument, collector::TopDocs, query::QueryParser, ReloadPolicy,
+        directory::MmapDirectory,
+        schema::{Schema, Field, STORED, STRING, TEXT, Value},
+        Index, IndexWriter, TantivyDocument, Term, collector::TopDocs, query::QueryParser, ReloadPolicy,
     };
 
+    /// Memory budget for the writer used by one-shot builds and rebuilds.
+    const BULK_WRITER_BUDGET: usize = 50_000_000;
+
+    /// Memory budget for the single-threaded writer used by incremental updates.
+    const INCREMENTAL_WRITER_BUDGET: usize = 15_000_000;
+
+    /// File left in an index directory by [`FullTextIndex::mark_stale`].
+    const STALE_MARKER: &str = "tablitz-stale";
+
     /// Full-text search index using Tantivy.
+    ///
+    /// The index either lives in RAM (see [`FullTextIndex::build`]) or in a
+    /// directory on disk (see [`FullTextIndex::open_or_create`]), in which case
+    /// it can be kept in step with the store through the incremental
+    /// `add_group` / `replace_group` / `delete_group` methods.
     pub struct FullTextIndex {
         index: Index,
+        /// Directory of an on-disk index; `None` for one built in RAM.
+        dir: Option<PathBuf>,
         tab_id_field: Field,
         group_id_field: Field,
         title_field: Field,
@@ -501,44 +1645,81 @@ mod fulltext {
     impl FullTextIndex {
         /// Build a full-text index from a TabSession.
         pub fn build(session: &TabSession) -> anyhow::Result<Self> {
-            let mut schema_builder = Schema::builder();
-            let tab_id_field = schema_builder.add_text_field("tab_id", TEXT | STORED);
-            let group_id_field = schema_builder.add_text_field("group_id", TEXT | STORED);
-            let title_field = schema_builder.add_text_field("title", TEXT | STORED);
-            let url_field = schema_builder.add_text_field("url", TEXT | STORED);
-            let favicon_url_field = schema_builder.add_text_field("favicon_url", STORED);
-            let added_at_field = schema_builder.add_u64_field("added_at", STORED);
-            let schema = schema_builder.build();
+            let index = Index::create_in_ram(Self::schema());
+            let ft = Self::from_index(index, None)?;
+            ft.add_session(session)?;
+            Ok(ft)
+        }
 
-            let index = Index::create_in_ram(schema);
-            let mut writer: IndexWriter = index.writer(50_000_000)?;
+        /// Open the on-disk index in `dir`, creating an empty one if none exists.
+        ///
+        /// Fails if the directory holds an index with a different schema,
+        /// unreadable segments, or one marked with [`FullTextIndex::mark_stale`];
+        /// use [`FullTextIndex::rebuild`] to recover.
+        pub fn open_or_create(dir: &Path) -> anyhow::Result<Self> {
+            if dir.join(STALE_MARKER).exists() {
+                anyhow::bail!("full-text index at {} is out of date with the store", dir.display());
+            }
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create index directory: {}", dir.display()))?;
+            let directory = MmapDirectory::open(dir)
+                .with_context(|| format!("failed to open index directory: {}", dir.display()))?;
+            let index = Index::open_or_create(directory, Self::schema())
+                .with_context(|| format!("failed to open full-text index at: {}", dir.display()))?;
+            Self::from_index(index, Some(dir.to_path_buf()))
+        }
 
-            for group in &session.groups {
-                for tab in &group.tabs {
-                    let mut doc = TantivyDocument::default();
-                    doc.add_text(tab_id_field, &tab.id);
-                    doc.add_text(group_id_field, &group.id);
-                    doc.add_text(title_field, &tab.title);
-                    doc.add_text(url_field, tab.url.as_str());
-                    if let Some(ref favicon) = tab.favicon_url {
-                        doc.add_text(favicon_url_field, favicon);
-                    }
-                    doc.add_u64(added_at_field, tab.added_at.timestamp_millis() as u64);
-                    writer.add_document(doc)?;
-                }
+        /// Discard whatever is in `dir` and re-index every tab in `session`.
+        pub fn rebuild(dir: &Path, session: &TabSession) -> anyhow::Result<Self> {
+            if dir.exists() {
+                std::fs::remove_dir_all(dir)
+                    .with_context(|| format!("failed to remove old index at: {}", dir.display()))?;
             }
+            let ft = Self::open_or_create(dir)?;
+            ft.add_session(session)?;
+            Ok(ft)
+        }
 
+        /// Index all tabs of a newly stored group.
+        pub fn add_group(&self, group: &TabGroup) -> anyhow::Result<()> {
+            let mut writer = self.incremental_writer()?;
+            self.add_group_docs(&mut writer, group)?;
             writer.commit()?;
+            Ok(())
+        }
 
-            Ok(FullTextIndex {
-                index,
-                tab_id_field,
-                group_id_field,
-                title_field,
-                url_field,
-                favicon_url_field,
-                added_at_field,
-            })
+        /// Replace the indexed tabs of a group with its current tab list.
+        pub fn replace_group(&self, group: &TabGroup) -> anyhow::Result<()> {
+            let mut writer = self.incremental_writer()?;
+            writer.delete_term(Term::from_field_text(self.group_id_field, &group.id));
+            self.add_group_docs(&mut writer, group)?;
+            writer.commit()?;
+            Ok(())
+        }
+
+        /// Remove every indexed tab belonging to a group.
+        pub fn delete_group(&self, group_id: &str) -> anyhow::Result<()> {
+            let mut writer = self.incremental_writer()?;
+            writer.delete_term(Term::from_field_text(self.group_id_field, group_id));
+            writer.commit()?;
+            Ok(())
+        }
+
+        /// Records that the index has missed a write, so that opening it again
+        /// fails until it is rebuilt. Does nothing for an index built in RAM.
+        pub fn mark_stale(&self) -> anyhow::Result<()> {
+            if let Some(dir) = &self.dir {
+                let marker = dir.join(STALE_MARKER);
+                std::fs::write(&marker, b"")
+                    .with_context(|| format!("failed to write {}", marker.display()))?;
+            }
+            Ok(())
+        }
+
+        /// Returns the number of tabs currently in the index.
+        pub fn num_docs(&self) -> anyhow::Result<u64> {
+            let reader = self.index.reader()?;
+            Ok(reader.searcher().num_docs())
         }
 
         /// Search the full-text index.
@@ -576,6 +1757,8 @@ mod fulltext {
                                     favicon_url,
                                     added_at: DateTime::from_timestamp_millis(added_ts)
                                         .unwrap_or_else(Utc::now),
+                                    tags: Vec::new(),
+                                    activity: Default::default(),
                                 };
 
                                 results.push(SearchResult {
@@ -592,6 +1775,64 @@ mod fulltext {
 
             Ok(results)
         }
+
+        /// The index schema. IDs are raw (untokenized) so groups can be deleted by term.
+        fn schema() -> Schema {
+            let mut schema_builder = Schema::builder();
+            schema_builder.add_text_field("tab_id", STRING | STORED);
+            schema_builder.add_text_field("group_id", STRING | STORED);
+            schema_builder.add_text_field("title", TEXT | STORED);
+            schema_builder.add_text_field("url", TEXT | STORED);
+            schema_builder.add_text_field("favicon_url", STORED);
+            schema_builder.add_u64_field("added_at", STORED);
+            schema_builder.build()
+        }
+
+        /// Resolve the field handles of an index created with [`Self::schema`].
+        fn from_index(index: Index, dir: Option<PathBuf>) -> anyhow::Result<Self> {
+            let schema = index.schema();
+            Ok(FullTextIndex {
+                tab_id_field: schema.get_field("tab_id")?,
+                group_id_field: schema.get_field("group_id")?,
+                title_field: schema.get_field("title")?,
+                url_field: schema.get_field("url")?,
+                favicon_url_field: schema.get_field("favicon_url")?,
+                added_at_field: schema.get_field("added_at")?,
+                index,
+                dir,
+            })
+        }
+
+        fn add_session(&self, session: &TabSession) -> anyhow::Result<()> {
+            let mut writer: IndexWriter = self.index.writer(BULK_WRITER_BUDGET)?;
+            for group in &session.groups {
+                self.add_group_docs(&mut writer, group)?;
+            }
+            writer.commit()?;
+            Ok(())
+        }
+
+        fn incremental_writer(&self) -> anyhow::Result<IndexWriter> {
+            self.index
+                .writer_with_num_threads(1, INCREMENTAL_WRITER_BUDGET)
+                .context("failed to acquire full-text index writer")
+        }
+
+        fn add_group_docs(&self, writer: &mut IndexWriter, group: &TabGroup) -> anyhow::Result<()> {
+            for tab in &group.tabs {
+                let mut doc = TantivyDocument::default();
+                doc.add_text(self.tab_id_field, &tab.id);
+                doc.add_text(self.group_id_field, &group.id);
+                doc.add_text(self.title_field, &tab.title);
+                doc.add_text(self.url_field, tab.url.as_str());
+                if let Some(ref favicon) = tab.favicon_url {
+                    doc.add_text(self.favicon_url_field, favicon);
+                }
+                doc.add_u64(self.added_at_field, tab.added_at.timestamp_millis() as u64);
+                writer.add_document(doc)?;
+            }
+            Ok(())
+        }
     }
 }
 
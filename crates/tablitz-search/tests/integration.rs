@@ -1,5 +1,5 @@
 use tablitz_core::{Tab, TabGroup, TabSession, SessionSource};
-use tablitz_search::{FuzzySearcher, TitleNormalizer, DedupEngine, DedupStrategy};
+use tablitz_search::{FuzzySearcher, TitleNormalizer, DedupEngine, DedupStrategy, Flag, Predicate, Query, QueryError};
 use chrono::Utc;
 use url::Url;
 
@@ -12,6 +12,8 @@ fn make_tab(id: &str, url: &str, title: &str) -> Tab {
         title: title.to_string(),
         favicon_url: None,
         added_at: Utc::now(),
+        tags: Vec::new(),
+        activity: Default::default(),
     }
 }
 
@@ -23,6 +25,7 @@ fn make_group(id: &str, tabs: Vec<Tab>) -> TabGroup {
         pinned: false,
         locked: false,
         starred: false,
+        tags: Vec::new(),
         tabs,
     }
 }
@@ -153,6 +156,52 @@ fn test_title_normalizer_idempotent() {
     );
 }
 
+#[test]
+fn test_title_normalizer_only_strips_whole_suffixes() {
+    // Site names inside a title stay, and " on X" only goes on X itself
+    assert_eq!(TitleNormalizer::normalize("crates.io | GitHub | mirror"), "crates.io | GitHub | mirror");
+    assert_eq!(TitleNormalizer::normalize("Why I gave up on X"), "Why I gave up on X");
+    assert_eq!(TitleNormalizer::normalize("Rust on Xbox - YouTube"), "Rust on Xbox");
+    let post = make_tab("t1", "https://x.com/jane/status/1", "Jane on X: \"shipping it\" / X");
+    assert_eq!(TitleNormalizer::normalize_tab(&post), "Jane");
+    assert_eq!(TitleNormalizer::normalize("(20+) Okay.. we gotta talk about Rust | daily.dev"), "Okay.. we gotta talk about Rust");
+}
+
+#[test]
+fn test_title_pipeline_rules() {
+    use tablitz_search::{TitlePipeline, TitleRule, TitleRules};
+
+    let rules = TitleRules {
+        strip_emoji: true,
+        fold_case: true,
+        rule: vec![
+            TitleRule { domain: Some("github.com".to_string()), prefix: Some("GitHub - ".to_string()), suffix: None },
+            TitleRule { domain: None, prefix: None, suffix: Some(r" \(\d{4}\)".to_string()) },
+        ],
+        ..TitleRules::default()
+    };
+    let pipeline = TitlePipeline::new(&rules).unwrap();
+    let github = Url::parse("https://www.github.com/tokio-rs/tokio").unwrap();
+    let elsewhere = Url::parse("https://example.com/").unwrap();
+    assert_eq!(pipeline.normalize("(3) 🚀 Launch  Day ✨ - YouTube", None), "launch day");
+    assert_eq!(pipeline.normalize("GitHub - tokio-rs/tokio (2024)", Some(&github)), "tokio-rs/tokio");
+    assert_eq!(pipeline.normalize("GitHub - tokio-rs/tokio (2024)", Some(&elsewhere)), "github - tokio-rs/tokio");
+    assert_eq!(pipeline.normalize("Rust™ 👍🏽", None), "rust™");
+    // Never strips a title to nothing
+    assert_eq!(pipeline.normalize("GitHub - ", Some(&github)), "github -");
+    let tab = make_tab("t1", "https://github.com/tokio-rs/tokio", "GitHub - tokio-rs/tokio");
+    assert_eq!(pipeline.normalize_tab(&tab), "tokio-rs/tokio");
+    let session = pipeline.normalize_session(&make_session(vec![make_group("g1", vec![tab])]));
+    assert_eq!(session.groups[0].tabs[0].title, "tokio-rs/tokio");
+
+    let plain = TitlePipeline::new(&TitleRules { strip_counters: false, builtin_rules: false, ..TitleRules::default() }).unwrap();
+    assert_eq!(plain.normalize("(3) Inbox - YouTube", None), "(3) Inbox - YouTube");
+
+    let bad = |rule: TitleRule| TitlePipeline::new(&TitleRules { rule: vec![rule], ..TitleRules::default() }).is_err();
+    assert!(bad(TitleRule { domain: None, prefix: Some("(".to_string()), suffix: None }));
+    assert!(bad(TitleRule { domain: Some("example.com".to_string()), prefix: None, suffix: None }));
+}
+
 // ─── Dedup – exact URL ─────────────────────────────────────────────────────
 
 #[test]
@@ -162,7 +211,7 @@ fn test_dedup_exact_url_removes_duplicates() {
         make_tab("t2", "https://example.com/", "Title"),
         make_tab("t3", "https://different.com/", "Other"),
     ])]);
-    let result = DedupEngine::dedup(&session, DedupStrategy::ExactUrl);
+    let result = DedupEngine::default().dedup(&session, DedupStrategy::ExactUrl);
     assert_eq!(result.original_count, 3);
     assert_eq!(result.deduplicated_count, 2);
     assert_eq!(result.removed.len(), 1);
@@ -175,7 +224,7 @@ fn test_dedup_exact_url_different_fragment_is_duplicate() {
         make_tab("t1", "https://example.com/page", "A"),
         make_tab("t2", "https://example.com/page", "A"),
     ])]);
-    let result = DedupEngine::dedup(&session, DedupStrategy::ExactUrl);
+    let result = DedupEngine::default().dedup(&session, DedupStrategy::ExactUrl);
     assert_eq!(result.removed.len(), 1);
 }
 
@@ -189,23 +238,36 @@ fn test_dedup_normalized_url() {
         make_tab("t2", "https://example.com/page?utm_source=twitter&utm_medium=social", "A copy"),
         make_tab("t3", "https://different.com/", "Other"),
     ])]);
-    let result = DedupEngine::dedup(&session, DedupStrategy::NormalizedUrl);
+    let result = DedupEngine::default().dedup(&session, DedupStrategy::NormalizedUrl);
     assert_eq!(result.original_count, 3);
     assert_eq!(result.deduplicated_count, 2);
 }
 
 /// Real OneTab pattern: dailydev links with ?ref=dailydev suffix.
-/// Note: only utm_* params are stripped by NormalizedUrl; ?ref= is preserved,
-/// so these count as distinct URLs.
+/// `ref` is one of the default tracking params, so these are the same page.
 #[test]
-fn test_dedup_normalized_url_ref_param_not_stripped() {
+fn test_dedup_normalized_url_ref_param_stripped() {
     let session = make_session(vec![make_group("g1", vec![
         make_tab("t1", "https://engineering.ramp.com/post/why-we-built-our-background-agent", "Ramp"),
         make_tab("t2", "https://engineering.ramp.com/post/why-we-built-our-background-agent?ref=dailydev", "Ramp via daily.dev"),
     ])]);
-    let result = DedupEngine::dedup(&session, DedupStrategy::NormalizedUrl);
-    // ?ref= is not a utm_ param so it's not stripped — these are 2 distinct normalized URLs
-    assert_eq!(result.deduplicated_count, 2, "?ref= is not stripped, so both URLs are distinct");
+    let result = DedupEngine::default().dedup(&session, DedupStrategy::NormalizedUrl);
+    assert_eq!(result.deduplicated_count, 1, "?ref= should be stripped, deduping these two");
+}
+
+/// `www.` and http→https are folded, but single-page app routes in the
+/// fragment are kept apart.
+#[test]
+fn test_dedup_normalized_url_folds_host_and_keeps_routes() {
+    let session = make_session(vec![make_group("g1", vec![
+        make_tab("t1", "https://example.com/post", "Post"),
+        make_tab("t2", "http://www.example.com/post?fbclid=abc#comments", "Post"),
+        make_tab("t3", "https://app.example.com/#/inbox", "Inbox"),
+        make_tab("t4", "https://app.example.com/#/settings", "Settings"),
+    ])]);
+    let result = DedupEngine::default().dedup(&session, DedupStrategy::NormalizedUrl);
+    assert_eq!(result.replaced_by.get("t2").map(String::as_str), Some("t1"));
+    assert_eq!(result.deduplicated_count, 3);
 }
 
 /// utm_ tracking params ARE stripped — these two should be deduped
@@ -215,11 +277,43 @@ fn test_dedup_normalized_url_utm_params_stripped() {
         make_tab("t1", "https://example.com/post", "Post"),
         make_tab("t2", "https://example.com/post?utm_source=twitter&utm_medium=social", "Post via Twitter"),
     ])]);
-    let result = DedupEngine::dedup(&session, DedupStrategy::NormalizedUrl);
+    let result = DedupEngine::default().dedup(&session, DedupStrategy::NormalizedUrl);
     assert_eq!(result.deduplicated_count, 1, "utm_ params should be stripped, deduping these two");
     assert_eq!(result.removed.len(), 1);
 }
 
+/// The engine compares URLs under the rules it was built with.
+#[test]
+fn test_dedup_normalized_url_follows_engine_rules() {
+    use tablitz_core::CanonicalRules;
+
+    let session = make_session(vec![make_group("g1", vec![
+        make_tab("t1", "https://example.com/post", "Post"),
+        make_tab("t2", "https://www.example.com/post", "Post"),
+    ])]);
+    let keep_www = CanonicalRules { fold_www: false, ..CanonicalRules::default() };
+    let result = DedupEngine::new(keep_www, Default::default()).dedup(&session, DedupStrategy::NormalizedUrl);
+    assert_eq!(result.deduplicated_count, 2, "www. is kept apart when the rules say so");
+}
+
+/// Titles are compared after the engine's title pipeline.
+#[test]
+fn test_dedup_url_and_title_follows_engine_titles() {
+    use tablitz_core::CanonicalRules;
+    use tablitz_search::{TitlePipeline, TitleRules};
+
+    let session = make_session(vec![make_group("g1", vec![
+        make_tab("t1", "https://example.com/post", "Rust Post"),
+        make_tab("t2", "https://example.com/post", "rust post"),
+    ])]);
+    let result = DedupEngine::default().dedup(&session, DedupStrategy::UrlAndTitle);
+    assert_eq!(result.deduplicated_count, 2);
+
+    let fold_case = TitlePipeline::new(&TitleRules { fold_case: true, ..TitleRules::default() }).unwrap();
+    let result = DedupEngine::new(CanonicalRules::default(), fold_case).dedup(&session, DedupStrategy::UrlAndTitle);
+    assert_eq!(result.replaced_by.get("t2").map(String::as_str), Some("t1"));
+}
+
 // ─── Dedup – across multiple groups ────────────────────────────────────────
 
 #[test]
@@ -234,9 +328,15 @@ fn test_dedup_across_multiple_groups() {
             make_tab("t4", "https://doc.rust-lang.org", "Rust Docs"),
         ]),
     ]);
-    let result = DedupEngine::dedup(&session, DedupStrategy::ExactUrl);
+    let result = DedupEngine::default().dedup(&session, DedupStrategy::ExactUrl);
     assert_eq!(result.original_count, 4);
     assert_eq!(result.removed.len(), 1, "one cross-group duplicate should be removed");
+
+    for strategy in [DedupStrategy::ExactUrl, DedupStrategy::NormalizedUrl, DedupStrategy::UrlAndTitle] {
+        let result = DedupEngine::default().dedup(&session, strategy);
+        let expected = if strategy == DedupStrategy::UrlAndTitle { None } else { Some("t1") };
+        assert_eq!(result.replaced_by.get("t3").map(String::as_str), expected, "{:?}", strategy);
+    }
 }
 
 // ─── Dedup – no duplicates ─────────────────────────────────────────────────
@@ -244,11 +344,340 @@ fn test_dedup_across_multiple_groups() {
 #[test]
 fn test_dedup_no_duplicates_unchanged() {
     let session = make_real_shape_session();
-    let result = DedupEngine::dedup(&session, DedupStrategy::ExactUrl);
+    let result = DedupEngine::default().dedup(&session, DedupStrategy::ExactUrl);
     assert_eq!(result.removed.len(), 0);
     assert_eq!(result.original_count, result.deduplicated_count);
 }
 
+// ─── Dedup – fuzzy URL ─────────────────────────────────────────────────────
+
+fn fuzzy() -> DedupStrategy {
+    DedupStrategy::FuzzyUrl { threshold: DedupEngine::DEFAULT_FUZZY_THRESHOLD }
+}
+
+#[test]
+fn test_dedup_fuzzy_url_keeps_groups() {
+    let session = make_session(vec![
+        make_group("g1", vec![
+            make_tab("t1", "https://docs.rs/tokio/latest/tokio/sync/index.html", "tokio::sync"),
+            make_tab("t2", "https://crates.io/crates/serde", "serde"),
+        ]),
+        make_group("g2", vec![
+            make_tab("t3", "http://www.docs.rs/tokio/latest/tokio/sync/index.htm", "tokio::sync"),
+            make_tab("t4", "https://crates.io/crates/serde_json", "serde_json"),
+        ]),
+        make_group("g3", vec![make_tab("t5", "https://docs.rs/tokio/latest/tokio/sync/", "tokio::sync")]),
+    ]);
+    let result = DedupEngine::default().dedup(&session, fuzzy());
+    let groups: Vec<(&str, Vec<&str>)> = result
+        .session
+        .groups
+        .iter()
+        .map(|g| (g.id.as_str(), g.tabs.iter().map(|t| t.id.as_str()).collect()))
+        .collect();
+    assert_eq!(groups, vec![("g1", vec!["t1", "t2"]), ("g2", vec!["t4"]), ("g3", vec!["t5"])]);
+    assert_eq!(result.replaced_by.get("t3").map(String::as_str), Some("t1"));
+    assert_eq!(result.deduplicated_count, 4);
+}
+
+#[test]
+fn test_dedup_fuzzy_url_threshold_and_blocks() {
+    let session = make_session(vec![make_group("g1", vec![
+        make_tab("t1", "https://example.com/articles/2024/rust-ownership", "a"),
+        make_tab("t2", "https://example.com/articles/2024/rust-ownershp", "b"),
+        make_tab("t3", "https://example.org/articles/2024/rust-ownership", "c"),
+        make_tab("t4", "https://example.com/posts/2024/rust-ownership", "d"),
+    ])]);
+    let result = DedupEngine::default().dedup(&session, fuzzy());
+    assert_eq!(result.removed.iter().map(|t| t.id.as_str()).collect::<Vec<_>>(), vec!["t2"]);
+
+    let exact = DedupEngine::default().dedup(&session, DedupStrategy::FuzzyUrl { threshold: 1.0 });
+    assert!(exact.removed.is_empty());
+    let loose = DedupEngine::default().dedup(&session, DedupStrategy::FuzzyUrl { threshold: 0.5 });
+    assert_eq!(loose.removed.len(), 1, "other hosts and first segments are never compared");
+}
+
+/// `len` pseudo-random characters from `alphabet`, derived from `seed`.
+fn pseudo_random(seed: u64, len: usize, alphabet: &[u8]) -> String {
+    let mut x = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+    (0..len)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            alphabet[(x % alphabet.len() as u64) as usize] as char
+        })
+        .collect()
+}
+
+#[test]
+fn test_dedup_fuzzy_url_at_scale() {
+    use std::time::Instant;
+    const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+    // 200 groups × 100 tabs = 20 000 tabs: half YouTube videos, all in one
+    // block, and half links to 2 500 GitHub repos, each opened four times
+    let groups: Vec<TabGroup> = (0..200u64).map(|gi| {
+        let tabs = (0..100u64).map(|ti| {
+            let n = gi * 100 + ti;
+            let url = if ti % 2 == 0 {
+                format!("https://www.youtube.com/watch?v={}", pseudo_random(n, 11, BASE64))
+            } else {
+                let repo = n % 5000;
+                format!("https://github.com/owner-{}/{}", repo % 50, pseudo_random(repo, 10, b"abcdefghijklmnopqrstuvwxyz"))
+            };
+            make_tab(&format!("t-{}", n), &url, "tab")
+        }).collect();
+        make_group(&format!("g-{}", gi), tabs)
+    }).collect();
+    let session = make_session(groups);
+
+    let t0 = Instant::now();
+    let result = DedupEngine::default().dedup(&session, fuzzy());
+    let elapsed = t0.elapsed();
+    eprintln!("fuzzy dedup: {} → {} tabs in {:?}", result.original_count, result.deduplicated_count, elapsed);
+    assert_eq!(result.session.groups.len(), 200);
+    assert_eq!(result.deduplicated_count, 12_500);
+    // Should complete in under 5 seconds even on slow CI
+    assert!(elapsed.as_secs() < 5, "fuzzy dedup too slow: {:?}", elapsed);
+}
+
+// ─── Dedup – near duplicates ───────────────────────────────────────────────
+
+#[test]
+fn test_rewrite_url_rules() {
+    use tablitz_core::CanonicalRules;
+
+    let rules = CanonicalRules::default();
+    let rewrite_url = |url: &str| tablitz_search::rewrite_url(url, &rules);
+    let same = [
+        ("https://youtu.be/dQw4w9WgXcQ?t=42", "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42"),
+        ("https://m.youtube.com/watch?v=dQw4w9WgXcQ", "https://www.youtube.com/shorts/dQw4w9WgXcQ"),
+        ("https://arxiv.org/abs/1706.03762v5", "https://arxiv.org/pdf/1706.03762.pdf"),
+        ("https://en.m.wikipedia.org/wiki/Rust", "https://en.wikipedia.org/wiki/Rust#History"),
+        ("https://www.example.com/news/story/amp", "http://example.com/news/story/"),
+        ("https://example.com/amp/news/story", "https://example.com/news/story?amp=1"),
+        ("https://example-com.cdn.ampproject.org/c/s/example.com/news/story", "https://example.com/news/story"),
+        ("https://old.reddit.com/r/rust/comments/abc/", "https://www.reddit.com/r/rust/comments/abc"),
+        ("https://mobile.twitter.com/rustlang/status/1", "https://x.com/rustlang/status/1"),
+    ];
+    for (a, b) in same {
+        assert_eq!(rewrite_url(a), rewrite_url(b), "{} vs {}", a, b);
+    }
+    assert_eq!(rewrite_url("https://youtu.be/dQw4w9WgXcQ"), "youtube:dQw4w9WgXcQ");
+    assert_eq!(rewrite_url("https://arxiv.org/abs/1706.03762v5"), "arxiv:1706.03762");
+
+    let different = [
+        ("https://www.youtube.com/watch?v=aaaaaaaaaaa", "https://www.youtube.com/watch?v=bbbbbbbbbbb"),
+        ("https://arxiv.org/abs/1706.03762", "https://arxiv.org/abs/1706.03763"),
+        ("https://example.com/page", "https://example.org/page"),
+    ];
+    for (a, b) in different {
+        assert_ne!(rewrite_url(a), rewrite_url(b), "{} vs {}", a, b);
+    }
+}
+
+fn near_duplicate_session() -> TabSession {
+    make_session(vec![
+        make_group("g1", vec![
+            make_tab("t1", "https://arxiv.org/abs/1706.03762", "[1706.03762] Attention Is All You Need"),
+            make_tab("t2", "https://www.youtube.com/watch?v=dQw4w9WgXcQ", "Never Gonna Give You Up - YouTube"),
+            make_tab("t3", "https://github.com/", "GitHub"),
+        ]),
+        make_group("g2", vec![
+            make_tab("t4", "https://arxiv.org/pdf/1706.03762v7", "1706.03762v7.pdf"),
+            make_tab("t5", "https://papers.example.org/attention", "Attention Is All You Need [1706.03762]"),
+            make_tab("t6", "https://gitlab.com/", "GitHub"),
+            make_tab("t7", "https://youtu.be/dQw4w9WgXcQ", "Rick Astley"),
+            make_tab("t8", "https://example.org/borrowing", "Understanding ownership and borrowing in Rust"),
+        ]),
+    ])
+}
+
+#[test]
+fn test_near_duplicates_cluster_with_scores() {
+    use tablitz_search::{DuplicateCluster, MatchReason};
+
+    let session = near_duplicate_session();
+    let clusters = DedupEngine::default().near_duplicates(&session, DedupEngine::DEFAULT_NEAR_DUPLICATE_THRESHOLD);
+    let summary: Vec<(&str, Vec<(&str, MatchReason)>)> = clusters
+        .iter()
+        .map(|c| (c.keep.as_str(), c.duplicates.iter().map(|d| (d.tab_id.as_str(), d.reason)).collect()))
+        .collect();
+    assert_eq!(summary, vec![
+        ("t1", vec![("t4", MatchReason::SameUrl), ("t5", MatchReason::SimilarTitle)]),
+        ("t2", vec![("t7", MatchReason::SameUrl)]),
+    ], "short, generic titles like 'GitHub' are never compared");
+
+    let DuplicateCluster { duplicates, .. } = &clusters[0];
+    assert_eq!(duplicates[0].score, 1.0);
+    assert!(duplicates[1].score >= 0.8 && duplicates[1].score < 1.0, "{}", duplicates[1].score);
+
+    let strict = DedupEngine::default().near_duplicates(&session, 1.0);
+    assert!(strict.iter().all(|c| c.duplicates.iter().all(|d| d.reason == MatchReason::SameUrl)));
+
+    // The mirror is found through the abstract's title even when it comes first
+    let mut reordered = session.clone();
+    reordered.groups.reverse();
+    let clusters = DedupEngine::default().near_duplicates(&reordered, DedupEngine::DEFAULT_NEAR_DUPLICATE_THRESHOLD);
+    let arxiv = clusters.iter().find(|c| c.keep == "t4").unwrap();
+    let mut ids: Vec<&str> = arxiv.duplicates.iter().map(|d| d.tab_id.as_str()).collect();
+    ids.sort();
+    assert_eq!(ids, vec!["t1", "t5"]);
+}
+
+#[test]
+fn test_near_duplicates_at_scale() {
+    use std::time::Instant;
+    // 100 groups × 100 tabs with distinct URLs and titles of five pseudo-random words
+    let groups: Vec<TabGroup> = (0..100u64).map(|gi| {
+        let tabs = (0..100u64).map(|ti| {
+            let n = gi * 100 + ti;
+            let title: Vec<String> = (0..5).map(|w| pseudo_random(n * 5 + w, 6, b"abcdefghijklmnopqrstuvwxyz")).collect();
+            make_tab(&format!("t-{}", n), &format!("https://example.com/post/{}", n), &title.join(" "))
+        }).collect();
+        make_group(&format!("g-{}", gi), tabs)
+    }).collect();
+    let session = make_session(groups);
+
+    let t0 = Instant::now();
+    let clusters = DedupEngine::default().near_duplicates(&session, DedupEngine::DEFAULT_NEAR_DUPLICATE_THRESHOLD);
+    let elapsed = t0.elapsed();
+    eprintln!("near duplicates: {} clusters in {:?}", clusters.len(), elapsed);
+    assert!(clusters.is_empty());
+    assert!(elapsed.as_secs() < 5, "near-duplicate detection too slow: {:?}", elapsed);
+}
+
+#[test]
+fn test_merge_clusters_keeps_groups() {
+    let session = near_duplicate_session();
+    let clusters = DedupEngine::default().near_duplicates(&session, DedupEngine::DEFAULT_NEAR_DUPLICATE_THRESHOLD);
+
+    // Accept only the video cluster, as `dedup --review` might
+    let result = DedupEngine::merge_clusters(&session, &clusters[1..]);
+    assert_eq!(result.removed.iter().map(|t| t.id.as_str()).collect::<Vec<_>>(), vec!["t7"]);
+    assert_eq!(result.replaced_by.get("t7").map(String::as_str), Some("t2"));
+    assert_eq!(result.session.groups.len(), 2);
+    assert_eq!(result.session.groups[1].tabs.len(), 4);
+
+    let all = DedupEngine::default().dedup(&session, DedupStrategy::NearDuplicate { threshold: 0.8 });
+    assert_eq!(all.deduplicated_count, 5);
+    assert_eq!(all.session.groups[0].tabs.len(), 3);
+}
+
+// ─── Query language ────────────────────────────────────────────────────────
+
+fn matching_ids(query: &str, session: &TabSession) -> Vec<String> {
+    let query = Query::parse(query).unwrap();
+    query.filter_session(session).groups.iter()
+        .flat_map(|g| g.tabs.iter().map(|t| t.id.clone()))
+        .collect()
+}
+
+#[test]
+fn test_query_parses_fields_and_phrases() {
+    let query = Query::parse(r#"domain:GitHub.com starred:true after:2024-01-01 tag:Rust "async runtime""#).unwrap();
+    let Query::And(items) = query else { panic!("expected a conjunction") };
+    assert_eq!(items[0], Predicate::Domain("github.com".into()).into());
+    assert_eq!(items[1], Predicate::Flag(Flag::Starred, true).into());
+    assert!(matches!(items[2], Query::Predicate(Predicate::After(_))));
+    assert_eq!(items[3], Predicate::Tag("rust".into()).into());
+    assert_eq!(items[4], Predicate::Text("async runtime".into()).into());
+    assert!(Query::parse("").unwrap().is_empty());
+    assert_eq!(Query::parse(r#"title:"data oriented""#).unwrap(), Predicate::Title("data oriented".into()).into());
+}
+
+#[test]
+fn test_query_parse_errors() {
+    assert!(matches!(Query::parse("colour:red"), Err(QueryError::UnknownField(_))));
+    assert!(matches!(Query::parse("starred:maybe"), Err(QueryError::InvalidValue { .. })));
+    assert!(matches!(Query::parse("after:yesterday"), Err(QueryError::InvalidValue { .. })));
+    assert!(matches!(Query::parse("domain:"), Err(QueryError::InvalidValue { .. })));
+    assert_eq!(Query::parse(r#""open"#), Err(QueryError::UnterminatedQuote));
+    assert_eq!(Query::parse("(rust"), Err(QueryError::UnbalancedParens));
+    assert_eq!(Query::parse("rust)"), Err(QueryError::UnbalancedParens));
+    assert_eq!(Query::parse("rust OR"), Err(QueryError::MissingOperand("OR")));
+    assert_eq!(Query::parse("NOT"), Err(QueryError::MissingOperand("NOT")));
+}
+
+#[test]
+fn test_query_urls_stay_plain_text() {
+    assert_eq!(
+        Query::parse("https://crates.io").unwrap(),
+        Predicate::Text("https://crates.io".into()).into()
+    );
+}
+
+#[test]
+fn test_query_matches_in_memory() {
+    let session = make_real_shape_session();
+    assert_eq!(matching_ids("domain:youtube.com", &session), vec!["t4", "t5"]);
+    assert_eq!(matching_ids("domain:daily.dev", &session), vec!["t8"]);
+    assert_eq!(matching_ids("domain:ube.com", &session), Vec::<String>::new());
+    assert_eq!(matching_ids("rust -domain:daily.dev", &session), vec!["t1", "t2"]);
+    assert_eq!(matching_ids("(domain:arxiv.org OR domain:crates.io) NOT title:grad", &session), vec!["t2", "t6"]);
+    assert_eq!(matching_ids("url:tokio-rs", &session), vec!["t3"]);
+    assert_eq!(matching_ids("\"model scaling\"", &session), vec!["t6"]);
+}
+
+#[test]
+fn test_query_matches_group_fields_tags_and_dates() {
+    let mut session = make_real_shape_session();
+    session.groups[0].starred = true;
+    session.groups[1].label = Some("Talks".into());
+    session.groups[2].tags = vec!["papers".into()];
+    session.groups[0].tabs[2].tags = vec!["async".into()];
+    session.groups[0].tabs[0].added_at = "2023-06-01T00:00:00Z".parse().unwrap();
+
+    assert_eq!(matching_ids("starred:true domain:crates.io", &session), vec!["t2"]);
+    assert_eq!(matching_ids("label:talks", &session), vec!["t4", "t5"]);
+    assert_eq!(matching_ids("tag:papers domain:arxiv.org", &session), vec!["t6", "t7"]);
+    assert_eq!(matching_ids("tag:async", &session), vec!["t3"]);
+    assert_eq!(matching_ids("before:2024-01-01", &session), vec!["t1"]);
+    assert_eq!(matching_ids("starred:true after:2024-01-01", &session), vec!["t2", "t3"]);
+}
+
+#[test]
+fn test_query_matches_history_activity() {
+    let mut session = make_real_shape_session();
+    let tabs = &mut session.groups[0].tabs;
+    tabs[0].activity.bookmarked = true;
+    tabs[0].activity.visit_count = 12;
+    tabs[0].activity.last_visited_at = Some("2024-03-01T00:00:00Z".parse().unwrap());
+    tabs[1].activity.visit_count = 3;
+    tabs[1].activity.last_visited_at = Some("2022-01-01T00:00:00Z".parse().unwrap());
+
+    assert_eq!(matching_ids("bookmarked:true", &session), vec!["t1"]);
+    assert_eq!(matching_ids("visits:3", &session), vec!["t1", "t2"]);
+    assert_eq!(matching_ids("visits:4", &session), vec!["t1"]);
+    assert_eq!(matching_ids("visited-after:2023-01-01", &session), vec!["t1"]);
+    assert_eq!(matching_ids("visited-before:2023-01-01", &session), vec!["t2"]);
+    assert_eq!(matching_ids("bookmarked:false visits:1", &session), vec!["t2"]);
+    assert!(matches!(Query::parse("visits:many"), Err(QueryError::InvalidValue { .. })));
+}
+
+#[test]
+fn test_query_split_text() {
+    let (text, rest) = Query::parse("rust domain:github.com \"async runtime\" -tokio").unwrap().split_text();
+    assert_eq!(text, vec!["rust", "async runtime"]);
+    assert_eq!(rest, Query::And(vec![
+        Predicate::Domain("github.com".into()).into(),
+        Query::Not(Box::new(Predicate::Text("tokio".into()).into())),
+    ]));
+
+    let (text, rest) = Query::parse("rust").unwrap().split_text();
+    assert_eq!(text, vec!["rust"]);
+    assert!(rest.is_empty());
+}
+
+#[test]
+fn test_query_to_sql_numbers_placeholders() {
+    let sql = Query::parse("rust tag:web starred:false").unwrap().to_sql(3);
+    assert!(sql.clause.contains("?3") && sql.clause.contains("?4") && !sql.clause.contains("?5"));
+    assert!(sql.clause.contains("g.starred = 0"));
+    assert_eq!(sql.params.len(), 2);
+    assert_eq!(Query::default().to_sql(1).clause, "1");
+}
+
 // ─── Live test (skipped unless env var set) ─────────────────────────────────
 
 /// Fuzz the fuzzy searcher with a large synthetic session simulating real scale
@@ -12,6 +12,8 @@ fn make_tab(id: &str, url: &str, title: &str) -> Tab {
         title: title.to_string(),
         favicon_url: None,
         added_at: Utc::now(),
+        tags: Vec::new(),
+        activity: Default::default(),
     }
 }
 
@@ -23,6 +25,7 @@ fn make_group(id: &str, label: Option<&str>, tabs: Vec<Tab>) -> TabGroup {
         pinned: false,
         locked: false,
         starred: false,
+        tags: Vec::new(),
         tabs,
     }
 }
@@ -112,6 +115,833 @@ async fn test_replace_tabs_for_group() {
     assert_eq!(updated[0].tabs.len(), 1);
 }
 
+#[tokio::test]
+async fn test_set_tab_titles() {
+    let (store, _dir) = open_store().await;
+    store.insert_session(&make_test_session()).await.unwrap();
+
+    let titles = [("test-tab-1", "Rust"), ("missing", "Nothing")]
+        .into_iter()
+        .map(|(id, title)| (id.to_string(), title.to_string()))
+        .collect();
+    assert_eq!(store.set_tab_titles(&titles).await.unwrap(), 1);
+    assert_eq!(store.search_by_title("Programming").await.unwrap().len(), 0);
+    assert_eq!(store.get_all_groups().await.unwrap()[0].tabs[0].title, "Rust");
+}
+
+// ─── Schema migrations ─────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_fresh_store_is_fully_migrated() {
+    let (store, _dir) = open_store().await;
+    assert_eq!(store.schema_version().await.unwrap(), tablitz_store::LATEST_SCHEMA_VERSION);
+    assert!(store.pending_migrations().await.unwrap().is_empty());
+    assert!(store.migrate().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_open_unmigrated_reports_pending() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = Store::open_unmigrated(&dir.path().join("test.db")).await.unwrap();
+
+    let status = store.migration_status().await.unwrap();
+    assert_eq!(status.len(), tablitz_store::MIGRATIONS.len());
+    assert!(status.iter().all(|m| m.applied_at.is_none()));
+    assert!(status.windows(2).all(|w| w[0].version < w[1].version));
+
+    let pending: Vec<_> = store.pending_migrations().await.unwrap()
+        .iter().map(|m| m.version).collect();
+    let applied: Vec<_> = store.migrate().await.unwrap()
+        .iter().map(|m| m.version).collect();
+    assert_eq!(pending, applied);
+    store.insert_session(&make_test_session()).await.unwrap();
+}
+
+// ─── Tags ──────────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_tags_round_trip_through_insert_session() {
+    let (store, _dir) = open_store().await;
+    let mut session = make_test_session();
+    session.groups[0].tags = vec!["Reading List".to_string()];
+    session.groups[0].tabs[0].tags = vec!["rust".to_string(), " Lang ".to_string()];
+    store.insert_session(&session).await.unwrap();
+
+    let group = &store.get_all_groups().await.unwrap()[0];
+    assert_eq!(group.tags, vec!["reading-list"]);
+    assert_eq!(group.tabs[0].tags, vec!["lang", "rust"]);
+    assert!(group.tabs[1].tags.is_empty());
+    assert_eq!(store.search_by_title("Rust").await.unwrap()[0].tags, vec!["lang", "rust"]);
+}
+
+#[tokio::test]
+async fn test_add_remove_and_list_tags() {
+    use tablitz_store::{TagCount, TagTarget};
+
+    let (store, _dir) = open_store().await;
+    store.insert_session(&make_test_session()).await.unwrap();
+
+    let tab = store.resolve_tag_target(TagTarget::Tab("test-tab-2".into())).await.unwrap();
+    let group = store.resolve_tag_target(TagTarget::Group("test-gr".into())).await.unwrap();
+    assert_eq!(group, TagTarget::Group("test-group-1".into()));
+    assert!(store.resolve_tag_target(TagTarget::Tab("test-tab".into())).await.is_err());
+    assert!(store.resolve_tag_target(TagTarget::Tab("missing".into())).await.is_err());
+
+    let tags = vec!["rust".to_string(), "tools".to_string()];
+    assert_eq!(store.add_tags(&tab, &tags).await.unwrap(), 2);
+    assert_eq!(store.add_tags(&tab, &tags).await.unwrap(), 0);
+    assert_eq!(store.add_tags(&group, &["rust".to_string()]).await.unwrap(), 1);
+    let err = store.add_tags(&TagTarget::Tab("missing".into()), &tags).await.unwrap_err();
+    assert_eq!(err.to_string(), "tab missing does not exist");
+
+    assert_eq!(store.list_tags().await.unwrap(), vec![
+        TagCount { tag: "rust".into(), tabs: 1, groups: 1 },
+        TagCount { tag: "tools".into(), tabs: 1, groups: 0 },
+    ]);
+
+    assert_eq!(store.remove_tags(&tab, &["TOOLS".to_string()]).await.unwrap(), 1);
+    assert_eq!(store.get_tabs_for_group("test-group-1").await.unwrap()[1].tags, vec!["rust"]);
+}
+
+#[tokio::test]
+async fn test_tags_follow_replace_and_delete() {
+    use tablitz_store::TagTarget;
+
+    let (store, _dir) = open_store().await;
+    store.insert_session(&make_test_session()).await.unwrap();
+    store.add_tags(&TagTarget::Tab("test-tab-1".into()), &["keep".to_string()]).await.unwrap();
+    store.add_tags(&TagTarget::Tab("test-tab-2".into()), &["drop".to_string()]).await.unwrap();
+
+    let mut group = store.get_all_groups().await.unwrap()[0].clone();
+    group.tabs.retain(|t| t.id == "test-tab-1");
+    store.replace_tabs_for_group(&group).await.unwrap();
+    let tags: Vec<_> = store.list_tags().await.unwrap().into_iter().map(|t| t.tag).collect();
+    assert_eq!(tags, vec!["keep"]);
+
+    store.delete_group("test-group-1").await.unwrap();
+    assert!(store.list_tags().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_search_fts_tagged() {
+    use tablitz_store::TagTarget;
+
+    let (store, _dir) = open_store().await;
+    store.insert_session(&TabSession {
+        groups: vec![
+            make_group("tagged-group", None, vec![
+                make_tab("tg-1", "https://example.com/a", "Rust async book"),
+            ]),
+            make_group("plain-group", None, vec![
+                make_tab("pg-1", "https://example.com/b", "Rust embedded book"),
+                make_tab("pg-2", "https://example.com/c", "Rust web book"),
+            ]),
+        ],
+        ..make_test_session()
+    }).await.unwrap();
+    store.add_tags(&TagTarget::Group("tagged-group".into()), &["study".to_string()]).await.unwrap();
+    store.add_tags(&TagTarget::Tab("pg-2".into()), &["study".to_string()]).await.unwrap();
+
+    assert_eq!(store.search_fts("book", 10).await.unwrap().len(), 3);
+    let mut ids: Vec<_> = store.search_fts_tagged("book", "Study", 10).await.unwrap()
+        .into_iter().map(|h| h.tab.id).collect();
+    ids.sort();
+    assert_eq!(ids, vec!["pg-2", "tg-1"]);
+}
+
+// ─── Query language ────────────────────────────────────────────────────────
+
+fn tab_ids(session: &TabSession) -> Vec<String> {
+    let mut ids: Vec<_> = session.groups.iter()
+        .flat_map(|g| g.tabs.iter().map(|t| t.id.clone()))
+        .collect();
+    ids.sort();
+    ids
+}
+
+async fn open_query_store() -> (Store, tempfile::TempDir) {
+    use tablitz_store::TagTarget;
+
+    let (store, dir) = open_store().await;
+    let mut old = make_tab("q-old", "https://user@docs.github.com:8443/en/actions", "GitHub Actions docs");
+    old.added_at = "2023-03-01T12:00:00Z".parse().unwrap();
+    let mut starred = make_group("q-starred", Some("Rust Reading"), vec![
+        make_tab("q-book", "https://doc.rust-lang.org/book/", "The Rust Book"),
+        make_tab("q-tokio", "https://github.com/tokio-rs/tokio", "Tokio: async runtime"),
+        old,
+    ]);
+    starred.starred = true;
+    let plain = make_group("q-plain", None, vec![
+        make_tab("q-video", "https://www.youtube.com/watch?v=1", "Async Rust talk"),
+        make_tab("q-fake", "https://notgithub.com/github.com", "Not GitHub"),
+        make_tab("q-blank", "about:blank", "Blank"),
+    ]);
+    store.insert_session(&TabSession { groups: vec![starred, plain], ..make_test_session() }).await.unwrap();
+    store.add_tags(&TagTarget::Tab("q-video".into()), &["talks".to_string()]).await.unwrap();
+    store.add_tags(&TagTarget::Group("q-starred".into()), &["rust".to_string()]).await.unwrap();
+    (store, dir)
+}
+
+#[tokio::test]
+async fn test_query_sql_matches_in_memory() {
+    use tablitz_search::Query;
+
+    let (store, _dir) = open_query_store().await;
+    let session = store.get_session().await.unwrap();
+    let cases: &[(&str, &[&str])] = &[
+        ("domain:github.com", &["q-old", "q-tokio"]),
+        ("domain:docs.github.com", &["q-old"]),
+        ("domain:youtube.com", &["q-video"]),
+        ("starred:true", &["q-book", "q-old", "q-tokio"]),
+        ("starred:false -domain:notgithub.com", &["q-blank", "q-video"]),
+        ("tag:rust OR tag:talks", &["q-book", "q-old", "q-tokio", "q-video"]),
+        ("async", &["q-tokio", "q-video"]),
+        ("\"rust book\"", &["q-book"]),
+        ("rust", &["q-book", "q-old", "q-tokio", "q-video"]),
+        ("label:reading url:github", &["q-old", "q-tokio"]),
+        ("before:2024-01-01", &["q-old"]),
+        ("after:2024-01-01 title:github", &["q-fake"]),
+        ("NOT (rust OR github)", &["q-blank"]),
+    ];
+    for (text, expected) in cases {
+        let query = Query::parse(text).unwrap();
+        let from_sql = store.get_session_matching(&query).await.unwrap();
+        assert_eq!(tab_ids(&from_sql), *expected, "SQL result for {}", text);
+        assert_eq!(tab_ids(&query.filter_session(&session)), *expected, "in-memory result for {}", text);
+    }
+}
+
+#[tokio::test]
+async fn test_get_groups_matching_keeps_group_fields() {
+    use tablitz_search::Query;
+
+    let (store, _dir) = open_query_store().await;
+    let groups = store.get_groups_matching(&Query::parse("domain:github.com").unwrap()).await.unwrap();
+    assert_eq!(groups.len(), 1);
+    let starred = &groups[0];
+    assert!(starred.starred);
+    assert_eq!(starred.tags, vec!["rust"]);
+    assert_eq!(starred.tabs.len(), 2);
+
+    assert_eq!(store.get_groups_matching(&Query::default()).await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_search_fts_filtered() {
+    use tablitz_search::Query;
+
+    let (store, _dir) = open_query_store().await;
+    let hits = store.search_fts_filtered("async", &Query::parse("starred:true").unwrap(), 10).await.unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].tab.id, "q-tokio");
+}
+
+// ─── History enrichment ────────────────────────────────────────────────────
+
+fn url_activity(url: &str, visits: u64, last_visit: Option<&str>, bookmarked: bool) -> tablitz_core::UrlActivity {
+    tablitz_core::UrlActivity {
+        url: url.to_string(),
+        activity: tablitz_core::TabActivity {
+            last_visited_at: last_visit.map(|at| at.parse().unwrap()),
+            visit_count: visits,
+            bookmarked,
+        },
+    }
+}
+
+#[tokio::test]
+async fn test_enrich_tabs_from_history_and_bookmarks() {
+    use tablitz_search::Query;
+
+    let (store, _dir) = open_query_store().await;
+    let history = [
+        // Normalized URLs match despite the missing slash, fragment, case and tracking params
+        url_activity("https://doc.rust-lang.org/book", 5, Some("2024-03-01T00:00:00Z"), false),
+        url_activity("https://DOC.rust-lang.org/book/#ch01", 2, Some("2024-02-01T00:00:00Z"), true),
+        url_activity("https://github.com/tokio-rs/tokio?utm_source=hn", 1, Some("2022-05-01T00:00:00Z"), false),
+        url_activity("https://unrelated.example/", 40, None, true),
+    ];
+    let matched = store.match_activity(&history).await.unwrap();
+    let mut ids: Vec<_> = matched.keys().cloned().collect();
+    ids.sort();
+    assert_eq!(ids, vec!["q-book", "q-tokio"]);
+    assert_eq!(matched["q-book"].visit_count, 7);
+    assert!(matched["q-book"].bookmarked);
+    assert_eq!(matched["q-book"].last_visited_at, Some("2024-03-01T00:00:00Z".parse().unwrap()));
+
+    assert_eq!(store.set_tab_activity(&matched).await.unwrap(), 2);
+    let session = store.get_session().await.unwrap();
+    let cases: &[(&str, &[&str])] = &[
+        ("bookmarked:true", &["q-book"]),
+        ("visits:1", &["q-book", "q-tokio"]),
+        ("visits:2", &["q-book"]),
+        ("visited-after:2024-01-01", &["q-book"]),
+        ("visited-before:2024-01-01", &["q-tokio"]),
+        ("bookmarked:false starred:true", &["q-old", "q-tokio"]),
+    ];
+    for (text, expected) in cases {
+        let query = Query::parse(text).unwrap();
+        let from_sql = store.get_session_matching(&query).await.unwrap();
+        assert_eq!(tab_ids(&from_sql), *expected, "SQL result for {}", text);
+        assert_eq!(tab_ids(&query.filter_session(&session)), *expected, "in-memory result for {}", text);
+    }
+}
+
+#[tokio::test]
+async fn test_tab_activity_round_trips_through_insert() {
+    let (store, _dir) = open_store().await;
+    let mut tab = make_tab("a1", "https://example.com/", "Example");
+    tab.activity.visit_count = 3;
+    tab.activity.bookmarked = true;
+    tab.activity.last_visited_at = Some("2024-03-01T12:30:00Z".parse().unwrap());
+    let session = TabSession { groups: vec![make_group("ga", None, vec![tab.clone()])], ..make_test_session() };
+    store.insert_session(&session).await.unwrap();
+
+    let stored = store.get_tabs_for_group("ga").await.unwrap();
+    assert_eq!(stored[0].activity, tab.activity);
+}
+
+// ─── Native import: merge and replace ──────────────────────────────────────
+
+/// `session` as if exported and edited: its group relabelled, starred and
+/// given another tab, plus a new group.
+fn edit_session(mut session: TabSession) -> TabSession {
+    let group = &mut session.groups[0];
+    group.label = Some("Renamed".to_string());
+    group.starred = true;
+    group.tabs.push(make_tab("test-tab-3", "https://example.com/clippy", "Clippy"));
+    session.groups.push(make_group("test-group-2", None, vec![make_tab("test-tab-4", "https://example.org/", "Example")]));
+    session
+}
+
+#[tokio::test]
+async fn test_import_session_merge_reports_conflicts() {
+    use tablitz_store::ImportMode;
+
+    let (store, _dir) = open_store().await;
+    let original = make_test_session();
+    store.insert_session(&original).await.unwrap();
+
+    // Importing what's already stored is not a conflict
+    let stored = TabSession { groups: store.get_all_groups().await.unwrap(), ..make_test_session() };
+    let stats = store.import_session(&stored, ImportMode::Merge).await.unwrap();
+    assert!(stats.conflicts.is_empty(), "{:?}", stats.conflicts);
+    assert_eq!(stats.groups_skipped, 1);
+
+    let stats = store.import_session(&edit_session(original), ImportMode::Merge).await.unwrap();
+    assert_eq!((stats.groups_inserted, stats.groups_replaced, stats.groups_skipped), (1, 0, 1));
+    assert_eq!(stats.conflicts.len(), 1);
+    let conflict = &stats.conflicts[0];
+    assert_eq!(conflict.group_id, "test-group-1");
+    assert_eq!(conflict.fields, vec!["label", "starred", "tabs"]);
+    assert_eq!((conflict.stored_tabs, conflict.imported_tabs), (2, 3));
+
+    let group = &store.get_all_groups().await.unwrap().into_iter().find(|g| g.id == "test-group-1").unwrap();
+    assert_eq!(group.label.as_deref(), Some("Test Group"), "merge keeps the stored copy");
+    assert_eq!(group.tabs.len(), 2);
+}
+
+#[tokio::test]
+async fn test_import_session_replace_overwrites_conflicts() {
+    use tablitz_store::{ImportMode, TagTarget};
+
+    let (store, _dir) = open_store().await;
+    let original = make_test_session();
+    store.insert_session(&original).await.unwrap();
+    store.add_tags(&TagTarget::Tab("test-tab-2".into()), &["stale".to_string()]).await.unwrap();
+
+    let mut edited = edit_session(original);
+    edited.groups[0].tabs.remove(0);
+    let stats = store.import_session(&edited, ImportMode::Replace).await.unwrap();
+    assert_eq!((stats.groups_inserted, stats.groups_replaced, stats.groups_skipped), (1, 1, 0));
+    assert_eq!(stats.conflicts.len(), 1);
+
+    let group = &store.get_all_groups().await.unwrap().into_iter().find(|g| g.id == "test-group-1").unwrap();
+    assert_eq!(group.label.as_deref(), Some("Renamed"));
+    assert!(group.starred);
+    let ids: Vec<_> = group.tabs.iter().map(|t| t.id.as_str()).collect();
+    assert_eq!(ids, vec!["test-tab-2", "test-tab-3"]);
+    assert!(group.tabs[0].tags.is_empty(), "tags come from the imported copy");
+    assert!(store.list_tags().await.unwrap().is_empty());
+    assert!(store.search_fts("rust", 10).await.unwrap().is_empty(), "removed tab leaves the index");
+
+    // Once replaced, the same import changes nothing
+    let stats = store.import_session(&edited, ImportMode::Replace).await.unwrap();
+    assert!(stats.conflicts.is_empty());
+    assert_eq!(stats.groups_replaced, 0);
+}
+
+// ─── Incremental update ────────────────────────────────────────────────────
+
+/// The test session re-recovered after the user renamed and starred its
+/// group, closed a tab, opened another, retitled one and moved it to the front.
+fn rerecovered(session: &TabSession) -> TabSession {
+    let mut later = session.clone();
+    let group = &mut later.groups[0];
+    group.label = Some("Renamed".to_string());
+    group.starred = true;
+    group.tabs.remove(0);
+    group.tabs[0].title = "Cargo Book".to_string();
+    group.tabs.push(make_tab("test-tab-3", "https://example.com/clippy", "Clippy"));
+    group.tabs.swap(0, 1);
+    later
+}
+
+#[tokio::test]
+async fn test_update_brings_changed_groups_in_step() {
+    use tablitz_store::{GroupUpdate, ImportMode, TagTarget};
+
+    let (store, _dir) = open_store().await;
+    let session = make_test_session();
+    store.insert_session(&session).await.unwrap();
+    store.add_tags(&TagTarget::Tab("test-tab-2".into()), &["keep".to_string()]).await.unwrap();
+    store.add_tags(&TagTarget::Group("test-group-1".into()), &["mine".to_string()]).await.unwrap();
+
+    let mut later = rerecovered(&session);
+    later.groups.push(make_group("test-group-2", None, vec![make_tab("test-tab-4", "https://example.org/", "Example")]));
+    let stats = store.import_session(&later, ImportMode::Update).await.unwrap();
+    assert_eq!((stats.groups_inserted, stats.groups_skipped), (1, 0));
+    assert_eq!((stats.tabs_inserted, stats.tabs_skipped), (2, 0));
+    assert!(stats.conflicts.is_empty());
+    assert_eq!(
+        stats.updates,
+        vec![GroupUpdate {
+            group_id: "test-group-1".to_string(),
+            label: Some("Renamed".to_string()),
+            fields: vec!["label", "starred"],
+            tabs_added: 1,
+            tabs_removed: 1,
+            tabs_changed: 1,
+            reordered: false,
+        }]
+    );
+
+    let group = store.get_all_groups().await.unwrap().into_iter().find(|g| g.id == "test-group-1").unwrap();
+    assert_eq!(group.label.as_deref(), Some("Renamed"));
+    assert!(group.starred);
+    assert_eq!(group.tags, vec!["mine"], "tags the store added are kept");
+    let tabs: Vec<_> = group.tabs.iter().map(|t| (t.id.as_str(), t.title.as_str())).collect();
+    assert_eq!(tabs, vec![("test-tab-3", "Clippy"), ("test-tab-2", "Cargo Book")]);
+    assert_eq!(group.tabs[1].tags, vec!["keep"]);
+    assert_eq!(store.search_fts("clippy", 10).await.unwrap().len(), 1);
+    assert!(store.search_fts("rust", 10).await.unwrap().is_empty(), "removed tab leaves FTS");
+
+    // Up to date now: nothing changes
+    let stats = store.import_session(&later, ImportMode::Update).await.unwrap();
+    assert!(stats.updates.is_empty());
+    assert_eq!((stats.groups_skipped, stats.tabs_skipped), (2, 3));
+}
+
+#[tokio::test]
+async fn test_update_reorders_and_matches_other_sources() {
+    use tablitz_store::ImportMode;
+
+    let (store, _dir) = open_store().await;
+    let session = make_test_session();
+    store.insert_session(&session).await.unwrap();
+
+    // Another source's copy: other tab ids, reversed
+    let mut other = session.clone();
+    other.groups[0].tabs.reverse();
+    for tab in &mut other.groups[0].tabs {
+        tab.id = format!("other-{}", tab.id);
+    }
+    let stats = store.import_session(&other, ImportMode::Update).await.unwrap();
+    assert_eq!(stats.updates.len(), 1);
+    let update = &stats.updates[0];
+    assert!(update.reordered);
+    assert_eq!((update.tabs_added, update.tabs_removed, update.tabs_changed), (0, 0, 0));
+
+    let ids: Vec<_> = store.get_tabs_for_group("test-group-1").await.unwrap().into_iter().map(|t| t.id).collect();
+    assert_eq!(ids, vec!["test-tab-2", "test-tab-1"], "stored ids are kept");
+}
+
+// ─── Identity across sources ───────────────────────────────────────────────
+
+/// The test session's group as another source would give it: other IDs,
+/// a tracking parameter and a fragment on the URLs.
+fn from_other_source(session: &TabSession) -> TabSession {
+    let mut other = session.clone();
+    let group = &mut other.groups[0];
+    group.id = "markdown-1234".to_string();
+    for (i, tab) in group.tabs.iter_mut().enumerate() {
+        tab.id = format!("markdown-1234-t{}", i);
+        tab.url.set_query(Some("utm_source=onetab"));
+        tab.url.set_fragment(Some("top"));
+    }
+    other
+}
+
+#[tokio::test]
+async fn test_same_group_from_another_source_is_recognised() {
+    use tablitz_store::TagTarget;
+
+    let (store, _dir) = open_store().await;
+    let session = make_test_session();
+    store.insert_session(&session).await.unwrap();
+
+    let stats = store.insert_session(&from_other_source(&session)).await.unwrap();
+    assert_eq!((stats.groups_inserted, stats.groups_skipped, stats.groups_recognised), (0, 1, 1));
+    assert_eq!((stats.tabs_inserted, stats.tabs_skipped), (0, 2));
+    assert_eq!(store.get_stats().await.unwrap().total_tabs, 2);
+
+    // The other source's IDs are aliases of the stored ones
+    let group = store.resolve_tag_target(TagTarget::Group("markdown-1234".into())).await.unwrap();
+    assert_eq!(group, TagTarget::Group("test-group-1".into()));
+    let tab = store.resolve_tag_target(TagTarget::Tab("markdown-1234-t1".into())).await.unwrap();
+    assert_eq!(tab, TagTarget::Tab("test-tab-2".into()));
+
+    // Recognised by alias even once the group has changed
+    let mut changed = from_other_source(&session);
+    changed.groups[0].tabs.pop();
+    let stats = store.insert_session(&changed).await.unwrap();
+    assert_eq!((stats.groups_inserted, stats.groups_recognised), (0, 1));
+
+    // Aliases go with the group
+    store.delete_group("test-group-1").await.unwrap();
+    assert!(store.resolve_tag_target(TagTarget::Group("markdown-1234".into())).await.is_err());
+}
+
+#[tokio::test]
+async fn test_different_groups_are_not_recognised() {
+    let (store, _dir) = open_store().await;
+    let session = make_test_session();
+    store.insert_session(&session).await.unwrap();
+
+    // Same tabs, created at another time
+    let mut later = from_other_source(&session);
+    later.groups[0].created_at += chrono::Duration::seconds(5);
+    // Created in the same second, with another tab
+    let mut grown = from_other_source(&session);
+    grown.groups[0].id = "markdown-5678".to_string();
+    grown.groups[0].tabs.push(make_tab("markdown-5678-t2", "https://example.com/clippy", "Clippy"));
+    for tab in &mut grown.groups[0].tabs {
+        tab.id = tab.id.replace("1234", "5678");
+    }
+
+    let stats = store.insert_session(&TabSession { groups: vec![later.groups[0].clone(), grown.groups[0].clone()], ..session }).await.unwrap();
+    assert_eq!((stats.groups_inserted, stats.groups_recognised), (2, 0));
+}
+
+#[tokio::test]
+async fn test_canonical_ids_follow_rule_changes() {
+    use tablitz_core::{CanonicalRules, DomainRule};
+
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let session = TabSession {
+        groups: vec![make_group("rules-group", None, vec![make_tab("rules-tab", "https://rules.test/page?session=1", "Page")])],
+        ..make_test_session()
+    };
+    let mut other = session.clone();
+    other.groups[0].id = "markdown-rules".to_string();
+    other.groups[0].tabs[0].id = "markdown-rules-t0".to_string();
+    other.groups[0].tabs[0].url.set_query(Some("session=2"));
+
+    Store::open(&db_path).await.unwrap().insert_session(&session).await.unwrap();
+
+    let rules = CanonicalRules {
+        domain: vec![DomainRule {
+            domain: "rules.test".to_string(),
+            keep_fragment: false,
+            keep_query: Some(Vec::new()),
+            tracking_params: Vec::new(),
+        }],
+        ..CanonicalRules::default()
+    };
+
+    // Refreshing recomputes the stored canonical IDs under the new rules
+    let mut store = Store::open(&db_path).await.unwrap();
+    store.set_canonical_rules(rules);
+    store.refresh_canonical_ids().await.unwrap();
+    let stats = store.insert_session(&other).await.unwrap();
+    assert_eq!((stats.groups_inserted, stats.groups_recognised), (0, 1));
+    assert_eq!(store.get_stats().await.unwrap().total_tabs, 1);
+}
+
+// ─── Reversible dedup ──────────────────────────────────────────────────────
+
+/// Two groups sharing duplicates, within and across groups, with tags on
+/// the duplicates.
+async fn open_dedup_store() -> (Store, tempfile::TempDir, TabSession) {
+    use tablitz_store::TagTarget;
+
+    let (store, dir) = open_store().await;
+    let mut session = make_test_session();
+    session.groups[0].tabs.push(make_tab("test-tab-3", "https://example.com/rust", "Rust again"));
+    let mut older = make_group("test-group-2", None, vec![
+        make_tab("test-tab-4", "https://example.org/", "Example"),
+        make_tab("test-tab-5", "https://example.com/cargo", "Cargo again"),
+    ]);
+    older.created_at -= chrono::Duration::hours(1);
+    session.groups.push(older);
+    store.insert_session(&session).await.unwrap();
+    store.add_tags(&TagTarget::Tab("test-tab-5".into()), &["later".to_string()]).await.unwrap();
+    let session = store.get_session().await.unwrap();
+    (store, dir, session)
+}
+
+#[tokio::test]
+async fn test_dedup_run_logs_merges_and_undoes_exactly() {
+    use tablitz_search::{DedupEngine, DedupStrategy};
+    use tablitz_store::{TabMerge, TagTarget};
+
+    let (store, _dir, before) = open_dedup_store().await;
+    let result = DedupEngine::default().dedup(&before, DedupStrategy::ExactUrl);
+    let run = store.apply_dedup("exact-url", &result).await.unwrap().unwrap();
+    assert_eq!((run.groups_changed, run.tabs_removed), (2, 2));
+    assert_eq!(store.get_stats().await.unwrap().total_tabs, 3);
+
+    let merges = store.dedup_merges(run.id).await.unwrap();
+    let merged: Vec<_> = merges.iter().map(|m| (m.tab_id.as_str(), m.position, m.merged_into.as_str())).collect();
+    assert_eq!(merged, vec![("test-tab-3", 2, "test-tab-1"), ("test-tab-5", 1, "test-tab-2")]);
+    assert!(matches!(&merges[1], TabMerge { title, group_id, .. } if title == "Cargo again" && group_id == "test-group-2"));
+
+    // A removed tab's id now leads to the tab it was merged into
+    let target = store.resolve_tag_target(TagTarget::Tab("test-tab-5".into())).await.unwrap();
+    assert_eq!(target, TagTarget::Tab("test-tab-2".into()));
+
+    let undone = store.undo_dedup(run.id).await.unwrap();
+    assert!(undone.undone_at.is_some());
+    assert_eq!(store.get_session().await.unwrap().groups, before.groups, "positions, titles and tags come back");
+    assert_eq!(store.dedup_runs().await.unwrap(), vec![undone]);
+
+    let err = store.undo_dedup(run.id).await.unwrap_err();
+    assert!(err.to_string().contains("already undone"), "{}", err);
+    assert!(store.undo_dedup(run.id + 1).await.is_err());
+}
+
+#[tokio::test]
+async fn test_dedup_runs_undo_newest_first() {
+    use tablitz_search::{DedupEngine, DedupStrategy};
+
+    let (store, _dir, session) = open_dedup_store().await;
+    let first = store.apply_dedup("exact-url", &DedupEngine::default().dedup(&session, DedupStrategy::ExactUrl)).await.unwrap().unwrap();
+    let session = store.get_session().await.unwrap();
+    let result = DedupEngine::default().dedup(&session, DedupStrategy::ExactUrl);
+    assert!(store.apply_dedup("exact-url", &result).await.unwrap().is_none(), "nothing left to merge");
+
+    let mut session = store.get_session().await.unwrap();
+    session.groups[1].tabs.push(make_tab("test-tab-6", "https://example.org/", "Example again"));
+    store.replace_tabs_for_group(&session.groups[1]).await.unwrap();
+    let second = store.apply_dedup("exact-url", &DedupEngine::default().dedup(&session, DedupStrategy::ExactUrl)).await.unwrap().unwrap();
+
+    let err = store.undo_dedup(first.id).await.unwrap_err();
+    assert!(err.to_string().contains(&format!("run {} came after", second.id)), "{}", err);
+    store.undo_dedup(second.id).await.unwrap();
+    store.undo_dedup(first.id).await.unwrap();
+    assert_eq!(store.get_stats().await.unwrap().total_tabs, 6);
+}
+
+#[tokio::test]
+async fn test_update_does_not_bring_back_merged_tabs() {
+    use tablitz_search::{DedupEngine, DedupStrategy};
+    use tablitz_store::ImportMode;
+
+    let (store, _dir, session) = open_dedup_store().await;
+    store.apply_dedup("exact-url", &DedupEngine::default().dedup(&session, DedupStrategy::ExactUrl)).await.unwrap();
+
+    // The browser still has the duplicates
+    let stats = store.import_session(&session, ImportMode::Update).await.unwrap();
+    assert!(stats.updates.is_empty(), "{:?}", stats.updates);
+    assert_eq!(store.get_stats().await.unwrap().total_tabs, 3);
+}
+
+// ─── Persistent full-text index ────────────────────────────────────────────
+
+#[cfg(feature = "full-text")]
+async fn open_store_with_index() -> (Store, tempfile::TempDir) {
+    let dir = tempfile::tempdir().unwrap();
+    let store = Store::open_with_index(&dir.path().join("test.db"), &dir.path().join("fulltext"))
+        .await
+        .unwrap();
+    (store, dir)
+}
+
+#[cfg(feature = "full-text")]
+#[tokio::test]
+async fn test_full_text_index_follows_writes() {
+    let (store, _dir) = open_store_with_index().await;
+    store.insert_session(&make_test_session()).await.unwrap();
+
+    let index = store.full_text_index().unwrap();
+    assert_eq!(index.num_docs().unwrap(), 2);
+    let results = index.search("cargo", 10).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].tab.id, "test-tab-2");
+    assert_eq!(results[0].group_id, "test-group-1");
+
+    // Re-inserting the same session must not duplicate documents
+    store.insert_session(&make_test_session()).await.unwrap();
+    assert_eq!(store.full_text_index().unwrap().num_docs().unwrap(), 2);
+
+    let mut group = store.get_all_groups().await.unwrap()[0].clone();
+    group.tabs.retain(|t| t.id == "test-tab-1");
+    store.replace_tabs_for_group(&group).await.unwrap();
+    let index = store.full_text_index().unwrap();
+    assert_eq!(index.num_docs().unwrap(), 1);
+    assert!(index.search("cargo", 10).unwrap().is_empty());
+
+    store.delete_group("test-group-1").await.unwrap();
+    assert_eq!(store.full_text_index().unwrap().num_docs().unwrap(), 0);
+}
+
+#[cfg(feature = "full-text")]
+#[tokio::test]
+async fn test_full_text_index_follows_updates() {
+    let (store, _dir) = open_store_with_index().await;
+    let session = make_test_session();
+    store.insert_session(&session).await.unwrap();
+
+    store.import_session(&rerecovered(&session), tablitz_store::ImportMode::Update).await.unwrap();
+    let index = store.full_text_index().unwrap();
+    assert_eq!(index.num_docs().unwrap(), 2);
+    assert_eq!(index.search("clippy", 10).unwrap().len(), 1);
+    assert!(index.search("programming", 10).unwrap().is_empty());
+
+    let titles = [("test-tab-2".to_string(), "Cargo".to_string())].into_iter().collect();
+    store.set_tab_titles(&titles).await.unwrap();
+    assert!(index.search("manager", 10).unwrap().is_empty());
+    assert_eq!(index.search("cargo", 10).unwrap().len(), 1);
+}
+
+#[cfg(feature = "full-text")]
+#[tokio::test]
+async fn test_full_text_index_follows_dedup_and_undo() {
+    use tablitz_search::{DedupEngine, DedupStrategy};
+
+    let (store, _dir) = open_store_with_index().await;
+    let mut session = make_test_session();
+    session.groups[0].tabs.push(make_tab("test-tab-3", "https://example.com/rust", "Rust again"));
+    store.insert_session(&session).await.unwrap();
+
+    let run = store.apply_dedup("exact-url", &DedupEngine::default().dedup(&session, DedupStrategy::ExactUrl)).await.unwrap().unwrap();
+    assert_eq!(store.full_text_index().unwrap().num_docs().unwrap(), 2);
+    store.undo_dedup(run.id).await.unwrap();
+    let index = store.full_text_index().unwrap();
+    assert_eq!(index.num_docs().unwrap(), 3);
+    assert_eq!(index.search("again", 10).unwrap().len(), 1);
+}
+
+#[cfg(feature = "full-text")]
+#[tokio::test]
+async fn test_full_text_index_persists_and_rebuilds() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let index_dir = dir.path().join("fulltext");
+
+    {
+        let store = Store::open_with_index(&db_path, &index_dir).await.unwrap();
+        store.insert_session(&make_test_session()).await.unwrap();
+    }
+
+    let mut store = Store::open_with_index(&db_path, &index_dir).await.unwrap();
+    assert_eq!(store.full_text_index().unwrap().search("rust", 10).unwrap().len(), 1);
+
+    // Simulate a corrupted index: wipe the directory contents and rebuild
+    std::fs::remove_dir_all(&index_dir).unwrap();
+    std::fs::create_dir_all(&index_dir).unwrap();
+    std::fs::write(index_dir.join("meta.json"), "not json").unwrap();
+    assert!(Store::open_with_index(&db_path, &index_dir).await.is_err());
+
+    let count = store.rebuild_full_text_index(&index_dir).await.unwrap();
+    assert_eq!(count, 2);
+    assert_eq!(store.full_text_index().unwrap().search("cargo", 10).unwrap().len(), 1);
+}
+
+#[cfg(feature = "full-text")]
+#[tokio::test]
+async fn test_search_index_filtered_fills_limit_past_unfiltered_hits() {
+    let (store, _dir) = open_store_with_index().await;
+    // 250 better-ranked hits outside the filter, then 120 inside it
+    let noise = (0..250)
+        .map(|i| make_tab(&format!("noise-{}", i), &format!("https://noise.test/{}", i), "Rust rust rust"))
+        .collect();
+    let mut wanted: Vec<Tab> = (0..120)
+        .map(|i| make_tab(&format!("wanted-{}", i), &format!("https://wanted.test/{}", i), &format!("Rust article number {} about other things", i)))
+        .collect();
+    wanted[0].tags = vec!["keep".to_string()];
+    let session = TabSession {
+        groups: vec![make_group("noise", Some("Noise"), noise), make_group("wanted", Some("Wanted"), wanted)],
+        ..make_test_session()
+    };
+    store.insert_session(&session).await.unwrap();
+    let filter = tablitz_search::Query::parse("label:wanted").unwrap();
+
+    let hits = store.search_index_filtered("rust", &filter, 5).await.unwrap();
+    assert_eq!(hits.len(), 5);
+    assert!(hits.iter().all(|h| h.tab.id.starts_with("wanted-")));
+
+    let hits = store.search_index_filtered("rust", &filter, 110).await.unwrap();
+    assert_eq!(hits.len(), 110);
+    assert!(hits.iter().all(|h| h.group_id == "wanted"));
+    let all = store.search_index_filtered("rust", &filter, 500).await.unwrap();
+    assert_eq!(all.len(), 120);
+    // Hits carry what only the store knows
+    assert_eq!(all.iter().find(|h| h.tab.id == "wanted-0").unwrap().tab.tags, ["keep"]);
+}
+
+#[cfg(feature = "full-text")]
+#[tokio::test]
+async fn test_search_index_skips_tabs_missing_from_store() {
+    let dir = tempfile::tempdir().unwrap();
+    let index_dir = dir.path().join("fulltext");
+    {
+        let store = Store::open_with_index(&dir.path().join("old.db"), &index_dir).await.unwrap();
+        store.insert_session(&make_test_session()).await.unwrap();
+    }
+
+    // A fresh database next to the old index: every hit is stale
+    let store = Store::open_with_index(&dir.path().join("new.db"), &index_dir).await.unwrap();
+    let everything = tablitz_search::Query::parse("").unwrap();
+    assert_eq!(store.full_text_index().unwrap().search("rust", 10).unwrap().len(), 1);
+    assert!(store.search_index_filtered("rust", &everything, 10).await.unwrap().is_empty());
+
+    store.insert_session(&make_test_session()).await.unwrap();
+    let hits = store.search_index_filtered("rust", &everything, 10).await.unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].tab.id, "test-tab-1");
+}
+
+#[cfg(feature = "full-text")]
+#[tokio::test]
+async fn test_failed_index_update_keeps_the_write_and_marks_index_stale() {
+    let dir = tempfile::tempdir().unwrap();
+    let index_dir = dir.path().join("fulltext");
+    let mut store = Store::open_with_index(&dir.path().join("test.db"), &index_dir).await.unwrap();
+    store.insert_session(&make_test_session()).await.unwrap();
+
+    std::fs::remove_dir_all(&index_dir).unwrap();
+    store.delete_group("test-group-1").await.unwrap();
+    assert!(store.get_all_groups().await.unwrap().is_empty());
+    assert!(store.full_text_error().is_some());
+    let everything = tablitz_search::Query::parse("").unwrap();
+    assert!(store.search_index_filtered("rust", &everything, 10).await.is_err());
+
+    assert_eq!(store.rebuild_full_text_index(&index_dir).await.unwrap(), 0);
+    assert!(store.full_text_error().is_none());
+    assert!(store.search_index_filtered("rust", &everything, 10).await.unwrap().is_empty());
+}
+
+#[cfg(feature = "full-text")]
+#[tokio::test]
+async fn test_stale_index_fails_to_open_until_rebuilt() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let index_dir = dir.path().join("fulltext");
+    {
+        let store = Store::open_with_index(&db_path, &index_dir).await.unwrap();
+        store.insert_session(&make_test_session()).await.unwrap();
+        store.full_text_index().unwrap().mark_stale().unwrap();
+    }
+    assert!(Store::open_with_index(&db_path, &index_dir).await.is_err());
+
+    let mut store = Store::open(&db_path).await.unwrap();
+    assert_eq!(store.rebuild_full_text_index(&index_dir).await.unwrap(), 2);
+    drop(store);
+    assert!(Store::open_with_index(&db_path, &index_dir).await.is_ok());
+}
+
 // ─── Unicode / long-URL handling ───────────────────────────────────────────
 
 /// Real OneTab data shape: unicode in tab titles (⟨ε|Δ⟩, em-dashes, etc.)
@@ -225,6 +1055,23 @@ async fn test_stats_top_domains() {
     assert_eq!(github_entry.unwrap().1, 3);
 }
 
+#[tokio::test]
+async fn test_stats_top_domains_fold_www() {
+    let (store, _dir) = open_store().await;
+    store.insert_session(&TabSession {
+        groups: vec![make_group("g1", None, vec![
+            make_tab("t1", "https://github.com/rust-lang/rust", "Rust"),
+            make_tab("t2", "http://www.github.com/serde-rs/serde", "Serde"),
+            make_tab("t3", "https://WWW.GitHub.com/tokio-rs/tokio", "Tokio"),
+            make_tab("t4", "https://www.youtube.com/watch?v=1", "YouTube"),
+        ])],
+        ..make_test_session()
+    }).await.unwrap();
+
+    let stats = store.get_stats().await.unwrap();
+    assert_eq!(stats.top_domains, vec![("github.com".to_string(), 3), ("youtube.com".to_string(), 1)]);
+}
+
 #[tokio::test]
 async fn test_stats_date_range() {
     let (store, _dir) = open_store().await;
@@ -257,6 +1104,60 @@ async fn test_search_no_results() {
     assert!(results.is_empty());
 }
 
+#[tokio::test]
+async fn test_search_fts_ranks_title_matches_first() {
+    let (store, _dir) = open_store().await;
+    store.insert_session(&TabSession {
+        groups: vec![
+            make_group("fts-1", Some("Reading list"), vec![
+                make_tab("fts-tab-1", "https://example.com/tokio-guide", "Async runtimes compared"),
+                make_tab("fts-tab-2", "https://tokio.rs/tokio/tutorial", "Tokio tutorial"),
+            ]),
+            make_group("fts-2", Some("tokio research"), vec![
+                make_tab("fts-tab-3", "https://example.org/blog", "Unrelated blog post"),
+            ]),
+        ],
+        ..make_test_session()
+    }).await.unwrap();
+
+    let hits = store.search_fts("tokio", 10).await.unwrap();
+    assert_eq!(hits.len(), 3);
+    assert_eq!(hits[0].tab.id, "fts-tab-2");
+    assert_eq!(hits[0].group_label.as_deref(), Some("Reading list"));
+    assert!(hits.windows(2).all(|w| w[0].rank <= w[1].rank));
+    assert!(hits.iter().any(|h| h.snippet.contains("[tokio]") || h.snippet.contains("[Tokio]")));
+
+    // Column filters and boolean operators use FTS5 syntax
+    let label_hits = store.search_fts("label:research", 10).await.unwrap();
+    assert_eq!(label_hits.len(), 1);
+    assert_eq!(label_hits[0].tab.id, "fts-tab-3");
+    assert_eq!(store.search_fts("tokio AND tutorial", 10).await.unwrap().len(), 1);
+    assert_eq!(store.search_fts("tokio", 1).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_search_fts_follows_replace_and_delete() {
+    let (store, _dir) = open_store().await;
+    store.insert_session(&make_test_session()).await.unwrap();
+    assert_eq!(store.search_fts("cargo", 10).await.unwrap().len(), 1);
+
+    let mut group = store.get_all_groups().await.unwrap()[0].clone();
+    group.tabs.retain(|t| t.id == "test-tab-1");
+    store.replace_tabs_for_group(&group).await.unwrap();
+    assert!(store.search_fts("cargo", 10).await.unwrap().is_empty());
+    assert_eq!(store.search_fts("rust", 10).await.unwrap().len(), 1);
+
+    store.delete_group("test-group-1").await.unwrap();
+    assert!(store.search_fts("rust", 10).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_search_fts_invalid_query_is_error() {
+    let (store, _dir) = open_store().await;
+    store.insert_session(&make_test_session()).await.unwrap();
+    assert!(store.search_fts("\"unterminated", 10).await.is_err());
+}
+
 // ─── get_session round-trip ────────────────────────────────────────────────
 
 #[tokio::test]
@@ -32,7 +32,7 @@ impl SyncManager {
     pub async fn snapshot(&self, store: &tablitz_store::Store) -> Result<String> {
         let session = store.get_session().await
             .context("failed to read store for snapshot")?;
-        let json = serde_json::to_string_pretty(&session)
+        let json = session.to_json_pretty()
             .context("failed to serialize session")?;
 
         let snapshot_path = self.snapshot_path();
@@ -73,7 +73,7 @@ impl SyncManager {
         let json = std::fs::read_to_string(&snapshot_path)
             .with_context(|| format!("failed to read snapshot from {}", snapshot_path.display()))?;
 
-        let session: tablitz_core::TabSession = serde_json::from_str(&json)
+        let session = tablitz_core::TabSession::from_json(&json)
             .context("failed to deserialize snapshot")?;
 
         let stats = store.insert_session(&session).await
@@ -112,7 +112,7 @@ impl SyncManager {
             &format!("{}:{}", commit_hash, self.snapshot_filename),
         ]).context("git show failed")?;
 
-        let session: tablitz_core::TabSession = serde_json::from_str(&output)
+        let session = tablitz_core::TabSession::from_json(&output)
             .context("failed to deserialize snapshot from commit")?;
 
         let stats = store.insert_session(&session).await
@@ -13,6 +13,8 @@ fn make_tab(id: &str, url: &str, title: &str) -> Tab {
         title: title.to_string(),
         favicon_url: None,
         added_at: Utc::now(),
+        tags: Vec::new(),
+        activity: Default::default(),
     }
 }
 
@@ -29,6 +31,7 @@ fn make_session(extra_suffix: &str) -> TabSession {
             pinned: false,
             locked: false,
             starred: false,
+            tags: Vec::new(),
             tabs: vec![
                 make_tab(
                     &format!("tab-1-{}", extra_suffix),
@@ -197,6 +200,34 @@ async fn test_restore_imports_data() {
     assert_eq!(groups[0].tabs.len(), 2);
 }
 
+#[tokio::test]
+async fn test_restore_keeps_tags() {
+    let source_store_dir = tempfile::tempdir().unwrap();
+    let target_store_dir = tempfile::tempdir().unwrap();
+    let repo_dir = tempfile::tempdir().unwrap();
+
+    let mut session = make_session("tags");
+    session.groups[0].tags = vec!["research".to_string()];
+    session.groups[0].tabs[0].tags = vec!["rust".to_string(), "books".to_string()];
+    let source = Store::open(&source_store_dir.path().join("source.db")).await.unwrap();
+    source.insert_session(&session).await.unwrap();
+
+    let sync = SyncManager::new(repo_dir.path());
+    sync.init_repo().unwrap();
+    git_config(repo_dir.path());
+    sync.snapshot(&source).await.unwrap();
+    let json = std::fs::read_to_string(sync.snapshot_path()).unwrap();
+    assert!(json.contains("\"research\""));
+
+    let target = Store::open(&target_store_dir.path().join("target.db")).await.unwrap();
+    sync.restore(&target).await.unwrap();
+
+    let groups = target.get_all_groups().await.unwrap();
+    assert_eq!(groups[0].tags, vec!["research"]);
+    assert_eq!(groups[0].tabs[0].tags, vec!["books", "rust"]);
+    assert!(groups[0].tabs[1].tags.is_empty());
+}
+
 #[tokio::test]
 async fn test_restore_is_idempotent() {
     let store_dir = tempfile::tempdir().unwrap();